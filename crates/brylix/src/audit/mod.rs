@@ -0,0 +1,550 @@
+//! Persisted audit log.
+//!
+//! An [`AuditEvent`] records who did what to which entity: the actor,
+//! tenant, action, target type and ID, a before/after JSON diff, and the
+//! client IP and user agent. Events are written to an [`AuditSink`]:
+//!
+//! - [`SeaOrmAuditSink`] - the `audit_log` table (migration included)
+//! - [`TracingAuditSink`] - structured `tracing` lines
+//! - [`MemoryAuditSink`] - in-process, for tests
+//!
+//! Register a sink once at startup with [`init_audit_sink`]. The framework
+//! then records admin override redemptions and auth events (logins, magic
+//! links, OAuth logins, token use) automatically; application code calls
+//! [`emit`] for its own events.
+//!
+//! # Usage
+//!
+//! ```rust,ignore
+//! use brylix::audit::{emit, init_audit_sink, AuditEvent, SeaOrmAuditSink};
+//!
+//! // At startup
+//! init_audit_sink(Arc::new(SeaOrmAuditSink::new(db.clone())));
+//!
+//! // In a resolver
+//! let data = ctx.data_unchecked::<ContextData>();
+//! emit(
+//!     AuditEvent::from_context(data, "invoice.update")
+//!         .with_target("invoice", invoice.id)
+//!         .with_changes(&serde_json::to_value(&before)?, &serde_json::to_value(&after)?),
+//! )
+//! .await;
+//! ```
+
+use std::sync::{Arc, Mutex, OnceLock};
+
+use chrono::{DateTime, Utc};
+use lambda_http::Request;
+use sea_orm::{DatabaseConnection, EntityTrait, Set};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::auth::throttle::client_ip;
+use crate::errors::{DomainError, DomainResult};
+use crate::graphql::ContextData;
+
+/// Global audit sink
+static SINK: OnceLock<Arc<dyn AuditSink>> = OnceLock::new();
+
+/// Action names used by events the framework records itself.
+pub mod actions {
+    /// A login attempt failed (recorded by `LoginThrottle::record_failure`)
+    pub const LOGIN_FAILED: &str = "auth.login_failed";
+    /// A login succeeded (recorded by `LoginThrottle::record_success`)
+    pub const LOGIN_SUCCEEDED: &str = "auth.login_succeeded";
+    /// A magic link was exchanged for a session
+    pub const MAGIC_LINK_LOGIN: &str = "auth.magic_link_login";
+    /// An OAuth login completed
+    pub const OAUTH_LOGIN: &str = "auth.oauth_login";
+    /// A password reset or email verification token was used
+    pub const TOKEN_USED: &str = "auth.token_used";
+    /// An admin override token was redeemed
+    pub const ADMIN_OVERRIDE_USED: &str = "admin_override.used";
+    /// An admin started impersonating a user
+    pub const IMPERSONATION_STARTED: &str = "impersonation.started";
+    /// An admin stopped impersonating a user
+    pub const IMPERSONATION_ENDED: &str = "impersonation.ended";
+}
+
+/// A single audit log entry.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEvent {
+    /// When the event happened
+    pub occurred_at: DateTime<Utc>,
+    /// User who performed the action
+    pub actor: Option<String>,
+    /// Tenant the action happened in
+    pub tenant: Option<String>,
+    /// Action name (e.g. `invoice.update`)
+    pub action: String,
+    /// Target entity type (e.g. `invoice`)
+    pub target_type: Option<String>,
+    /// Target entity ID
+    pub target_id: Option<String>,
+    /// Changed fields as `{ "field": { "from": .., "to": .. } }`
+    pub changes: Option<Value>,
+    /// Extra context (e.g. the admin who authorized an override)
+    pub metadata: Option<Value>,
+    /// Client IP address
+    pub ip: Option<String>,
+    /// Client user agent
+    pub user_agent: Option<String>,
+}
+
+impl AuditEvent {
+    /// Create an event for `action`, timestamped now.
+    pub fn new(action: impl Into<String>) -> Self {
+        Self {
+            occurred_at: Utc::now(),
+            actor: None,
+            tenant: None,
+            action: action.into(),
+            target_type: None,
+            target_id: None,
+            changes: None,
+            metadata: None,
+            ip: None,
+            user_agent: None,
+        }
+    }
+
+    /// Create an event with the actor and tenant of the current request.
+    ///
+    /// While impersonating, the impersonating admin is added to the metadata
+    /// as `impersonator`.
+    pub fn from_context(data: &ContextData, action: impl Into<String>) -> Self {
+        let mut event = Self::new(action);
+        event.actor = data.user.clone();
+        event.tenant = data.tenant_name().map(String::from);
+        if let Some(admin) = data.impersonator() {
+            event.metadata = Some(serde_json::json!({ "impersonator": admin }));
+        }
+        event
+    }
+
+    /// Set the actor.
+    pub fn with_actor(mut self, actor: impl ToString) -> Self {
+        self.actor = Some(actor.to_string());
+        self
+    }
+
+    /// Set the tenant.
+    pub fn with_tenant(mut self, tenant: Option<&str>) -> Self {
+        self.tenant = tenant.map(String::from);
+        self
+    }
+
+    /// Set the target entity.
+    pub fn with_target(mut self, target_type: impl Into<String>, target_id: impl ToString) -> Self {
+        self.target_type = Some(target_type.into());
+        self.target_id = Some(target_id.to_string());
+        self
+    }
+
+    /// Record the fields that differ between two snapshots.
+    pub fn with_changes(mut self, before: &Value, after: &Value) -> Self {
+        let diff = json_diff(before, after);
+        self.changes = (!diff.as_object().is_some_and(Map::is_empty)).then_some(diff);
+        self
+    }
+
+    /// Attach extra context, merging object keys into existing metadata.
+    pub fn with_metadata(mut self, metadata: Value) -> Self {
+        self.metadata = match (self.metadata.take(), metadata) {
+            (Some(Value::Object(mut existing)), Value::Object(extra)) => {
+                existing.extend(extra);
+                Some(Value::Object(existing))
+            }
+            (_, metadata) => Some(metadata),
+        };
+        self
+    }
+
+    /// Set the client IP.
+    pub fn with_ip(mut self, ip: Option<&str>) -> Self {
+        self.ip = ip.map(String::from);
+        self
+    }
+
+    /// Set the client user agent.
+    pub fn with_user_agent(mut self, user_agent: Option<&str>) -> Self {
+        self.user_agent = user_agent.map(String::from);
+        self
+    }
+
+    /// Set the client IP and user agent from the HTTP request.
+    pub fn with_request(mut self, request: &Request) -> Self {
+        self.ip = client_ip(request);
+        self.user_agent = request
+            .headers()
+            .get("User-Agent")
+            .and_then(|v| v.to_str().ok())
+            .map(String::from);
+        self
+    }
+}
+
+/// Compute the difference between two JSON snapshots.
+///
+/// For objects, returns `{ "field": { "from": old, "to": new } }` for every
+/// top-level key whose value changed (missing keys count as `null`). Other
+/// values produce a single `{ "from": .., "to": .. }` entry, or an empty
+/// object when equal.
+pub fn json_diff(before: &Value, after: &Value) -> Value {
+    fn change(from: &Value, to: &Value) -> Value {
+        serde_json::json!({ "from": from, "to": to })
+    }
+
+    match (before, after) {
+        (Value::Object(old), Value::Object(new)) => {
+            let mut diff = Map::new();
+            for (key, from) in old {
+                let to = new.get(key).unwrap_or(&Value::Null);
+                if from != to {
+                    diff.insert(key.clone(), change(from, to));
+                }
+            }
+            for (key, to) in new {
+                if !old.contains_key(key) && !to.is_null() {
+                    diff.insert(key.clone(), change(&Value::Null, to));
+                }
+            }
+            Value::Object(diff)
+        }
+        _ if before == after => Value::Object(Map::new()),
+        _ => change(before, after),
+    }
+}
+
+/// Destination for audit events.
+#[async_trait::async_trait]
+pub trait AuditSink: Send + Sync {
+    /// Persist one event.
+    async fn record(&self, event: &AuditEvent) -> DomainResult<()>;
+}
+
+/// Initialize the global audit sink.
+///
+/// Call once at startup. Later calls are ignored.
+pub fn init_audit_sink(sink: Arc<dyn AuditSink>) {
+    if SINK.set(sink).is_err() {
+        tracing::debug!("Audit sink already initialized");
+    }
+}
+
+/// Get the global audit sink, if initialized.
+pub fn audit_sink() -> Option<&'static Arc<dyn AuditSink>> {
+    SINK.get()
+}
+
+/// Record an event to the global sink.
+///
+/// Does nothing if no sink is initialized. Sink failures are logged rather
+/// than returned so auditing never breaks the request; call
+/// [`AuditSink::record`] directly when the write must succeed.
+pub async fn emit(event: AuditEvent) {
+    if let Some(sink) = audit_sink() {
+        if let Err(e) = sink.record(&event).await {
+            tracing::error!(error = %e, action = %event.action, "Failed to record audit event");
+        }
+    }
+}
+
+/// Record an event to the global sink, failing if it cannot be stored.
+///
+/// Use for events that must not be lost, such as the start of an
+/// impersonation session.
+///
+/// # Errors
+///
+/// Returns `Internal` if no sink is initialized, or the sink's error
+pub async fn record(event: AuditEvent) -> DomainResult<()> {
+    let sink =
+        audit_sink().ok_or_else(|| DomainError::Internal("Audit sink not initialized".into()))?;
+    sink.record(&event).await
+}
+
+/// Audit sink that writes structured `tracing` events.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TracingAuditSink;
+
+#[async_trait::async_trait]
+impl AuditSink for TracingAuditSink {
+    async fn record(&self, event: &AuditEvent) -> DomainResult<()> {
+        tracing::info!(
+            target: "audit",
+            action = %event.action,
+            actor = ?event.actor,
+            tenant = ?event.tenant,
+            target_type = ?event.target_type,
+            target_id = ?event.target_id,
+            changes = ?event.changes,
+            metadata = ?event.metadata,
+            ip = ?event.ip,
+            user_agent = ?event.user_agent,
+            "Audit event"
+        );
+        Ok(())
+    }
+}
+
+/// In-process audit sink, mainly for tests.
+#[derive(Debug, Default)]
+pub struct MemoryAuditSink {
+    events: Mutex<Vec<AuditEvent>>,
+}
+
+impl MemoryAuditSink {
+    /// Create an empty sink.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get a copy of the recorded events.
+    pub fn events(&self) -> Vec<AuditEvent> {
+        self.events.lock().map(|e| e.clone()).unwrap_or_default()
+    }
+}
+
+#[async_trait::async_trait]
+impl AuditSink for MemoryAuditSink {
+    async fn record(&self, event: &AuditEvent) -> DomainResult<()> {
+        self.events
+            .lock()
+            .map_err(|_| DomainError::Internal("Audit sink lock poisoned".into()))?
+            .push(event.clone());
+        Ok(())
+    }
+}
+
+/// SeaORM-backed audit sink using the `audit_log` table.
+///
+/// Add [`migration::Migration`] to your migrator to create the table.
+#[derive(Debug, Clone)]
+pub struct SeaOrmAuditSink {
+    db: DatabaseConnection,
+}
+
+impl SeaOrmAuditSink {
+    /// Create a sink using the given connection.
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait::async_trait]
+impl AuditSink for SeaOrmAuditSink {
+    async fn record(&self, event: &AuditEvent) -> DomainResult<()> {
+        let model = entity::ActiveModel {
+            occurred_at: Set(event.occurred_at),
+            actor: Set(event.actor.clone()),
+            tenant: Set(event.tenant.clone()),
+            action: Set(event.action.clone()),
+            target_type: Set(event.target_type.clone()),
+            target_id: Set(event.target_id.clone()),
+            changes: Set(event.changes.clone()),
+            metadata: Set(event.metadata.clone()),
+            ip: Set(event.ip.clone()),
+            user_agent: Set(event.user_agent.clone()),
+            ..Default::default()
+        };
+        entity::Entity::insert(model).exec(&self.db).await?;
+        Ok(())
+    }
+}
+
+/// SeaORM entity for the `audit_log` table.
+pub mod entity {
+    use sea_orm::entity::prelude::*;
+
+    /// A stored audit event.
+    #[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+    #[sea_orm(table_name = "audit_log")]
+    pub struct Model {
+        /// Row ID
+        #[sea_orm(primary_key)]
+        pub id: i64,
+        /// When the event happened
+        pub occurred_at: DateTimeUtc,
+        /// User who performed the action
+        pub actor: Option<String>,
+        /// Tenant the action happened in
+        pub tenant: Option<String>,
+        /// Action name
+        pub action: String,
+        /// Target entity type
+        pub target_type: Option<String>,
+        /// Target entity ID
+        pub target_id: Option<String>,
+        /// Changed fields
+        pub changes: Option<Json>,
+        /// Extra context
+        pub metadata: Option<Json>,
+        /// Client IP address
+        pub ip: Option<String>,
+        /// Client user agent
+        pub user_agent: Option<String>,
+    }
+
+    /// No relations.
+    #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+    pub enum Relation {}
+
+    impl ActiveModelBehavior for ActiveModel {}
+}
+
+/// Migration creating the `audit_log` table.
+pub mod migration {
+    use sea_orm_migration::prelude::*;
+
+    /// Creates the `audit_log` table.
+    pub struct Migration;
+
+    impl MigrationName for Migration {
+        fn name(&self) -> &str {
+            "m20261018_000006_create_audit_log_table"
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl MigrationTrait for Migration {
+        async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+            manager
+                .create_table(
+                    Table::create()
+                        .table(AuditLog::Table)
+                        .if_not_exists()
+                        .col(
+                            ColumnDef::new(AuditLog::Id)
+                                .big_integer()
+                                .not_null()
+                                .auto_increment()
+                                .primary_key(),
+                        )
+                        .col(ColumnDef::new(AuditLog::OccurredAt).timestamp().not_null())
+                        .col(ColumnDef::new(AuditLog::Actor).string_len(255).null())
+                        .col(ColumnDef::new(AuditLog::Tenant).string_len(255).null())
+                        .col(ColumnDef::new(AuditLog::Action).string_len(255).not_null())
+                        .col(ColumnDef::new(AuditLog::TargetType).string_len(255).null())
+                        .col(ColumnDef::new(AuditLog::TargetId).string_len(255).null())
+                        .col(ColumnDef::new(AuditLog::Changes).json().null())
+                        .col(ColumnDef::new(AuditLog::Metadata).json().null())
+                        .col(ColumnDef::new(AuditLog::Ip).string_len(64).null())
+                        .col(ColumnDef::new(AuditLog::UserAgent).string_len(512).null())
+                        .to_owned(),
+                )
+                .await?;
+
+            manager
+                .create_index(
+                    Index::create()
+                        .name("idx_audit_log_target")
+                        .table(AuditLog::Table)
+                        .col(AuditLog::TargetType)
+                        .col(AuditLog::TargetId)
+                        .to_owned(),
+                )
+                .await?;
+
+            manager
+                .create_index(
+                    Index::create()
+                        .name("idx_audit_log_actor")
+                        .table(AuditLog::Table)
+                        .col(AuditLog::Actor)
+                        .col(AuditLog::OccurredAt)
+                        .to_owned(),
+                )
+                .await
+        }
+
+        async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+            manager
+                .drop_table(Table::drop().table(AuditLog::Table).to_owned())
+                .await
+        }
+    }
+
+    #[derive(Iden)]
+    enum AuditLog {
+        Table,
+        Id,
+        OccurredAt,
+        Actor,
+        Tenant,
+        Action,
+        TargetType,
+        TargetId,
+        Changes,
+        Metadata,
+        Ip,
+        UserAgent,
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// Global memory sink shared by tests that check automatic recording.
+    pub(crate) fn test_sink() -> &'static MemoryAuditSink {
+        static TEST_SINK: OnceLock<Arc<MemoryAuditSink>> = OnceLock::new();
+        let sink = TEST_SINK.get_or_init(|| Arc::new(MemoryAuditSink::new()));
+        init_audit_sink(sink.clone());
+        sink
+    }
+
+    #[test]
+    fn test_json_diff_objects() {
+        let before = json!({ "status": "draft", "total": 10, "note": "a" });
+        let after = json!({ "status": "sent", "total": 10, "paid": true });
+
+        assert_eq!(
+            json_diff(&before, &after),
+            json!({
+                "status": { "from": "draft", "to": "sent" },
+                "note": { "from": "a", "to": null },
+                "paid": { "from": null, "to": true },
+            })
+        );
+        assert_eq!(json_diff(&before, &before), json!({}));
+        assert_eq!(
+            json_diff(&json!(1), &json!(2)),
+            json!({ "from": 1, "to": 2 })
+        );
+    }
+
+    #[test]
+    fn test_event_builder() {
+        let request = http::Request::builder()
+            .header("X-Forwarded-For", "10.0.0.1, 10.0.0.2")
+            .header("User-Agent", "pos-terminal/1.0")
+            .body(lambda_http::Body::Empty)
+            .unwrap();
+
+        let event = AuditEvent::new("invoice.update")
+            .with_actor(5)
+            .with_tenant(Some("acme"))
+            .with_target("invoice", 42)
+            .with_changes(&json!({ "total": 1 }), &json!({ "total": 1 }))
+            .with_request(&request);
+
+        assert_eq!(event.actor.as_deref(), Some("5"));
+        assert_eq!(event.target_id.as_deref(), Some("42"));
+        assert_eq!(event.changes, None);
+        assert_eq!(event.ip.as_deref(), Some("10.0.0.2"));
+        assert_eq!(event.user_agent.as_deref(), Some("pos-terminal/1.0"));
+    }
+
+    #[tokio::test]
+    async fn test_emit_records_to_global_sink() {
+        let sink = test_sink();
+        emit(AuditEvent::new("test.emit").with_target("thing", "emit-1")).await;
+
+        assert!(sink
+            .events()
+            .iter()
+            .any(|e| e.action == "test.emit" && e.target_id.as_deref() == Some("emit-1")));
+    }
+}
@@ -3,6 +3,13 @@
 //! Allows a logged-in user (e.g. cashier) to perform admin-only actions
 //! when an admin "taps in" with a short-lived override token.
 //!
+//! Override tokens are single-use: each carries a `jti` nonce that is
+//! recorded in an [`OverrideNonceStore`] when the token is redeemed, so a
+//! captured header cannot be replayed. A token can also be bound to the
+//! actor (the logged-in user it was issued for) and scoped to one action.
+//! Scoped tokens are only accepted by [`require_admin_override_for`] with the
+//! matching action; unscoped tokens act as general admin elevation.
+//!
 //! # Usage
 //!
 //! ```rust,ignore
@@ -12,19 +19,27 @@
 //!
 //! // Issue a short-lived override token after verifying admin credentials
 //! let config = AdminOverrideConfig::new("admin-secret".to_string());
-//! let token = issue_admin_override_token(&config, 42, "John Admin", Some("delete_invoice"))?;
+//! let token = issue_admin_override_token(&config, 42, "John Admin", Some("delete_invoice"), Some(cashier_id))?;
 //!
 //! // Frontend sends: X-Admin-Override: <token>
-//! // Guards like require_admin() will accept this automatically
+//! // The handler redeems it once with admin_override_middleware(), then:
+//! let (cashier_id, admin_override) = require_admin_override_for(ctx, "delete_invoice")?;
 //! ```
 
+use std::collections::HashMap;
+use std::sync::Mutex;
+
 use async_graphql::{Context, Error as GqlError};
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
 use lambda_http::Request;
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set, SqlErr};
 use serde::{Deserialize, Serialize};
 
-use crate::errors::{gql_error, gql_unauthorized};
+use super::password::random_alphanumeric;
+use super::sessions::SessionInfo;
+use crate::audit::{actions, AuditEvent};
+use crate::errors::{gql_error, gql_unauthorized, DomainError, DomainResult};
 use crate::graphql::ContextData;
 
 /// HTTP header name for the admin override token.
@@ -36,6 +51,9 @@ const DEFAULT_EXPIRY_SECS: i64 = 60;
 /// Token type marker to distinguish override tokens from regular admin JWTs.
 const ADMIN_OVERRIDE_TOKEN_TYPE: &str = "admin_override";
 
+/// Length of the random `jti` nonce.
+const JTI_LEN: usize = 32;
+
 /// Configuration for admin override tokens.
 #[derive(Debug, Clone)]
 pub struct AdminOverrideConfig {
@@ -71,6 +89,11 @@ pub struct AdminOverrideClaims {
     /// Optional action being authorized (e.g. "delete_invoice").
     #[serde(default)]
     pub action: Option<String>,
+    /// Optional user ID the override was issued for (the actor).
+    #[serde(default)]
+    pub actor: Option<String>,
+    /// Unique token ID, consumed on first use.
+    pub jti: String,
     /// Expiration timestamp (Unix epoch seconds).
     pub exp: usize,
     /// Token type marker — must be "admin_override".
@@ -88,6 +111,31 @@ pub struct AdminOverride {
     pub admin_name: String,
     /// Optional action that was authorized.
     pub action: Option<String>,
+    /// Optional user ID the override is bound to.
+    pub actor_user_id: Option<i64>,
+    /// Unique token ID.
+    pub jti: String,
+    /// Expiration timestamp (Unix epoch seconds).
+    pub expires_at: i64,
+}
+
+impl AdminOverride {
+    /// Check whether the override may be used for `action`.
+    ///
+    /// Unscoped overrides allow any action.
+    pub fn allows_action(&self, action: &str) -> bool {
+        self.action.as_deref().is_none_or(|scoped| scoped == action)
+    }
+
+    /// Check whether the override may be used by `user_id`.
+    ///
+    /// Overrides without an actor binding may be used by anyone.
+    pub fn allows_actor(&self, user_id: Option<&str>) -> bool {
+        match self.actor_user_id {
+            Some(actor) => user_id.and_then(|u| u.parse::<i64>().ok()) == Some(actor),
+            None => true,
+        }
+    }
 }
 
 /// Issue a short-lived admin override token.
@@ -101,6 +149,7 @@ pub struct AdminOverride {
 /// * `admin_id` - The admin's user ID
 /// * `admin_name` - The admin's display name (for audit)
 /// * `action` - Optional action being authorized
+/// * `actor_user_id` - Optional user the override is for (e.g. the cashier)
 ///
 /// # Errors
 ///
@@ -110,6 +159,7 @@ pub fn issue_admin_override_token(
     admin_id: i64,
     admin_name: &str,
     action: Option<&str>,
+    actor_user_id: Option<i64>,
 ) -> Result<String, String> {
     let exp = Utc::now().timestamp() + config.expiry_secs;
 
@@ -117,6 +167,8 @@ pub fn issue_admin_override_token(
         sub: admin_id.to_string(),
         admin_name: admin_name.to_string(),
         action: action.map(String::from),
+        actor: actor_user_id.map(|id| id.to_string()),
+        jti: random_alphanumeric(JTI_LEN),
         exp: exp as usize,
         token_type: ADMIN_OVERRIDE_TOKEN_TYPE.to_string(),
     };
@@ -131,6 +183,10 @@ pub fn issue_admin_override_token(
 
 /// Validate an admin override token and extract the override info.
 ///
+/// This only checks the signature, expiry and token type; use
+/// [`redeem_admin_override_token`] to also enforce single use and the actor
+/// binding.
+///
 /// # Arguments
 ///
 /// * `token` - The raw JWT string from the `X-Admin-Override` header
@@ -161,14 +217,234 @@ pub fn validate_admin_override_token(
         .parse()
         .map_err(|_| "Invalid admin ID in override token".to_string())?;
 
+    let actor_user_id = claims
+        .actor
+        .map(|actor| actor.parse::<i64>())
+        .transpose()
+        .map_err(|_| "Invalid actor ID in override token".to_string())?;
+
     Ok(AdminOverride {
         admin_id,
         admin_sub: claims.sub,
         admin_name: claims.admin_name,
         action: claims.action,
+        actor_user_id,
+        jti: claims.jti,
+        expires_at: claims.exp as i64,
+    })
+}
+
+/// Validate an admin override token and consume it.
+///
+/// # Arguments
+///
+/// * `token` - The raw JWT string from the `X-Admin-Override` header
+/// * `config` - Admin override configuration
+/// * `store` - Store recording consumed token IDs
+/// * `user_id` - The authenticated user presenting the token
+/// * `tenant` - The user's tenant (multi-tenant mode)
+/// * `client` - Client IP and user agent, recorded with the audit event
+///
+/// # Errors
+///
+/// Returns an error if the token is invalid, was issued for another user,
+/// or has already been used
+pub async fn redeem_admin_override_token(
+    token: &str,
+    config: &AdminOverrideConfig,
+    store: &dyn OverrideNonceStore,
+    user_id: Option<&str>,
+    tenant: Option<&str>,
+    client: &SessionInfo,
+) -> Result<AdminOverride, String> {
+    let admin_override = validate_admin_override_token(token, config)?;
+
+    if !admin_override.allows_actor(user_id) {
+        return Err("Admin override was issued for another user".to_string());
+    }
+
+    // Keep the nonce until the token can no longer pass validation
+    let leeway = Validation::default().leeway as i64;
+    let keep_until =
+        DateTime::from_timestamp(admin_override.expires_at + leeway, 0).unwrap_or_else(Utc::now);
+    match store.consume(&admin_override.jti, keep_until).await {
+        Ok(true) => {
+            let mut event = AuditEvent::new(actions::ADMIN_OVERRIDE_USED)
+                .with_tenant(tenant)
+                .with_ip(client.ip.as_deref())
+                .with_user_agent(client.user_agent.as_deref())
+                .with_metadata(override_metadata(&admin_override));
+            event.actor = user_id.map(String::from);
+            crate::audit::emit(event).await;
+            Ok(admin_override)
+        }
+        Ok(false) => Err("Admin override token has already been used".to_string()),
+        Err(e) => Err(format!("Failed to record admin override use: {}", e)),
+    }
+}
+
+fn override_metadata(admin_override: &AdminOverride) -> serde_json::Value {
+    serde_json::json!({
+        "admin_id": admin_override.admin_id,
+        "admin_name": admin_override.admin_name,
+        "override_action": admin_override.action,
+        "jti": admin_override.jti,
     })
 }
 
+/// Storage for consumed override token IDs.
+#[async_trait::async_trait]
+pub trait OverrideNonceStore: Send + Sync {
+    /// Record `jti` as used until `expires_at`.
+    ///
+    /// Returns `false` if it had already been used.
+    async fn consume(&self, jti: &str, expires_at: DateTime<Utc>) -> DomainResult<bool>;
+}
+
+/// In-memory nonce store.
+///
+/// Only protects a single process; use [`SeaOrmOverrideNonceStore`] when
+/// requests are spread across Lambda instances.
+#[derive(Debug, Default)]
+pub struct MemoryOverrideNonceStore {
+    used: Mutex<HashMap<String, DateTime<Utc>>>,
+}
+
+impl MemoryOverrideNonceStore {
+    /// Create an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait::async_trait]
+impl OverrideNonceStore for MemoryOverrideNonceStore {
+    async fn consume(&self, jti: &str, expires_at: DateTime<Utc>) -> DomainResult<bool> {
+        let mut used = self
+            .used
+            .lock()
+            .map_err(|_| DomainError::Internal("Nonce store lock poisoned".into()))?;
+        let now = Utc::now();
+        used.retain(|_, until| *until > now);
+        if used.contains_key(jti) {
+            return Ok(false);
+        }
+        used.insert(jti.to_string(), expires_at);
+        Ok(true)
+    }
+}
+
+/// SeaORM-backed nonce store using the `admin_override_nonces` table.
+///
+/// Add [`migration::Migration`] to your migrator to create the table.
+#[derive(Debug, Clone)]
+pub struct SeaOrmOverrideNonceStore {
+    db: DatabaseConnection,
+}
+
+impl SeaOrmOverrideNonceStore {
+    /// Create a store using the given connection.
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait::async_trait]
+impl OverrideNonceStore for SeaOrmOverrideNonceStore {
+    async fn consume(&self, jti: &str, expires_at: DateTime<Utc>) -> DomainResult<bool> {
+        entity::Entity::delete_many()
+            .filter(entity::Column::ExpiresAt.lt(Utc::now()))
+            .exec(&self.db)
+            .await?;
+
+        let model = entity::ActiveModel {
+            jti: Set(jti.to_string()),
+            expires_at: Set(expires_at),
+        };
+        match entity::Entity::insert(model).exec(&self.db).await {
+            Ok(_) => Ok(true),
+            Err(e) if matches!(e.sql_err(), Some(SqlErr::UniqueConstraintViolation(_))) => {
+                Ok(false)
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+/// SeaORM entity for the `admin_override_nonces` table.
+pub mod entity {
+    use sea_orm::entity::prelude::*;
+
+    /// A consumed override token ID.
+    #[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+    #[sea_orm(table_name = "admin_override_nonces")]
+    pub struct Model {
+        /// Token ID (`jti` claim)
+        #[sea_orm(primary_key, auto_increment = false)]
+        pub jti: String,
+        /// When the row can be pruned
+        pub expires_at: DateTimeUtc,
+    }
+
+    /// No relations.
+    #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+    pub enum Relation {}
+
+    impl ActiveModelBehavior for ActiveModel {}
+}
+
+/// Migration creating the `admin_override_nonces` table.
+pub mod migration {
+    use sea_orm_migration::prelude::*;
+
+    /// Creates the `admin_override_nonces` table.
+    pub struct Migration;
+
+    impl MigrationName for Migration {
+        fn name(&self) -> &str {
+            "m20261018_000005_create_admin_override_nonces_table"
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl MigrationTrait for Migration {
+        async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+            manager
+                .create_table(
+                    Table::create()
+                        .table(AdminOverrideNonces::Table)
+                        .if_not_exists()
+                        .col(
+                            ColumnDef::new(AdminOverrideNonces::Jti)
+                                .string_len(64)
+                                .not_null()
+                                .primary_key(),
+                        )
+                        .col(
+                            ColumnDef::new(AdminOverrideNonces::ExpiresAt)
+                                .timestamp()
+                                .not_null(),
+                        )
+                        .to_owned(),
+                )
+                .await
+        }
+
+        async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+            manager
+                .drop_table(Table::drop().table(AdminOverrideNonces::Table).to_owned())
+                .await
+        }
+    }
+
+    #[derive(Iden)]
+    enum AdminOverrideNonces {
+        Table,
+        Jti,
+        ExpiresAt,
+    }
+}
+
 /// Extract the admin override token from the request headers.
 ///
 /// Reads the `X-Admin-Override` header value.
@@ -186,6 +462,24 @@ pub fn get_admin_override<'a>(ctx: &'a Context<'a>) -> Option<&'a AdminOverride>
     data.admin_override.as_ref()
 }
 
+/// Get the override from the context if the current user may use it for
+/// `action`.
+///
+/// With `action: None` only unscoped overrides qualify, so a token issued for
+/// one action never grants general admin access.
+pub(crate) fn usable_admin_override<'a>(
+    data: &'a ContextData,
+    action: Option<&str>,
+) -> Option<&'a AdminOverride> {
+    data.admin_override.as_ref().filter(|ao| {
+        ao.allows_actor(data.user.as_deref())
+            && match action {
+                Some(action) => ao.allows_action(action),
+                None => ao.action.is_none(),
+            }
+    })
+}
+
 /// Require both an authenticated user AND an admin override.
 ///
 /// Returns the user's ID and the admin override info.
@@ -195,9 +489,40 @@ pub fn get_admin_override<'a>(ctx: &'a Context<'a>) -> Option<&'a AdminOverride>
 /// # Errors
 ///
 /// Returns UNAUTHORIZED if no user is authenticated,
-/// or FORBIDDEN if no admin override is present
+/// or FORBIDDEN if no unscoped admin override for this user is present
 pub fn require_auth_with_admin_override(
     ctx: &Context<'_>,
+) -> Result<(i64, AdminOverride), GqlError> {
+    require_override(ctx, None)
+}
+
+/// Require an authenticated user AND an admin override valid for `action`.
+///
+/// Accepts overrides scoped to `action` as well as unscoped ones.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// async fn delete_invoice(ctx: &Context<'_>, id: i64) -> Result<bool> {
+///     let (cashier_id, admin_override) = require_admin_override_for(ctx, "delete_invoice")?;
+///     // ...
+/// }
+/// ```
+///
+/// # Errors
+///
+/// Returns UNAUTHORIZED if no user is authenticated,
+/// or FORBIDDEN if no matching admin override is present
+pub fn require_admin_override_for(
+    ctx: &Context<'_>,
+    action: &str,
+) -> Result<(i64, AdminOverride), GqlError> {
+    require_override(ctx, Some(action))
+}
+
+fn require_override(
+    ctx: &Context<'_>,
+    action: Option<&str>,
 ) -> Result<(i64, AdminOverride), GqlError> {
     let data = ctx.data_unchecked::<ContextData>();
 
@@ -208,9 +533,7 @@ pub fn require_auth_with_admin_override(
         .parse()
         .map_err(|_| gql_error("BAD_REQUEST", "Invalid user ID"))?;
 
-    let admin_override = data
-        .admin_override
-        .as_ref()
+    let admin_override = usable_admin_override(data, action)
         .ok_or_else(|| gql_error("FORBIDDEN", "Admin override required"))?
         .clone();
 
@@ -234,6 +557,23 @@ pub struct AdminOverrideAudit {
 }
 
 impl AdminOverrideAudit {
+    /// Build an audit event for `action_name` performed under the override.
+    pub fn to_event(&self, action_name: impl Into<String>) -> AuditEvent {
+        AuditEvent::new(action_name)
+            .with_actor(self.actor_user_id)
+            .with_metadata(serde_json::json!({
+                "admin_id": self.authorizer_admin_id,
+                "admin_name": self.authorizer_name,
+                "override_action": self.action,
+            }))
+    }
+
+    /// Record the audit trail to the global audit sink and log it.
+    pub async fn record(&self, action_name: impl Into<String>) {
+        self.log();
+        crate::audit::emit(self.to_event(action_name)).await;
+    }
+
     /// Log the audit trail using tracing.
     pub fn log(&self) {
         tracing::info!(
@@ -270,7 +610,8 @@ mod tests {
     fn test_issue_and_validate_token() {
         let config = test_config();
         let token =
-            issue_admin_override_token(&config, 42, "Admin User", Some("delete_invoice")).unwrap();
+            issue_admin_override_token(&config, 42, "Admin User", Some("delete_invoice"), None)
+                .unwrap();
 
         let result = validate_admin_override_token(&token, &config).unwrap();
         assert_eq!(result.admin_id, 42);
@@ -282,7 +623,7 @@ mod tests {
     #[test]
     fn test_issue_token_without_action() {
         let config = test_config();
-        let token = issue_admin_override_token(&config, 1, "Boss", None).unwrap();
+        let token = issue_admin_override_token(&config, 1, "Boss", None, None).unwrap();
 
         let result = validate_admin_override_token(&token, &config).unwrap();
         assert_eq!(result.admin_id, 1);
@@ -293,8 +634,7 @@ mod tests {
     #[test]
     fn test_validate_with_wrong_secret() {
         let config = test_config();
-        let token =
-            issue_admin_override_token(&config, 42, "Admin", None).unwrap();
+        let token = issue_admin_override_token(&config, 42, "Admin", None, None).unwrap();
 
         let wrong_config = AdminOverrideConfig::new("wrong-secret".to_string());
         let result = validate_admin_override_token(&token, &wrong_config);
@@ -305,8 +645,7 @@ mod tests {
     fn test_validate_expired_token() {
         // Use -120 to exceed jsonwebtoken's default leeway (60 seconds)
         let config = AdminOverrideConfig::new("secret".to_string()).with_expiry_secs(-120);
-        let token =
-            issue_admin_override_token(&config, 42, "Admin", None).unwrap();
+        let token = issue_admin_override_token(&config, 42, "Admin", None, None).unwrap();
 
         let valid_config = AdminOverrideConfig::new("secret".to_string());
         let result = validate_admin_override_token(&token, &valid_config);
@@ -343,6 +682,148 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[tokio::test]
+    async fn test_redeem_is_single_use() {
+        let config = test_config();
+        let store = MemoryOverrideNonceStore::new();
+        let token = issue_admin_override_token(&config, 42, "Admin", None, None).unwrap();
+
+        assert!(redeem_admin_override_token(
+            &token,
+            &config,
+            &store,
+            Some("5"),
+            None,
+            &SessionInfo::new()
+        )
+        .await
+        .is_ok());
+        let replay = redeem_admin_override_token(
+            &token,
+            &config,
+            &store,
+            Some("5"),
+            None,
+            &SessionInfo::new(),
+        )
+        .await;
+        assert!(replay.unwrap_err().contains("already been used"));
+
+        // Every token gets its own nonce
+        let other = issue_admin_override_token(&config, 42, "Admin", None, None).unwrap();
+        assert!(redeem_admin_override_token(
+            &other,
+            &config,
+            &store,
+            Some("5"),
+            None,
+            &SessionInfo::new()
+        )
+        .await
+        .is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_redeem_is_audited() {
+        let sink = crate::audit::tests::test_sink();
+        let config = test_config();
+        let token = issue_admin_override_token(&config, 42, "Admin", None, Some(5)).unwrap();
+
+        let client = SessionInfo {
+            ip: Some("10.0.0.9".into()),
+            user_agent: Some("pos-terminal/2.0".into()),
+            ..SessionInfo::new()
+        };
+        let ao = redeem_admin_override_token(
+            &token,
+            &config,
+            &MemoryOverrideNonceStore::new(),
+            Some("5"),
+            Some("acme"),
+            &client,
+        )
+        .await
+        .unwrap();
+        let event = sink
+            .events()
+            .into_iter()
+            .find(|e| {
+                e.metadata.as_ref().and_then(|m| m.get("jti")) == Some(&ao.jti.clone().into())
+            })
+            .unwrap();
+        assert_eq!(event.action, crate::audit::actions::ADMIN_OVERRIDE_USED);
+        assert_eq!(event.actor.as_deref(), Some("5"));
+        assert_eq!(event.tenant.as_deref(), Some("acme"));
+        assert_eq!(event.ip.as_deref(), Some("10.0.0.9"));
+        assert_eq!(event.user_agent.as_deref(), Some("pos-terminal/2.0"));
+    }
+
+    #[tokio::test]
+    async fn test_redeem_enforces_actor() {
+        let config = test_config();
+        let store = MemoryOverrideNonceStore::new();
+        let token = issue_admin_override_token(&config, 42, "Admin", None, Some(5)).unwrap();
+
+        assert!(redeem_admin_override_token(
+            &token,
+            &config,
+            &store,
+            Some("6"),
+            None,
+            &SessionInfo::new()
+        )
+        .await
+        .is_err());
+        assert!(redeem_admin_override_token(
+            &token,
+            &config,
+            &store,
+            None,
+            None,
+            &SessionInfo::new()
+        )
+        .await
+        .is_err());
+        let result = redeem_admin_override_token(
+            &token,
+            &config,
+            &store,
+            Some("5"),
+            None,
+            &SessionInfo::new(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(result.actor_user_id, Some(5));
+    }
+
+    #[test]
+    fn test_usable_override_respects_action_and_actor() {
+        use sea_orm::DatabaseConnection;
+
+        let config = test_config();
+        let token =
+            issue_admin_override_token(&config, 42, "Admin", Some("delete_invoice"), Some(5))
+                .unwrap();
+        let ao = validate_admin_override_token(&token, &config).unwrap();
+        let data = |user: &str| {
+            ContextData::single_tenant(
+                DatabaseConnection::Disconnected,
+                Some(user.to_string()),
+                None,
+                Some(ao.clone()),
+            )
+        };
+
+        assert!(usable_admin_override(&data("5"), Some("delete_invoice")).is_some());
+        assert!(usable_admin_override(&data("5"), Some("refund_order")).is_none());
+        // Scoped overrides never count as general admin access
+        assert!(usable_admin_override(&data("5"), None).is_none());
+        assert!(!data("5").is_admin());
+        // Bound to the actor
+        assert!(usable_admin_override(&data("6"), Some("delete_invoice")).is_none());
+    }
+
     #[test]
     fn test_audit_log() {
         let audit = AdminOverrideAudit {
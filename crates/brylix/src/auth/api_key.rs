@@ -0,0 +1,708 @@
+//! API key authentication for machine-to-machine clients.
+//!
+//! Integrations that cannot perform an interactive login (partner webhooks,
+//! cron scripts) authenticate with a long-lived API key instead of a JWT.
+//!
+//! Keys look like `brx_<prefix>_<secret>`. The prefix is stored in plain text
+//! so the key can be looked up and shown in UIs; the secret is only stored as
+//! an Argon2 hash (the same hashing as [`hash_password`]).
+//!
+//! Clients send the key as either header:
+//!
+//! ```text
+//! Authorization: ApiKey brx_k3J9aQ2m_...
+//! X-Api-Key: brx_k3J9aQ2m_...
+//! ```
+//!
+//! # Usage
+//!
+//! ```rust,ignore
+//! use brylix::auth::api_key::{issue_api_key, SeaOrmApiKeyStore};
+//! use brylix::auth::jwt_middleware_with_api_keys;
+//!
+//! // Issue a key (show `raw_key` to the user once; it cannot be recovered)
+//! let store = SeaOrmApiKeyStore::new(db.clone());
+//! let (raw_key, api_key) = issue_api_key(&store, user_id, "Billing webhook", &["invoice.read"], None).await?;
+//!
+//! // Per request: accepts API keys and falls back to Bearer JWTs
+//! let auth = jwt_middleware_with_api_keys(&request, &store).await?;
+//! let ctx = ContextData::single_tenant(db, auth.user_id, auth.role)
+//!     .with_permissions(auth.scopes.iter().map(String::as_str).collect());
+//! ```
+
+use chrono::{DateTime, Utc};
+use lambda_http::Request;
+use sea_orm::sea_query::Expr;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, Set,
+};
+
+use super::password::{hash_password, random_alphanumeric, verify_password};
+use super::permissions::{Permission, PermissionSet};
+use crate::errors::{DomainError, DomainResult};
+
+/// HTTP header name for API keys.
+pub const API_KEY_HEADER: &str = "X-Api-Key";
+
+/// Authorization scheme for API keys (`Authorization: ApiKey <key>`).
+pub const API_KEY_SCHEME: &str = "ApiKey ";
+
+/// Marker at the start of every generated key.
+const KEY_MARKER: &str = "brx";
+
+/// Length of the visible lookup prefix.
+const PREFIX_LEN: usize = 8;
+
+/// Length of the secret part.
+const SECRET_LEN: usize = 32;
+
+/// Separator between scopes in the stored `scopes` column.
+const SCOPE_SEPARATOR: &str = ",";
+
+/// A stored API key.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApiKey {
+    /// Key ID
+    pub id: i64,
+    /// ID of the user the key acts on behalf of
+    pub owner_id: i64,
+    /// Human-readable name (e.g. "Billing webhook")
+    pub name: String,
+    /// Visible lookup prefix
+    pub prefix: String,
+    /// Argon2 hash of the secret part
+    pub key_hash: String,
+    /// Scopes granted to the key (permission strings, wildcards allowed)
+    pub scopes: Vec<String>,
+    /// When the key was created
+    pub created_at: DateTime<Utc>,
+    /// When the key was last used to authenticate
+    pub last_used_at: Option<DateTime<Utc>>,
+    /// Optional expiry
+    pub expires_at: Option<DateTime<Utc>>,
+    /// When the key was revoked, if it has been
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+impl ApiKey {
+    /// Check if the key is neither revoked nor expired at `now`.
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        self.revoked_at.is_none() && self.expires_at.is_none_or(|exp| exp > now)
+    }
+
+    /// Check if the key has been granted a scope.
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scopes
+            .iter()
+            .any(|s| Permission::new(s.as_str()).grants(scope))
+    }
+
+    /// Get the key's scopes as a permission set.
+    pub fn scope_set(&self) -> PermissionSet {
+        self.scopes.iter().map(String::as_str).collect()
+    }
+}
+
+/// Data needed to persist a newly generated API key.
+#[derive(Debug, Clone)]
+pub struct NewApiKey {
+    /// ID of the user the key acts on behalf of
+    pub owner_id: i64,
+    /// Human-readable name
+    pub name: String,
+    /// Visible lookup prefix
+    pub prefix: String,
+    /// Argon2 hash of the secret part
+    pub key_hash: String,
+    /// Scopes granted to the key
+    pub scopes: Vec<String>,
+    /// Optional expiry
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// A freshly generated key. `key` is the only time the secret is visible.
+#[derive(Debug, Clone)]
+pub struct GeneratedApiKey {
+    /// The full key to hand to the client
+    pub key: String,
+    /// Visible lookup prefix
+    pub prefix: String,
+    /// Argon2 hash of the secret part
+    pub key_hash: String,
+}
+
+/// Storage backend for API keys.
+#[async_trait::async_trait]
+pub trait ApiKeyStore: Send + Sync {
+    /// Persist a new key.
+    async fn create(&self, key: NewApiKey) -> DomainResult<ApiKey>;
+
+    /// Find a key by its visible prefix.
+    async fn find_by_prefix(&self, prefix: &str) -> DomainResult<Option<ApiKey>>;
+
+    /// List all keys belonging to a user.
+    async fn list_for_owner(&self, owner_id: i64) -> DomainResult<Vec<ApiKey>>;
+
+    /// Record that a key was used.
+    async fn touch(&self, id: i64, used_at: DateTime<Utc>) -> DomainResult<()>;
+
+    /// Revoke a key.
+    async fn revoke(&self, id: i64, revoked_at: DateTime<Utc>) -> DomainResult<()>;
+
+    /// Tenant the stored keys belong to (multi-tenant mode).
+    fn tenant(&self) -> Option<&str> {
+        None
+    }
+}
+
+/// Generate a new API key with a random prefix and hashed secret.
+///
+/// # Errors
+///
+/// Returns an error if hashing fails
+pub fn generate_api_key() -> Result<GeneratedApiKey, String> {
+    let prefix = random_alphanumeric(PREFIX_LEN);
+    let secret = random_alphanumeric(SECRET_LEN);
+    let key_hash = hash_password(&secret)?;
+
+    Ok(GeneratedApiKey {
+        key: format!("{}_{}_{}", KEY_MARKER, prefix, secret),
+        prefix,
+        key_hash,
+    })
+}
+
+/// Split a raw key into its prefix and secret parts.
+///
+/// Returns `None` if the key is not in the `brx_<prefix>_<secret>` format.
+pub fn parse_api_key(raw: &str) -> Option<(&str, &str)> {
+    let rest = raw.strip_prefix(KEY_MARKER)?.strip_prefix('_')?;
+    let (prefix, secret) = rest.split_once('_')?;
+    if prefix.len() != PREFIX_LEN || secret.is_empty() {
+        return None;
+    }
+    Some((prefix, secret))
+}
+
+/// Generate and store a new API key.
+///
+/// Returns the raw key (to be shown once) and the stored record.
+///
+/// # Errors
+///
+/// Returns `InvalidInput` if a scope contains a comma (scopes are stored
+/// comma-separated), or an error if hashing or storage fails
+pub async fn issue_api_key(
+    store: &dyn ApiKeyStore,
+    owner_id: i64,
+    name: &str,
+    scopes: &[&str],
+    expires_at: Option<DateTime<Utc>>,
+) -> DomainResult<(String, ApiKey)> {
+    if let Some(scope) = scopes.iter().find(|s| s.contains(SCOPE_SEPARATOR)) {
+        return Err(DomainError::InvalidInput(format!(
+            "Scope '{}' must not contain '{}'",
+            scope, SCOPE_SEPARATOR
+        )));
+    }
+
+    let generated = generate_api_key().map_err(DomainError::Internal)?;
+    let stored = store
+        .create(NewApiKey {
+            owner_id,
+            name: name.to_string(),
+            prefix: generated.prefix,
+            key_hash: generated.key_hash,
+            scopes: scopes.iter().map(|s| s.to_string()).collect(),
+            expires_at,
+        })
+        .await?;
+    Ok((generated.key, stored))
+}
+
+/// Verify a raw API key and record its use.
+///
+/// # Errors
+///
+/// Returns `InvalidCredentials` if the key is malformed, unknown, revoked,
+/// expired, or the secret does not match
+pub async fn verify_api_key(store: &dyn ApiKeyStore, raw: &str) -> DomainResult<ApiKey> {
+    let (prefix, secret) = parse_api_key(raw).ok_or(DomainError::InvalidCredentials)?;
+    let key = store
+        .find_by_prefix(prefix)
+        .await?
+        .ok_or(DomainError::InvalidCredentials)?;
+
+    let now = Utc::now();
+    if !key.is_active(now) {
+        return Err(DomainError::InvalidCredentials);
+    }
+    if !verify_password(secret, &key.key_hash).map_err(DomainError::Internal)? {
+        return Err(DomainError::InvalidCredentials);
+    }
+
+    if let Err(e) = store.touch(key.id, now).await {
+        tracing::warn!(key_id = key.id, error = %e, "Failed to record API key usage");
+    }
+
+    Ok(ApiKey {
+        last_used_at: Some(now),
+        ..key
+    })
+}
+
+/// Revoke an API key.
+///
+/// # Errors
+///
+/// Returns an error if storage fails
+pub async fn revoke_api_key(store: &dyn ApiKeyStore, id: i64) -> DomainResult<()> {
+    store.revoke(id, Utc::now()).await
+}
+
+/// Extract an API key from the `Authorization: ApiKey ...` or `X-Api-Key` header.
+pub fn extract_api_key(request: &Request) -> Option<String> {
+    let from_authorization = request
+        .headers()
+        .get("Authorization")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|s| s.strip_prefix(API_KEY_SCHEME));
+
+    from_authorization
+        .or_else(|| {
+            request
+                .headers()
+                .get(API_KEY_HEADER)
+                .and_then(|v| v.to_str().ok())
+        })
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+}
+
+/// SeaORM-backed API key store using the `api_keys` table.
+///
+/// Add [`migration::Migration`] to your migrator to create the table.
+#[derive(Debug, Clone)]
+pub struct SeaOrmApiKeyStore {
+    db: DatabaseConnection,
+    tenant: Option<String>,
+}
+
+impl SeaOrmApiKeyStore {
+    /// Create a store over the given connection.
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self { db, tenant: None }
+    }
+
+    /// Mark the keys as belonging to `tenant`, reported as
+    /// [`JwtResult::tenant`](crate::auth::JwtResult::tenant) for requests
+    /// authenticated with them. Use with the tenant's own database.
+    pub fn with_tenant(mut self, tenant: impl Into<String>) -> Self {
+        self.tenant = Some(tenant.into());
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl ApiKeyStore for SeaOrmApiKeyStore {
+    async fn create(&self, key: NewApiKey) -> DomainResult<ApiKey> {
+        let model = entity::ActiveModel {
+            owner_id: Set(key.owner_id),
+            name: Set(key.name),
+            prefix: Set(key.prefix),
+            key_hash: Set(key.key_hash),
+            scopes: Set(key.scopes.join(SCOPE_SEPARATOR)),
+            created_at: Set(Utc::now()),
+            last_used_at: Set(None),
+            expires_at: Set(key.expires_at),
+            revoked_at: Set(None),
+            ..Default::default()
+        }
+        .insert(&self.db)
+        .await?;
+        Ok(model.into())
+    }
+
+    async fn find_by_prefix(&self, prefix: &str) -> DomainResult<Option<ApiKey>> {
+        Ok(entity::Entity::find()
+            .filter(entity::Column::Prefix.eq(prefix))
+            .one(&self.db)
+            .await?
+            .map(Into::into))
+    }
+
+    async fn list_for_owner(&self, owner_id: i64) -> DomainResult<Vec<ApiKey>> {
+        Ok(entity::Entity::find()
+            .filter(entity::Column::OwnerId.eq(owner_id))
+            .order_by_asc(entity::Column::Id)
+            .all(&self.db)
+            .await?
+            .into_iter()
+            .map(Into::into)
+            .collect())
+    }
+
+    async fn touch(&self, id: i64, used_at: DateTime<Utc>) -> DomainResult<()> {
+        entity::Entity::update_many()
+            .col_expr(entity::Column::LastUsedAt, Expr::value(used_at))
+            .filter(entity::Column::Id.eq(id))
+            .exec(&self.db)
+            .await?;
+        Ok(())
+    }
+
+    async fn revoke(&self, id: i64, revoked_at: DateTime<Utc>) -> DomainResult<()> {
+        entity::Entity::update_many()
+            .col_expr(entity::Column::RevokedAt, Expr::value(revoked_at))
+            .filter(entity::Column::Id.eq(id))
+            .exec(&self.db)
+            .await?;
+        Ok(())
+    }
+
+    fn tenant(&self) -> Option<&str> {
+        self.tenant.as_deref()
+    }
+}
+
+impl From<entity::Model> for ApiKey {
+    fn from(m: entity::Model) -> Self {
+        Self {
+            id: m.id,
+            owner_id: m.owner_id,
+            name: m.name,
+            prefix: m.prefix,
+            key_hash: m.key_hash,
+            scopes: m
+                .scopes
+                .split(SCOPE_SEPARATOR)
+                .filter(|s| !s.is_empty())
+                .map(String::from)
+                .collect(),
+            created_at: m.created_at,
+            last_used_at: m.last_used_at,
+            expires_at: m.expires_at,
+            revoked_at: m.revoked_at,
+        }
+    }
+}
+
+/// SeaORM entity for the `api_keys` table.
+pub mod entity {
+    use sea_orm::entity::prelude::*;
+
+    /// A stored API key row.
+    #[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+    #[sea_orm(table_name = "api_keys")]
+    pub struct Model {
+        /// Key ID
+        #[sea_orm(primary_key)]
+        pub id: i64,
+        /// Owning user ID
+        pub owner_id: i64,
+        /// Human-readable name
+        pub name: String,
+        /// Visible lookup prefix
+        #[sea_orm(unique)]
+        pub prefix: String,
+        /// Argon2 hash of the secret part
+        pub key_hash: String,
+        /// Comma-separated scopes
+        #[sea_orm(column_type = "Text")]
+        pub scopes: String,
+        /// Creation time
+        pub created_at: DateTimeUtc,
+        /// Last successful authentication
+        pub last_used_at: Option<DateTimeUtc>,
+        /// Optional expiry
+        pub expires_at: Option<DateTimeUtc>,
+        /// Revocation time
+        pub revoked_at: Option<DateTimeUtc>,
+    }
+
+    /// No relations.
+    #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+    pub enum Relation {}
+
+    impl ActiveModelBehavior for ActiveModel {}
+}
+
+/// Migration creating the `api_keys` table.
+pub mod migration {
+    use sea_orm_migration::prelude::*;
+
+    /// Creates the `api_keys` table.
+    pub struct Migration;
+
+    impl MigrationName for Migration {
+        fn name(&self) -> &str {
+            "m20261018_000002_create_api_keys_table"
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl MigrationTrait for Migration {
+        async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+            manager
+                .create_table(
+                    Table::create()
+                        .table(ApiKeys::Table)
+                        .if_not_exists()
+                        .col(
+                            ColumnDef::new(ApiKeys::Id)
+                                .big_integer()
+                                .not_null()
+                                .auto_increment()
+                                .primary_key(),
+                        )
+                        .col(ColumnDef::new(ApiKeys::OwnerId).big_integer().not_null())
+                        .col(ColumnDef::new(ApiKeys::Name).string_len(255).not_null())
+                        .col(
+                            ColumnDef::new(ApiKeys::Prefix)
+                                .string_len(32)
+                                .not_null()
+                                .unique_key(),
+                        )
+                        .col(ColumnDef::new(ApiKeys::KeyHash).string_len(255).not_null())
+                        .col(ColumnDef::new(ApiKeys::Scopes).text().not_null())
+                        .col(
+                            ColumnDef::new(ApiKeys::CreatedAt)
+                                .timestamp()
+                                .not_null()
+                                .default(Expr::current_timestamp()),
+                        )
+                        .col(ColumnDef::new(ApiKeys::LastUsedAt).timestamp().null())
+                        .col(ColumnDef::new(ApiKeys::ExpiresAt).timestamp().null())
+                        .col(ColumnDef::new(ApiKeys::RevokedAt).timestamp().null())
+                        .index(
+                            Index::create()
+                                .name("idx_api_keys_owner_id")
+                                .col(ApiKeys::OwnerId),
+                        )
+                        .to_owned(),
+                )
+                .await
+        }
+
+        async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+            manager
+                .drop_table(Table::drop().table(ApiKeys::Table).to_owned())
+                .await
+        }
+    }
+
+    #[derive(Iden)]
+    enum ApiKeys {
+        Table,
+        Id,
+        OwnerId,
+        Name,
+        Prefix,
+        KeyHash,
+        Scopes,
+        CreatedAt,
+        LastUsedAt,
+        ExpiresAt,
+        RevokedAt,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct MemoryStore {
+        keys: Mutex<Vec<ApiKey>>,
+        tenant: Option<String>,
+    }
+
+    #[async_trait::async_trait]
+    impl ApiKeyStore for MemoryStore {
+        async fn create(&self, key: NewApiKey) -> DomainResult<ApiKey> {
+            let mut keys = self.keys.lock().unwrap();
+            let stored = ApiKey {
+                id: keys.len() as i64 + 1,
+                owner_id: key.owner_id,
+                name: key.name,
+                prefix: key.prefix,
+                key_hash: key.key_hash,
+                scopes: key.scopes,
+                created_at: Utc::now(),
+                last_used_at: None,
+                expires_at: key.expires_at,
+                revoked_at: None,
+            };
+            keys.push(stored.clone());
+            Ok(stored)
+        }
+
+        async fn find_by_prefix(&self, prefix: &str) -> DomainResult<Option<ApiKey>> {
+            let keys = self.keys.lock().unwrap();
+            Ok(keys.iter().find(|k| k.prefix == prefix).cloned())
+        }
+
+        async fn list_for_owner(&self, owner_id: i64) -> DomainResult<Vec<ApiKey>> {
+            let keys = self.keys.lock().unwrap();
+            Ok(keys.iter().filter(|k| k.owner_id == owner_id).cloned().collect())
+        }
+
+        async fn touch(&self, id: i64, used_at: DateTime<Utc>) -> DomainResult<()> {
+            let mut keys = self.keys.lock().unwrap();
+            if let Some(k) = keys.iter_mut().find(|k| k.id == id) {
+                k.last_used_at = Some(used_at);
+            }
+            Ok(())
+        }
+
+        async fn revoke(&self, id: i64, revoked_at: DateTime<Utc>) -> DomainResult<()> {
+            let mut keys = self.keys.lock().unwrap();
+            if let Some(k) = keys.iter_mut().find(|k| k.id == id) {
+                k.revoked_at = Some(revoked_at);
+            }
+            Ok(())
+        }
+
+        fn tenant(&self) -> Option<&str> {
+            self.tenant.as_deref()
+        }
+    }
+
+    #[test]
+    fn test_generate_and_parse() {
+        let generated = generate_api_key().unwrap();
+        let (prefix, secret) = parse_api_key(&generated.key).unwrap();
+        assert_eq!(prefix, generated.prefix);
+        assert_eq!(secret.len(), SECRET_LEN);
+        assert!(verify_password(secret, &generated.key_hash).unwrap());
+    }
+
+    #[test]
+    fn test_parse_rejects_malformed() {
+        assert!(parse_api_key("not-a-key").is_none());
+        assert!(parse_api_key("brx_short_secret").is_none());
+        assert!(parse_api_key("brx_abcdefgh_").is_none());
+    }
+
+    #[test]
+    fn test_has_scope() {
+        let key = ApiKey {
+            id: 1,
+            owner_id: 1,
+            name: "test".to_string(),
+            prefix: "abcdefgh".to_string(),
+            key_hash: String::new(),
+            scopes: vec!["invoice.*".to_string()],
+            created_at: Utc::now(),
+            last_used_at: None,
+            expires_at: None,
+            revoked_at: None,
+        };
+        assert!(key.has_scope("invoice.read"));
+        assert!(!key.has_scope("user.read"));
+        assert!(key.scope_set().allows("invoice.delete"));
+    }
+
+    #[tokio::test]
+    async fn test_issue_and_verify() {
+        let store = MemoryStore::default();
+        let (raw, stored) = issue_api_key(&store, 7, "cron", &["report.read"], None)
+            .await
+            .unwrap();
+
+        let verified = verify_api_key(&store, &raw).await.unwrap();
+        assert_eq!(verified.id, stored.id);
+        assert_eq!(verified.owner_id, 7);
+        assert!(verified.last_used_at.is_some());
+        assert!(store.list_for_owner(7).await.unwrap()[0].last_used_at.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_middleware_reports_key_and_tenant() {
+        let store = MemoryStore {
+            tenant: Some("acme".to_string()),
+            ..Default::default()
+        };
+        let (raw, stored) = issue_api_key(&store, 7, "cron", &["report.read"], None)
+            .await
+            .unwrap();
+        let request = http::Request::builder()
+            .header("X-Api-Key", raw)
+            .body(lambda_http::Body::Empty)
+            .unwrap();
+
+        let result = crate::auth::jwt_middleware_with_api_keys(&request, &store)
+            .await
+            .unwrap();
+        assert_eq!(result.user_id.as_deref(), Some("7"));
+        assert_eq!(result.tenant.as_deref(), Some("acme"));
+        assert_eq!(
+            result.role,
+            Some(crate::auth::roles::AuthRole::ApiKey {
+                key_id: stored.id,
+                owner_id: 7
+            })
+        );
+        assert_eq!(result.scopes, vec!["report.read".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_issue_rejects_comma_in_scope() {
+        let store = MemoryStore::default();
+        let result = issue_api_key(&store, 7, "cron", &["report.read,admin"], None).await;
+        assert!(matches!(result, Err(DomainError::InvalidInput(_))));
+        assert!(store.list_for_owner(7).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_verify_rejects_wrong_secret() {
+        let store = MemoryStore::default();
+        let (raw, stored) = issue_api_key(&store, 7, "cron", &[], None).await.unwrap();
+        let tampered = format!("{}x", raw);
+        assert!(matches!(
+            verify_api_key(&store, &tampered).await,
+            Err(DomainError::InvalidCredentials)
+        ));
+        assert!(stored.last_used_at.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_verify_rejects_revoked_and_expired() {
+        let store = MemoryStore::default();
+        let (raw, stored) = issue_api_key(&store, 7, "cron", &[], None).await.unwrap();
+        revoke_api_key(&store, stored.id).await.unwrap();
+        assert!(verify_api_key(&store, &raw).await.is_err());
+
+        let expired = Utc::now() - chrono::Duration::minutes(1);
+        let (raw, _) = issue_api_key(&store, 7, "old", &[], Some(expired)).await.unwrap();
+        assert!(verify_api_key(&store, &raw).await.is_err());
+    }
+
+    #[test]
+    fn test_extract_api_key_headers() {
+        let request = http::Request::builder()
+            .header("Authorization", "ApiKey brx_abcdefgh_secret")
+            .body(lambda_http::Body::Empty)
+            .unwrap();
+        assert_eq!(
+            extract_api_key(&request).as_deref(),
+            Some("brx_abcdefgh_secret")
+        );
+
+        let request = http::Request::builder()
+            .header(API_KEY_HEADER, "brx_abcdefgh_secret")
+            .body(lambda_http::Body::Empty)
+            .unwrap();
+        assert_eq!(
+            extract_api_key(&request).as_deref(),
+            Some("brx_abcdefgh_secret")
+        );
+
+        let request = http::Request::builder()
+            .header("Authorization", "Bearer token")
+            .body(lambda_http::Body::Empty)
+            .unwrap();
+        assert!(extract_api_key(&request).is_none());
+    }
+}
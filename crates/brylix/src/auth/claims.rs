@@ -1,5 +1,6 @@
 //! JWT Claims structure and validation.
 
+use super::roles::AuthRole;
 use crate::config::Config;
 use jsonwebtoken::{decode, DecodingKey, Validation};
 use serde::{Deserialize, Serialize};
@@ -7,7 +8,8 @@ use serde::{Deserialize, Serialize};
 /// JWT Claims structure.
 ///
 /// Contains the standard JWT claims plus optional tenant information
-/// for multi-tenant applications.
+/// for multi-tenant applications, and optional role claims used to
+/// populate [`AuthRole`] without trial-decoding against several secrets.
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Claims {
     /// Subject - typically the user ID
@@ -19,9 +21,59 @@ pub struct Claims {
 
     /// Expiration timestamp (Unix epoch seconds)
     pub exp: usize,
+
+    /// Primary role name (e.g. "user", "admin")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub role: Option<String>,
+
+    /// All role names granted to the subject
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub roles: Vec<String>,
+
+    /// Marks a first-step token awaiting MFA verification.
+    /// Such tokens are rejected by regular JWT validation.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub mfa_pending: bool,
+
+    /// Actor claim (RFC 8693): the real admin when this token impersonates
+    /// `sub`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub act: Option<ActorClaim>,
+
+    /// Session ID; tokens for revoked sessions are rejected by the middleware
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sid: Option<String>,
+
+    /// Token ID, set on single-use tokens such as MFA pending tokens
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jti: Option<String>,
 }
 
-/// Result of JWT validation containing user_id and optional tenant.
+/// The party acting on behalf of the token subject.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct ActorClaim {
+    /// Subject of the actor (the impersonating admin's user ID)
+    pub sub: String,
+}
+
+impl Claims {
+    /// Get the primary role name.
+    ///
+    /// Returns the `role` claim if present, otherwise the first entry of `roles`.
+    pub fn primary_role(&self) -> Option<&str> {
+        self.role
+            .as_deref()
+            .or_else(|| self.roles.first().map(String::as_str))
+    }
+
+    /// Check if the claims grant the given role, either as the primary
+    /// `role` or as one of `roles`.
+    pub fn has_role(&self, name: &str) -> bool {
+        self.role.as_deref() == Some(name) || self.roles.iter().any(|r| r == name)
+    }
+}
+
+/// Result of JWT validation containing user_id, optional tenant and optional role.
 #[derive(Debug, Clone)]
 pub struct JwtResult {
     /// The user ID extracted from the JWT subject
@@ -29,6 +81,18 @@ pub struct JwtResult {
 
     /// The tenant name from the JWT (multi-tenant mode)
     pub tenant: Option<String>,
+
+    /// The role from the verified `role`/`roles` claim, if any
+    pub role: Option<AuthRole>,
+
+    /// Scopes granted by the credential (API keys only; empty for JWTs)
+    pub scopes: Vec<String>,
+
+    /// The real admin's user ID when the token impersonates `user_id`
+    pub impersonator: Option<String>,
+
+    /// Session ID from the `sid` claim, if the token belongs to a tracked session
+    pub session_id: Option<String>,
 }
 
 impl JwtResult {
@@ -37,6 +101,10 @@ impl JwtResult {
         Self {
             user_id: None,
             tenant: None,
+            role: None,
+            scopes: Vec::new(),
+            impersonator: None,
+            session_id: None,
         }
     }
 
@@ -47,7 +115,18 @@ impl JwtResult {
 }
 
 /// Validate a JWT token with the given config.
+///
+/// Tokens still awaiting MFA verification are rejected.
 pub(crate) fn validate_jwt_with_config(token: &str, config: &Config) -> Result<Claims, String> {
+    let claims = decode_jwt_with_config(token, config)?;
+    if claims.mfa_pending {
+        return Err("MFA verification required".to_string());
+    }
+    Ok(claims)
+}
+
+/// Decode and verify a JWT without checking the MFA state.
+pub(crate) fn decode_jwt_with_config(token: &str, config: &Config) -> Result<Claims, String> {
     let decoding_key = DecodingKey::from_secret(config.jwt.secret.as_ref());
     let validation = Validation::default();
     let decoded = decode::<Claims>(token, &decoding_key, &validation).map_err(|e| {
@@ -67,6 +146,7 @@ mod tests {
         assert!(!result.is_authenticated());
         assert!(result.user_id.is_none());
         assert!(result.tenant.is_none());
+        assert!(result.role.is_none());
     }
 
     #[test]
@@ -74,7 +154,55 @@ mod tests {
         let result = JwtResult {
             user_id: Some("123".to_string()),
             tenant: Some("acme".to_string()),
+            role: Some(AuthRole::User(123)),
+            scopes: Vec::new(),
+            impersonator: None,
+            session_id: None,
         };
         assert!(result.is_authenticated());
     }
+
+    fn claims_with(role: Option<&str>, roles: &[&str]) -> Claims {
+        Claims {
+            sub: "7".to_string(),
+            tenant: None,
+            exp: 0,
+            role: role.map(String::from),
+            roles: roles.iter().map(|r| r.to_string()).collect(),
+            mfa_pending: false,
+            act: None,
+            sid: None,
+            jti: None,
+        }
+    }
+
+    #[test]
+    fn test_primary_role() {
+        assert_eq!(claims_with(Some("admin"), &["user"]).primary_role(), Some("admin"));
+        assert_eq!(claims_with(None, &["editor", "user"]).primary_role(), Some("editor"));
+        assert_eq!(claims_with(None, &[]).primary_role(), None);
+    }
+
+    #[test]
+    fn test_has_role() {
+        let claims = claims_with(Some("admin"), &["editor"]);
+        assert!(claims.has_role("admin"));
+        assert!(claims.has_role("editor"));
+        assert!(!claims.has_role("user"));
+    }
+
+    #[test]
+    fn test_legacy_claims_deserialize_without_roles() {
+        let claims: Claims =
+            serde_json::from_str(r#"{"sub":"1","tenant":null,"exp":0}"#).unwrap();
+        assert!(claims.role.is_none());
+        assert!(claims.roles.is_empty());
+    }
+
+    #[test]
+    fn test_role_claims_omitted_when_empty() {
+        let json = serde_json::to_string(&claims_with(None, &[])).unwrap();
+        assert!(!json.contains("role"));
+        assert!(!json.contains("mfa_pending"));
+    }
 }
@@ -0,0 +1,373 @@
+//! Cookie-based sessions for browser clients.
+//!
+//! Instead of keeping the JWT in `localStorage`, the login mutation stores it
+//! in an `HttpOnly` session cookie and [`jwt_middleware_with_cookies`] reads
+//! it back. Because browsers attach cookies automatically, state-changing
+//! requests are protected with the double-submit pattern: a second, readable
+//! CSRF cookie is set alongside the session, and the client must echo its
+//! value in the `X-CSRF-Token` header.
+//!
+//! # Usage
+//!
+//! ```rust,ignore
+//! use brylix::auth::cookie::{clear_session_cookies, set_session_cookies, CookieConfig, SameSite};
+//! use brylix::auth::jwt_middleware_with_cookies;
+//!
+//! let cookies = CookieConfig::new().with_same_site(SameSite::Strict);
+//!
+//! // Handler: authenticate from the cookie (Bearer headers still work)
+//! let auth = jwt_middleware_with_cookies(&request, &cookies).await?;
+//!
+//! // Login mutation
+//! let token = issue_jwt(&user.id.to_string(), None).map_err(gql_internal)?;
+//! set_session_cookies(ctx, &token, &cookies);
+//!
+//! // Logout mutation
+//! clear_session_cookies(ctx, &cookies);
+//! ```
+//!
+//! The cookies reach the browser only if the handler copies the GraphQL
+//! response headers, which [`graphql_response`](crate::handler::graphql_response) does.
+//!
+//! [`jwt_middleware_with_cookies`]: super::jwt_middleware_with_cookies
+
+use async_graphql::Context;
+use chrono::Duration;
+use lambda_http::Request;
+
+use super::mfa::constant_time_eq;
+use super::password::random_alphanumeric;
+use crate::config::Config;
+
+/// Default session cookie name.
+pub const SESSION_COOKIE: &str = "brylix_session";
+
+/// Default CSRF cookie name.
+pub const CSRF_COOKIE: &str = "brylix_csrf";
+
+/// Default CSRF header name.
+pub const CSRF_HEADER: &str = "X-CSRF-Token";
+
+/// Length of generated CSRF tokens.
+const CSRF_TOKEN_LEN: usize = 32;
+
+/// `SameSite` cookie attribute.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SameSite {
+    /// Only sent on same-site requests
+    Strict,
+    /// Also sent on top-level cross-site navigations
+    Lax,
+    /// Sent on all requests (requires `Secure`)
+    None,
+}
+
+impl SameSite {
+    /// Get the attribute value.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SameSite::Strict => "Strict",
+            SameSite::Lax => "Lax",
+            SameSite::None => "None",
+        }
+    }
+}
+
+/// Session cookie and CSRF settings.
+#[derive(Debug, Clone)]
+pub struct CookieConfig {
+    /// Session cookie name (default: `brylix_session`)
+    pub name: String,
+    /// CSRF cookie name (default: `brylix_csrf`)
+    pub csrf_cookie_name: String,
+    /// CSRF header name (default: `X-CSRF-Token`)
+    pub csrf_header_name: String,
+    /// Cookie `Domain` attribute
+    pub domain: Option<String>,
+    /// Cookie `Path` attribute (default: `/`)
+    pub path: String,
+    /// Set the `Secure` attribute (default: `true`)
+    pub secure: bool,
+    /// `SameSite` attribute (default: `Lax`)
+    pub same_site: SameSite,
+    /// Cookie lifetime (default: the JWT expiry from [`Config`])
+    pub max_age: Option<Duration>,
+}
+
+impl Default for CookieConfig {
+    fn default() -> Self {
+        Self {
+            name: SESSION_COOKIE.to_string(),
+            csrf_cookie_name: CSRF_COOKIE.to_string(),
+            csrf_header_name: CSRF_HEADER.to_string(),
+            domain: None,
+            path: "/".to_string(),
+            secure: true,
+            same_site: SameSite::Lax,
+            max_age: None,
+        }
+    }
+}
+
+impl CookieConfig {
+    /// Create a config with the defaults.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the session cookie name.
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Set the CSRF cookie and header names.
+    pub fn with_csrf_names(
+        mut self,
+        cookie_name: impl Into<String>,
+        header_name: impl Into<String>,
+    ) -> Self {
+        self.csrf_cookie_name = cookie_name.into();
+        self.csrf_header_name = header_name.into();
+        self
+    }
+
+    /// Set the cookie domain.
+    pub fn with_domain(mut self, domain: impl Into<String>) -> Self {
+        self.domain = Some(domain.into());
+        self
+    }
+
+    /// Set the cookie path.
+    pub fn with_path(mut self, path: impl Into<String>) -> Self {
+        self.path = path.into();
+        self
+    }
+
+    /// Set the `Secure` attribute (disable only for local HTTP development).
+    pub fn with_secure(mut self, secure: bool) -> Self {
+        self.secure = secure;
+        self
+    }
+
+    /// Set the `SameSite` attribute.
+    pub fn with_same_site(mut self, same_site: SameSite) -> Self {
+        self.same_site = same_site;
+        self
+    }
+
+    /// Set the cookie lifetime.
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    fn max_age_secs(&self) -> Option<i64> {
+        self.max_age.map(|d| d.num_seconds()).or_else(|| {
+            Config::try_get().map(|config| Duration::days(config.jwt.exp_days).num_seconds())
+        })
+    }
+
+    fn build(&self, name: &str, value: &str, http_only: bool, max_age: Option<i64>) -> String {
+        let mut cookie = format!("{}={}; Path={}", name, value, self.path);
+        if let Some(domain) = &self.domain {
+            cookie.push_str(&format!("; Domain={}", domain));
+        }
+        if let Some(secs) = max_age {
+            cookie.push_str(&format!("; Max-Age={}", secs));
+        }
+        if http_only {
+            cookie.push_str("; HttpOnly");
+        }
+        if self.secure {
+            cookie.push_str("; Secure");
+        }
+        cookie.push_str(&format!("; SameSite={}", self.same_site.as_str()));
+        cookie
+    }
+}
+
+/// Build the `Set-Cookie` value for the session cookie (`HttpOnly`).
+pub fn session_cookie(token: &str, config: &CookieConfig) -> String {
+    config.build(&config.name, token, true, config.max_age_secs())
+}
+
+/// Build the `Set-Cookie` value for the CSRF cookie (readable by scripts).
+pub fn csrf_cookie(csrf_token: &str, config: &CookieConfig) -> String {
+    config.build(&config.csrf_cookie_name, csrf_token, false, config.max_age_secs())
+}
+
+/// Build `Set-Cookie` values that delete the session and CSRF cookies.
+pub fn expired_cookies(config: &CookieConfig) -> [String; 2] {
+    [
+        config.build(&config.name, "", true, Some(0)),
+        config.build(&config.csrf_cookie_name, "", false, Some(0)),
+    ]
+}
+
+/// Generate a random CSRF token.
+pub fn generate_csrf_token() -> String {
+    random_alphanumeric(CSRF_TOKEN_LEN)
+}
+
+/// Add session and CSRF `Set-Cookie` headers to the GraphQL response.
+///
+/// Returns the CSRF token, which can also be returned in the mutation
+/// payload for clients that cannot read cookies.
+pub fn set_session_cookies(ctx: &Context<'_>, token: &str, config: &CookieConfig) -> String {
+    let csrf_token = generate_csrf_token();
+    ctx.append_http_header("Set-Cookie", session_cookie(token, config));
+    ctx.append_http_header("Set-Cookie", csrf_cookie(&csrf_token, config));
+    csrf_token
+}
+
+/// Add `Set-Cookie` headers that log the browser out.
+pub fn clear_session_cookies(ctx: &Context<'_>, config: &CookieConfig) {
+    for cookie in expired_cookies(config) {
+        ctx.append_http_header("Set-Cookie", cookie);
+    }
+}
+
+/// Read a cookie value from the request's `Cookie` headers.
+pub fn extract_cookie(request: &Request, name: &str) -> Option<String> {
+    request
+        .headers()
+        .get_all("Cookie")
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|header| header.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value.to_string())
+        .filter(|value| !value.is_empty())
+}
+
+/// Check the double-submit CSRF token.
+///
+/// # Errors
+///
+/// Returns an error if the CSRF cookie or header is missing, or they differ
+pub fn verify_csrf(request: &Request, config: &CookieConfig) -> Result<(), String> {
+    let cookie = extract_cookie(request, &config.csrf_cookie_name);
+    let header = request
+        .headers()
+        .get(config.csrf_header_name.as_str())
+        .and_then(|v| v.to_str().ok());
+
+    match (cookie, header) {
+        (Some(cookie), Some(header)) if constant_time_eq(cookie.as_bytes(), header.as_bytes()) => {
+            Ok(())
+        }
+        _ => Err("CSRF token missing or invalid".to_string()),
+    }
+}
+
+/// Methods that do not need CSRF protection.
+pub(super) fn is_safe_method(method: &http::Method) -> bool {
+    matches!(
+        *method,
+        http::Method::GET | http::Method::HEAD | http::Method::OPTIONS
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::tests::init_test_config;
+    use crate::auth::{issue_jwt, jwt_middleware_with_cookies};
+    use crate::handler::graphql_request_from_get;
+    use lambda_http::RequestExt;
+    use std::collections::HashMap;
+
+    fn request(method: &str, headers: &[(&str, &str)]) -> Request {
+        let mut builder = http::Request::builder().method(method);
+        for (name, value) in headers {
+            builder = builder.header(*name, *value);
+        }
+        builder.body(lambda_http::Body::Empty).unwrap()
+    }
+
+    #[test]
+    fn test_cookie_attributes() {
+        let config = CookieConfig::new()
+            .with_domain("app.test")
+            .with_max_age(Duration::hours(1));
+
+        assert_eq!(
+            session_cookie("abc", &config),
+            "brylix_session=abc; Path=/; Domain=app.test; Max-Age=3600; HttpOnly; Secure; SameSite=Lax"
+        );
+        assert!(!csrf_cookie("xyz", &config).contains("HttpOnly"));
+        assert!(expired_cookies(&config)[0].contains("Max-Age=0"));
+    }
+
+    #[test]
+    fn test_extract_cookie() {
+        let req = request("GET", &[("Cookie", "theme=dark; brylix_session=tok; brylix_csrf=c1")]);
+        assert_eq!(extract_cookie(&req, "brylix_session").as_deref(), Some("tok"));
+        assert_eq!(extract_cookie(&req, "brylix_csrf").as_deref(), Some("c1"));
+        assert_eq!(extract_cookie(&req, "missing"), None);
+    }
+
+    #[tokio::test]
+    async fn test_cookie_session_requires_csrf_on_post() {
+        init_test_config();
+        let config = CookieConfig::new();
+        let token = issue_jwt("42", None).unwrap();
+        let cookies = format!("brylix_session={}; brylix_csrf=c1", token);
+
+        let get = request("GET", &[("Cookie", &cookies)]);
+        let result = jwt_middleware_with_cookies(&get, &config).await.unwrap();
+        assert_eq!(result.user_id.as_deref(), Some("42"));
+
+        let post = request("POST", &[("Cookie", &cookies)]);
+        assert!(jwt_middleware_with_cookies(&post, &config).await.is_err());
+
+        let forged = request("POST", &[("Cookie", &cookies), ("X-CSRF-Token", "c2")]);
+        assert!(jwt_middleware_with_cookies(&forged, &config).await.is_err());
+
+        let post = request("POST", &[("Cookie", &cookies), ("X-CSRF-Token", "c1")]);
+        let result = jwt_middleware_with_cookies(&post, &config).await.unwrap();
+        assert_eq!(result.user_id.as_deref(), Some("42"));
+    }
+
+    #[tokio::test]
+    async fn test_get_mutation_with_cookie_is_rejected() {
+        init_test_config();
+        let config = CookieConfig::new();
+        let token = issue_jwt("42", None).unwrap();
+        let cookies = format!("brylix_session={}", token);
+
+        // A cross-site link: the cookie is sent, the CSRF header is not
+        let get = |query: &str| {
+            request("GET", &[("Cookie", &cookies)]).with_query_string_parameters(
+                HashMap::from([("query".to_string(), query.to_string())]),
+            )
+        };
+        let mutation = get("mutation { deleteAccount }");
+        assert!(jwt_middleware_with_cookies(&mutation, &config).await.is_ok());
+        let err = graphql_request_from_get(mutation).await.unwrap_err();
+        assert!(err.to_string().contains("not allowed over GET"));
+
+        assert!(graphql_request_from_get(get("{ me }")).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_bearer_header_bypasses_cookie_mode() {
+        init_test_config();
+        let token = issue_jwt("7", None).unwrap();
+        let bearer = format!("Bearer {}", token);
+
+        let req = request("POST", &[("Authorization", &bearer)]);
+        let result = jwt_middleware_with_cookies(&req, &CookieConfig::new())
+            .await
+            .unwrap();
+        assert_eq!(result.user_id.as_deref(), Some("7"));
+
+        let anonymous = request("POST", &[]);
+        assert!(!jwt_middleware_with_cookies(&anonymous, &CookieConfig::new())
+            .await
+            .unwrap()
+            .is_authenticated());
+    }
+}
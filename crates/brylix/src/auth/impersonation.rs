@@ -0,0 +1,329 @@
+//! Impersonation ("log in as user") for support staff.
+//!
+//! An admin can obtain a short-lived token whose subject is the customer,
+//! so every resolver behaves exactly as it does for that customer. The real
+//! admin is carried in the RFC 8693 `act` claim; [`jwt_middleware`] exposes
+//! it as [`JwtResult::impersonator`] and [`ContextData::from_auth`] makes
+//! both identities available to resolvers.
+//!
+//! Dangerous mutations should be guarded with
+//! [`NotImpersonating`](crate::graphql::NotImpersonating) or
+//! [`require_not_impersonating`](crate::graphql::require_not_impersonating).
+//!
+//! Starting and ending a session is always audited: issuance fails unless
+//! the event is written to the audit sink (see [`crate::audit`]).
+//!
+//! # Usage
+//!
+//! ```rust,ignore
+//! use brylix::auth::impersonation::{end_impersonation, issue_impersonation_token, ImpersonationConfig, UserRoleLookup};
+//!
+//! // `user_roles` implements `UserRoleLookup`, reading the role from the users table
+//!
+//! // Admin-only mutation
+//! let data = ctx.data_unchecked::<ContextData>();
+//! let token = issue_impersonation_token(data, &customer_id.to_string(), &user_roles, &reason, &ImpersonationConfig::new())
+//!     .await
+//!     .map_err(gql_from_domain)?;
+//!
+//! // Handler
+//! let auth = jwt_middleware(&request).await?;
+//! let data = ContextData::from_auth(db, auth, None, None);
+//!
+//! // "Stop impersonating" mutation: revokes the impersonation token
+//! end_impersonation(data).await.map_err(gql_from_domain)?;
+//! ```
+//!
+//! [`jwt_middleware`]: super::jwt_middleware
+//! [`JwtResult::impersonator`]: super::JwtResult::impersonator
+//! [`ContextData::from_auth`]: crate::graphql::ContextData::from_auth
+
+use chrono::{Duration, Utc};
+
+use super::claims::{ActorClaim, Claims};
+use super::sessions::{revoke_session, start_session_with_claims, SessionInfo};
+use crate::audit::{actions, AuditEvent};
+use crate::errors::{DomainError, DomainResult};
+use crate::graphql::ContextData;
+
+/// Impersonation settings.
+#[derive(Debug, Clone)]
+pub struct ImpersonationConfig {
+    /// How long an impersonation token is valid (default: 30 minutes)
+    pub ttl: Duration,
+}
+
+impl Default for ImpersonationConfig {
+    fn default() -> Self {
+        Self {
+            ttl: Duration::minutes(30),
+        }
+    }
+}
+
+impl ImpersonationConfig {
+    /// Create a config with the defaults.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set how long an impersonation token is valid.
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+}
+
+/// Resolves the role of the user being impersonated.
+///
+/// The impersonation token carries this role, so it must come from the
+/// user's record rather than from the admin's request.
+#[async_trait::async_trait]
+pub trait UserRoleLookup: Send + Sync {
+    /// Return the role of `user_id` in `tenant`, or `None` if the user has
+    /// no role claim.
+    ///
+    /// # Errors
+    ///
+    /// Return `NotFound` if the user does not exist
+    async fn user_role(&self, tenant: Option<&str>, user_id: &str) -> DomainResult<Option<String>>;
+}
+
+/// Issue a token that impersonates `target_user_id`.
+///
+/// The caller must hold a real admin role; admin overrides and existing
+/// impersonation sessions are refused. The token carries the target's role
+/// as resolved by `roles`, the caller's tenant, and the admin in the `act`
+/// claim. It is bound to a new session (see [`super::sessions`]), so
+/// [`end_impersonation`] can revoke it; the session store must be
+/// initialized.
+///
+/// # Errors
+///
+/// Returns `Unauthorized` if the request is not authenticated, `Forbidden`
+/// if the caller is not an admin or is already impersonating,
+/// `InvalidInput` if the reason is empty or the target is the caller,
+/// `NotFound` if the target does not exist, and an error if the audit
+/// event cannot be recorded or the session cannot be started
+pub async fn issue_impersonation_token(
+    data: &ContextData,
+    target_user_id: &str,
+    roles: &dyn UserRoleLookup,
+    reason: &str,
+    config: &ImpersonationConfig,
+) -> DomainResult<String> {
+    let admin_id = data.user_id().ok_or(DomainError::Unauthorized)?;
+    if data.is_impersonating() {
+        return Err(DomainError::Forbidden(
+            "Cannot impersonate while impersonating".into(),
+        ));
+    }
+    if !data.auth_role().is_some_and(|r| r.is_admin()) {
+        return Err(DomainError::Forbidden("Admin access required".into()));
+    }
+    if target_user_id == admin_id {
+        return Err(DomainError::InvalidInput(
+            "Cannot impersonate yourself".into(),
+        ));
+    }
+    if reason.trim().is_empty() {
+        return Err(DomainError::InvalidInput(
+            "A reason is required to impersonate a user".into(),
+        ));
+    }
+
+    let tenant = data.tenant_name();
+    let role = roles.user_role(tenant, target_user_id).await?;
+    let claims = Claims {
+        sub: target_user_id.to_string(),
+        tenant: tenant.map(String::from),
+        exp: (Utc::now() + config.ttl).timestamp() as usize,
+        role,
+        roles: Vec::new(),
+        mfa_pending: false,
+        act: Some(ActorClaim {
+            sub: admin_id.to_string(),
+        }),
+        sid: None,
+        jti: None,
+    };
+
+    crate::audit::record(
+        AuditEvent::from_context(data, actions::IMPERSONATION_STARTED)
+            .with_target("user", target_user_id)
+            .with_metadata(serde_json::json!({
+                "reason": reason.trim(),
+                "role": claims.role,
+                "expires_at": claims.exp,
+            })),
+    )
+    .await?;
+
+    let info = SessionInfo::new().with_device(format!("Impersonation by {}", admin_id));
+    let (_, token) = start_session_with_claims(claims, info).await?;
+    Ok(token)
+}
+
+/// End an impersonation session.
+///
+/// Revokes the session the impersonation token is bound to (build the
+/// context with [`ContextData::from_auth`]) and records the end in the
+/// audit log.
+///
+/// # Errors
+///
+/// Returns `InvalidInput` if the request is not impersonating or carries no
+/// session ID, or an error if the session cannot be revoked or the audit
+/// event recorded
+pub async fn end_impersonation(data: &ContextData) -> DomainResult<()> {
+    let target = data
+        .user_id()
+        .filter(|_| data.is_impersonating())
+        .ok_or_else(|| DomainError::InvalidInput("Not impersonating".into()))?;
+
+    // Impersonation tokens are always bound to a session; without its ID
+    // the token would stay valid until it expires
+    let session_id = data
+        .session_id
+        .as_deref()
+        .ok_or_else(|| DomainError::InvalidInput("Impersonation session not attached".into()))?;
+    revoke_session(data.tenant_name(), target, session_id).await?;
+
+    crate::audit::record(
+        AuditEvent::from_context(data, actions::IMPERSONATION_ENDED).with_target("user", target),
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::jwt_middleware;
+    use crate::auth::roles::AuthRole;
+    use crate::auth::sessions::tests::init;
+    use sea_orm::DatabaseConnection;
+
+    struct Roles;
+
+    #[async_trait::async_trait]
+    impl UserRoleLookup for Roles {
+        async fn user_role(
+            &self,
+            _tenant: Option<&str>,
+            user_id: &str,
+        ) -> DomainResult<Option<String>> {
+            match user_id {
+                "77" | "78" => Ok(Some("user".into())),
+                _ => Err(DomainError::NotFound("User not found".into())),
+            }
+        }
+    }
+
+    fn context(user: &str, role: AuthRole) -> ContextData {
+        ContextData::single_tenant(
+            DatabaseConnection::Disconnected,
+            Some(user.to_string()),
+            Some(role),
+            #[cfg(feature = "admin-override")]
+            None,
+        )
+    }
+
+    fn bearer(token: &str) -> lambda_http::Request {
+        http::Request::builder()
+            .header("Authorization", format!("Bearer {}", token))
+            .body(lambda_http::Body::Empty)
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_admin_impersonates_user() {
+        init();
+        let sink = crate::audit::tests::test_sink();
+        let admin = context("1", AuthRole::Admin(1));
+
+        let token = issue_impersonation_token(
+            &admin,
+            "77",
+            &Roles,
+            "Ticket #123",
+            &ImpersonationConfig::new(),
+        )
+        .await
+        .unwrap();
+
+        let claims = crate::auth::validate_jwt(&token).unwrap();
+        assert_eq!(claims.sub, "77");
+        assert_eq!(claims.role.as_deref(), Some("user"));
+        assert_eq!(claims.act.unwrap().sub, "1");
+        assert!(claims.sid.is_some());
+
+        let event = sink
+            .events()
+            .into_iter()
+            .find(|e| {
+                e.action == actions::IMPERSONATION_STARTED && e.target_id.as_deref() == Some("77")
+            })
+            .unwrap();
+        assert_eq!(event.actor.as_deref(), Some("1"));
+
+        let auth = jwt_middleware(&bearer(&token)).await.unwrap();
+
+        // Without the session ID nothing could be revoked
+        let detached =
+            context("77", AuthRole::User(77)).with_impersonator(auth.impersonator.clone());
+        assert!(matches!(
+            end_impersonation(&detached).await,
+            Err(DomainError::InvalidInput(_))
+        ));
+
+        let impersonating = ContextData::from_auth(
+            DatabaseConnection::Disconnected,
+            auth,
+            None,
+            #[cfg(feature = "admin-override")]
+            None,
+        );
+        assert!(impersonating.is_impersonating());
+        assert_eq!(impersonating.real_user_id(), Some("1"));
+        end_impersonation(&impersonating).await.unwrap();
+        assert!(sink.events().iter().any(|e| {
+            e.action == actions::IMPERSONATION_ENDED
+                && e.metadata.as_ref().and_then(|m| m.get("impersonator")) == Some(&"1".into())
+        }));
+
+        // The token stops working once the impersonation has ended
+        assert!(jwt_middleware(&bearer(&token)).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_impersonation_is_admin_only() {
+        init();
+        crate::audit::tests::test_sink();
+        let config = ImpersonationConfig::new();
+
+        let user = context("5", AuthRole::User(5));
+        assert!(matches!(
+            issue_impersonation_token(&user, "77", &Roles, "reason", &config).await,
+            Err(DomainError::Forbidden(_))
+        ));
+
+        let nested = context("77", AuthRole::Admin(77)).with_impersonator(Some("1".into()));
+        assert!(matches!(
+            issue_impersonation_token(&nested, "78", &Roles, "reason", &config).await,
+            Err(DomainError::Forbidden(_))
+        ));
+
+        let admin = context("1", AuthRole::Admin(1));
+        assert!(matches!(
+            issue_impersonation_token(&admin, "77", &Roles, "  ", &config).await,
+            Err(DomainError::InvalidInput(_))
+        ));
+
+        // The target's role comes from the lookup, so unknown users fail
+        assert!(matches!(
+            issue_impersonation_token(&admin, "404", &Roles, "reason", &config).await,
+            Err(DomainError::NotFound(_))
+        ));
+    }
+}
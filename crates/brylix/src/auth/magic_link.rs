@@ -0,0 +1,344 @@
+//! Passwordless login via emailed magic links.
+//!
+//! A magic link carries a short-lived, single-use token built on
+//! [`tokens`](super::tokens): the random part is stored hashed, and an
+//! HMAC-SHA256 signature (keyed with the JWT secret) is appended so forged
+//! links are rejected before touching the database. Verifying the link
+//! consumes the token and issues a regular session JWT, recorded in the
+//! session store when one is initialized (see [`super::sessions`]).
+//!
+//! Requests are rate-limited per email address (trimmed and lowercased): at
+//! most [`MagicLinkConfig::max_requests`] links are issued per
+//! [`MagicLinkConfig::window`].
+//!
+//! # Usage
+//!
+//! ```rust,ignore
+//! use brylix::auth::magic_link::{send_magic_link, verify_magic_link, MagicLinkConfig};
+//!
+//! let store = SeaOrmOneTimeTokenStore::new(db.clone());
+//! let config = MagicLinkConfig::new();
+//!
+//! // "Email me a login link" mutation (requires the `email` feature)
+//! if let Some(user) = UserService::find_by_email(&db, &email).await? {
+//!     send_magic_link(&store, &email_provider, &config, user.id, &user.email, "Acme", "https://app.acme.com/login")
+//!         .await?;
+//! }
+//!
+//! // Landing page exchanges the link token for a session
+//! let (user_id, jwt) = verify_magic_link(&store, &token, None, SessionInfo::from_request(&request)).await?;
+//! ```
+
+use chrono::{Duration, Utc};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use super::mfa::constant_time_eq;
+use super::sessions::{issue_login_token, SessionInfo};
+use super::tokens::{consume_token, issue_token_to, OneTimeTokenStore, TokenPurpose};
+use crate::audit::{actions, AuditEvent};
+use crate::config::Config;
+use crate::errors::{DomainError, DomainResult};
+
+/// Magic link settings.
+#[derive(Debug, Clone)]
+pub struct MagicLinkConfig {
+    /// How long a link stays valid (default: 15 minutes)
+    pub ttl: Duration,
+    /// Maximum links issued per email address within `window` (default: 3)
+    pub max_requests: u64,
+    /// Rate limit window (default: 15 minutes)
+    pub window: Duration,
+}
+
+impl Default for MagicLinkConfig {
+    fn default() -> Self {
+        Self {
+            ttl: TokenPurpose::MagicLink.default_ttl(),
+            max_requests: 3,
+            window: Duration::minutes(15),
+        }
+    }
+}
+
+impl MagicLinkConfig {
+    /// Create a config with the defaults.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set how long a link stays valid.
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Set the per-email rate limit.
+    pub fn with_rate_limit(mut self, max_requests: u64, window: Duration) -> Self {
+        self.max_requests = max_requests;
+        self.window = window;
+        self
+    }
+}
+
+/// Issue a signed magic link token for a user.
+///
+/// # Arguments
+///
+/// * `store` - Token storage
+/// * `config` - Link lifetime and rate limit
+/// * `user_id` - The user logging in
+/// * `email` - The address the link is sent to; the rate limit is keyed by it
+///
+/// # Errors
+///
+/// Returns `TooManyAttempts` if the email has reached its rate limit,
+/// `ConfigNotInitialized` if the config is missing, or an error if storage fails
+pub async fn issue_magic_link_token(
+    store: &dyn OneTimeTokenStore,
+    config: &MagicLinkConfig,
+    user_id: i64,
+    email: &str,
+) -> DomainResult<String> {
+    let secret = signing_secret()?;
+    let email = email.trim().to_lowercase();
+
+    let now = Utc::now();
+    let recent = store
+        .issued_since(
+            &email,
+            TokenPurpose::MagicLink.as_str(),
+            now - config.window,
+        )
+        .await?;
+    let count = recent.len() as u64;
+    if count >= config.max_requests {
+        // Another link is allowed once enough of the recent ones leave the window
+        let excess = (count + 1 - config.max_requests) as usize;
+        let wait = recent
+            .get(excess - 1)
+            .map_or(config.window, |issued_at| *issued_at + config.window - now);
+        let millis = wait.num_milliseconds().max(0) as u64;
+        return Err(DomainError::TooManyAttempts {
+            retry_after_secs: millis.div_ceil(1000).max(1),
+        });
+    }
+
+    let raw = issue_token_to(
+        store,
+        user_id,
+        Some(email),
+        TokenPurpose::MagicLink,
+        config.ttl,
+    )
+    .await?;
+    let signature = sign(secret, &raw);
+    Ok(format!("{}.{}", raw, signature))
+}
+
+/// Verify a magic link token and exchange it for a session JWT.
+///
+/// Returns the user ID and a session JWT. `info` is recorded with the
+/// session when the session store is initialized.
+///
+/// # Errors
+///
+/// Returns `InvalidCredentials` if the token is malformed, forged, expired,
+/// or already used
+pub async fn verify_magic_link(
+    store: &dyn OneTimeTokenStore,
+    token: &str,
+    tenant: Option<&str>,
+    info: SessionInfo,
+) -> DomainResult<(i64, String)> {
+    let secret = signing_secret()?;
+
+    let (raw, signature) = token
+        .split_once('.')
+        .ok_or(DomainError::InvalidCredentials)?;
+    if !constant_time_eq(sign(secret, raw).as_bytes(), signature.as_bytes()) {
+        return Err(DomainError::InvalidCredentials);
+    }
+
+    let user_id = consume_token(store, TokenPurpose::MagicLink, raw)
+        .await
+        .map_err(|e| match e {
+            DomainError::InvalidInput(_) => DomainError::InvalidCredentials,
+            other => other,
+        })?;
+
+    let config = Config::try_get().ok_or(DomainError::ConfigNotInitialized)?;
+    let claims = super::login_claims(&user_id.to_string(), tenant, None, Vec::new(), config);
+    let jwt = issue_login_token(claims, info).await?;
+    crate::audit::emit(
+        AuditEvent::new(actions::MAGIC_LINK_LOGIN)
+            .with_actor(user_id)
+            .with_tenant(tenant),
+    )
+    .await;
+    Ok((user_id, jwt))
+}
+
+/// Ready-made magic link email.
+#[cfg(feature = "email")]
+mod email {
+    use super::*;
+    use crate::auth::tokens::{escape_html, token_link};
+    use crate::provider::email::{EmailMessage, EmailProvider};
+
+    /// Build the magic link login email.
+    pub fn magic_link_email(to: &str, app_name: &str, link: &str, ttl: Duration) -> EmailMessage {
+        let app_name = escape_html(app_name);
+        let link = escape_html(link);
+        EmailMessage::new(
+            to,
+            format!("Your {} login link", app_name),
+            format!(
+                "<p>Use the link below to sign in to {app}.</p>\
+                 <p><a href=\"{link}\">Sign in</a></p>\
+                 <p>This link expires in {minutes} minutes and can only be used once. \
+                 If you did not request it, you can ignore this email.</p>",
+                app = app_name,
+                link = link,
+                minutes = ttl.num_minutes(),
+            ),
+        )
+    }
+
+    /// Issue a magic link token and email the login link.
+    ///
+    /// `link_base` is the page that completes the login; the token is
+    /// appended as the `token` query parameter.
+    ///
+    /// # Errors
+    ///
+    /// Returns `TooManyAttempts` if the rate limit is reached, or an error if
+    /// storage or sending fails
+    pub async fn send_magic_link(
+        store: &dyn OneTimeTokenStore,
+        provider: &dyn EmailProvider,
+        config: &MagicLinkConfig,
+        user_id: i64,
+        to: &str,
+        app_name: &str,
+        link_base: &str,
+    ) -> DomainResult<()> {
+        let token = issue_magic_link_token(store, config, user_id, to).await?;
+        let link = token_link(link_base, &token);
+        provider
+            .send(magic_link_email(to, app_name, &link, config.ttl))
+            .await
+    }
+}
+
+#[cfg(feature = "email")]
+pub use email::{magic_link_email, send_magic_link};
+
+fn signing_secret() -> DomainResult<&'static str> {
+    Config::try_get()
+        .map(|config| config.jwt.secret.as_str())
+        .ok_or(DomainError::ConfigNotInitialized)
+}
+
+/// HMAC-SHA256 signature of the raw token (hex).
+fn sign(secret: &str, raw: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(TokenPurpose::MagicLink.as_str().as_bytes());
+    mac.update(b":");
+    mac.update(raw.as_bytes());
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::tests::init_test_config;
+    use crate::auth::tokens::tests::MemoryStore;
+
+    #[tokio::test]
+    async fn test_magic_link_round_trip() {
+        crate::auth::sessions::tests::init();
+        let store = MemoryStore::default();
+        let config = MagicLinkConfig::new();
+
+        let token = issue_magic_link_token(&store, &config, 9, "ada@example.com")
+            .await
+            .unwrap();
+        let (user_id, jwt) = verify_magic_link(&store, &token, Some("acme"), SessionInfo::new())
+            .await
+            .unwrap();
+        assert_eq!(user_id, 9);
+
+        let claims = crate::auth::validate_jwt(&jwt).unwrap();
+        assert_eq!(claims.sub, "9");
+        assert_eq!(claims.tenant.as_deref(), Some("acme"));
+        let sessions = crate::auth::sessions::list_sessions(Some("acme"), "9")
+            .await
+            .unwrap();
+        assert_eq!(claims.sid, Some(sessions[0].id.clone()));
+
+        // Single use
+        assert!(matches!(
+            verify_magic_link(&store, &token, None, SessionInfo::new()).await,
+            Err(DomainError::InvalidCredentials)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_magic_link_rejects_tampered_signature() {
+        init_test_config();
+        let store = MemoryStore::default();
+        let token = issue_magic_link_token(&store, &MagicLinkConfig::new(), 9, "ada@example.com")
+            .await
+            .unwrap();
+
+        let (raw, _) = token.split_once('.').unwrap();
+        let forged = format!("{}.{}", raw, "0".repeat(64));
+        assert!(verify_magic_link(&store, &forged, None, SessionInfo::new())
+            .await
+            .is_err());
+        assert!(verify_magic_link(&store, raw, None, SessionInfo::new())
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_magic_link_rate_limited_per_email() {
+        init_test_config();
+        let store = MemoryStore::default();
+        let config = MagicLinkConfig::new().with_rate_limit(2, Duration::minutes(15));
+
+        assert!(
+            issue_magic_link_token(&store, &config, 9, "ada@example.com")
+                .await
+                .is_ok()
+        );
+        assert!(
+            issue_magic_link_token(&store, &config, 9, " Ada@Example.com ")
+                .await
+                .is_ok()
+        );
+
+        // Shift the first link 10 minutes into the past
+        store.tokens.lock().unwrap()[0].created_at -= Duration::minutes(10);
+        match issue_magic_link_token(&store, &config, 9, "ADA@example.com").await {
+            Err(DomainError::TooManyAttempts { retry_after_secs }) => {
+                // Retry once the first link leaves the window, not after a full window
+                assert!((299..=300).contains(&retry_after_secs));
+            }
+            other => panic!("expected TooManyAttempts, got {:?}", other),
+        }
+
+        // Other addresses are unaffected
+        assert!(
+            issue_magic_link_token(&store, &config, 10, "bob@example.com")
+                .await
+                .is_ok()
+        );
+    }
+}
@@ -0,0 +1,1140 @@
+//! TOTP multi-factor authentication.
+//!
+//! Implements RFC 6238 time-based one-time passwords (compatible with Google
+//! Authenticator, 1Password, Authy, ...), hashed recovery codes, and a
+//! two-step login flow.
+//!
+//! # Two-step login
+//!
+//! 1. The `login` mutation verifies the password. If the user has MFA
+//!    enabled, it returns [`issue_mfa_pending_token`] instead of a session.
+//!    Pending tokens are short-lived and rejected by `jwt_middleware`, so
+//!    `require_auth` never treats them as logged in.
+//! 2. The `verifyMfa` mutation calls [`validate_mfa_pending_token`], loads
+//!    the user's TOTP secret, and exchanges the pending token for a regular
+//!    session with [`complete_mfa_login`].
+//!
+//! The second step goes through an [`MfaVerifier`]: each pending token can
+//! be exchanged only once, a TOTP time step is accepted only if it is newer
+//! than the last one the user presented (so an observed code cannot be
+//! replayed within its drift window), and wrong codes count towards a
+//! [`LoginThrottle`] lockout.
+//!
+//! # Usage
+//!
+//! ```rust,ignore
+//! use brylix::auth::mfa::*;
+//!
+//! // Enrollment
+//! let config = TotpConfig::new("Acme");
+//! let secret = generate_totp_secret();
+//! let uri = provisioning_uri(&config, &secret, &user.email); // render as QR code
+//! let recovery = generate_recovery_codes(10)?;               // show recovery.codes once
+//! // persist `secret` and `recovery.hashes` once the user confirms a first code
+//!
+//! // Login step 1
+//! if user.mfa_enabled {
+//!     return Ok(LoginResult::mfa_required(issue_mfa_pending_token(&user.id.to_string(), None, None)?));
+//! }
+//!
+//! // Login step 2
+//! let verifier = MfaVerifier::new(config, Arc::new(SeaOrmMfaStore::new(db.clone())), throttle);
+//! let pending = validate_mfa_pending_token(&mfa_token).map_err(gql_from_domain)?;
+//! let user = UserService::get_by_id(db, pending.sub.parse()?).await?;
+//! let token = complete_mfa_login(&pending, &verifier, &user.totp_secret, &code, SessionInfo::from_request(&request))
+//!     .await
+//!     .map_err(gql_from_domain)?;
+//! ```
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use jsonwebtoken::Validation;
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set, SqlErr};
+use sha1::Sha1;
+
+use super::claims::{decode_jwt_with_config, Claims};
+use super::password::{hash_password, random_alphanumeric, verify_password};
+use super::sessions::{issue_login_token, SessionInfo};
+use super::throttle::LoginThrottle;
+use super::{encode_jwt, login_claims};
+use crate::config::Config;
+use crate::errors::{DomainError, DomainResult};
+
+/// Default number of digits in a TOTP code.
+const DEFAULT_DIGITS: u32 = 6;
+
+/// Supported number of digits in a TOTP code.
+const DIGITS_RANGE: std::ops::RangeInclusive<u32> = 6..=8;
+
+/// Default TOTP time step in seconds.
+const DEFAULT_PERIOD_SECS: u64 = 30;
+
+/// Default number of time steps accepted before/after the current one.
+const DEFAULT_SKEW_STEPS: u64 = 1;
+
+/// Size of generated TOTP secrets in bytes (160 bits, as recommended by RFC 4226).
+const SECRET_BYTES: usize = 20;
+
+/// Lifetime of an MFA pending token in seconds.
+const MFA_PENDING_EXPIRY_SECS: i64 = 300;
+
+/// Length of the random `jti` of a pending token.
+const PENDING_JTI_LEN: usize = 32;
+
+/// Length of each recovery code (excluding the separator).
+const RECOVERY_CODE_LEN: usize = 10;
+
+/// RFC 4648 base32 alphabet.
+const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+/// TOTP parameters.
+#[derive(Debug, Clone)]
+pub struct TotpConfig {
+    /// Issuer shown in authenticator apps (usually the application name)
+    pub issuer: String,
+    /// Number of digits per code
+    pub digits: u32,
+    /// Time step in seconds
+    pub period_secs: u64,
+    /// Number of steps of clock drift tolerated in each direction
+    pub skew_steps: u64,
+}
+
+impl TotpConfig {
+    /// Create a config with the standard parameters (6 digits, 30 seconds, ±1 step).
+    pub fn new(issuer: impl Into<String>) -> Self {
+        Self {
+            issuer: issuer.into(),
+            digits: DEFAULT_DIGITS,
+            period_secs: DEFAULT_PERIOD_SECS,
+            skew_steps: DEFAULT_SKEW_STEPS,
+        }
+    }
+
+    /// Set the number of digits per code (6 to 8).
+    pub fn with_digits(mut self, digits: u32) -> Self {
+        self.digits = digits;
+        self
+    }
+
+    /// Set the time step in seconds (greater than zero).
+    pub fn with_period_secs(mut self, secs: u64) -> Self {
+        self.period_secs = secs;
+        self
+    }
+
+    /// Set the tolerated clock drift in steps.
+    pub fn with_skew_steps(mut self, steps: u64) -> Self {
+        self.skew_steps = steps;
+        self
+    }
+
+    /// Check that the parameters can produce codes.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `digits` is not between 6 and 8 or `period_secs`
+    /// is zero
+    pub fn validate(&self) -> Result<(), String> {
+        if !DIGITS_RANGE.contains(&self.digits) {
+            return Err(format!(
+                "TOTP digits must be between {} and {}",
+                DIGITS_RANGE.start(),
+                DIGITS_RANGE.end()
+            ));
+        }
+        if self.period_secs == 0 {
+            return Err("TOTP period must be greater than zero".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Generate a random base32-encoded TOTP secret.
+pub fn generate_totp_secret() -> String {
+    use rand::RngExt;
+    let mut rng = rand::rng();
+    let bytes: Vec<u8> = (0..SECRET_BYTES).map(|_| rng.random()).collect();
+    base32_encode(&bytes)
+}
+
+/// Build the `otpauth://` URI to render as a QR code during enrollment.
+///
+/// # Arguments
+///
+/// * `config` - TOTP parameters
+/// * `secret` - The base32 secret from [`generate_totp_secret`]
+/// * `account` - The account label, typically the user's email
+pub fn provisioning_uri(config: &TotpConfig, secret: &str, account: &str) -> String {
+    format!(
+        "otpauth://totp/{}:{}?secret={}&issuer={}&algorithm=SHA1&digits={}&period={}",
+        percent_encode(&config.issuer),
+        percent_encode(account),
+        secret,
+        percent_encode(&config.issuer),
+        config.digits,
+        config.period_secs
+    )
+}
+
+/// Compute the TOTP code for a Unix timestamp.
+///
+/// # Errors
+///
+/// Returns an error if the config is invalid or the secret is not valid
+/// base32
+pub fn totp_code(config: &TotpConfig, secret: &str, timestamp: u64) -> Result<String, String> {
+    config.validate()?;
+    let key = base32_decode(secret).ok_or("Invalid TOTP secret")?;
+    Ok(hotp(&key, timestamp / config.period_secs, config.digits))
+}
+
+/// Verify a TOTP code at a Unix timestamp, tolerating clock drift.
+///
+/// Returns the matched time step so callers can reject reuse of the same
+/// code (store it and refuse steps that are not greater than the last one).
+///
+/// # Errors
+///
+/// Returns an error if the config is invalid or the secret is not valid
+/// base32
+pub fn verify_totp_at(
+    config: &TotpConfig,
+    secret: &str,
+    code: &str,
+    timestamp: u64,
+) -> Result<Option<u64>, String> {
+    config.validate()?;
+    let key = base32_decode(secret).ok_or("Invalid TOTP secret")?;
+    let code = code.trim();
+    let current = timestamp / config.period_secs;
+    let first = current.saturating_sub(config.skew_steps);
+
+    for step in first..=current + config.skew_steps {
+        if constant_time_eq(hotp(&key, step, config.digits).as_bytes(), code.as_bytes()) {
+            return Ok(Some(step));
+        }
+    }
+    Ok(None)
+}
+
+/// Verify a TOTP code against the current time.
+///
+/// Returns the matched time step, like [`verify_totp_at`].
+///
+/// # Errors
+///
+/// Returns an error if the config is invalid or the secret is not valid
+/// base32
+pub fn verify_totp(config: &TotpConfig, secret: &str, code: &str) -> Result<Option<u64>, String> {
+    let now = Utc::now().timestamp().max(0) as u64;
+    verify_totp_at(config, secret, code, now)
+}
+
+/// Recovery codes: shown to the user once, stored only as hashes.
+#[derive(Debug, Clone)]
+pub struct RecoveryCodes {
+    /// Plain codes to display to the user
+    pub codes: Vec<String>,
+    /// Argon2 hashes to persist
+    pub hashes: Vec<String>,
+}
+
+/// Generate `count` recovery codes in the form `xxxxx-xxxxx`.
+///
+/// # Errors
+///
+/// Returns an error if hashing fails
+pub fn generate_recovery_codes(count: usize) -> Result<RecoveryCodes, String> {
+    use rand::RngExt;
+    const CHARSET: &[u8] = b"abcdefghjkmnpqrstuvwxyz23456789";
+    let mut rng = rand::rng();
+
+    let codes: Vec<String> = (0..count)
+        .map(|_| {
+            let raw: String = (0..RECOVERY_CODE_LEN)
+                .map(|_| CHARSET[rng.random_range(0..CHARSET.len())] as char)
+                .collect();
+            format!("{}-{}", &raw[..RECOVERY_CODE_LEN / 2], &raw[RECOVERY_CODE_LEN / 2..])
+        })
+        .collect();
+    let hashes = codes
+        .iter()
+        .map(|code| hash_password(code))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(RecoveryCodes { codes, hashes })
+}
+
+/// Check a recovery code against stored hashes.
+///
+/// Returns the index of the matching hash; remove it from storage so the
+/// code cannot be used again.
+pub fn verify_recovery_code(code: &str, hashes: &[String]) -> Option<usize> {
+    let code = code.trim().to_lowercase();
+    hashes
+        .iter()
+        .position(|hash| verify_password(&code, hash).unwrap_or(false))
+}
+
+/// Issue a short-lived token for the first login step of an MFA-enabled user.
+///
+/// The token carries `mfa_pending: true` and is refused by `jwt_middleware`
+/// and `validate_jwt`. Its random `jti` lets [`MfaVerifier`] accept it only
+/// once.
+///
+/// # Arguments
+///
+/// * `sub` - The user ID
+/// * `tenant` - Optional tenant name for multi-tenant mode
+/// * `role` - Optional role to carry over into the final session token
+///
+/// # Errors
+///
+/// Returns an error if the config is not initialized or encoding fails
+pub fn issue_mfa_pending_token(
+    sub: &str,
+    tenant: Option<&str>,
+    role: Option<&str>,
+) -> Result<String, String> {
+    let config = Config::try_get().ok_or("Config not initialized")?;
+    let claims = Claims {
+        sub: sub.to_string(),
+        tenant: tenant.map(String::from),
+        exp: (Utc::now().timestamp() + MFA_PENDING_EXPIRY_SECS) as usize,
+        role: role.map(String::from),
+        roles: Vec::new(),
+        mfa_pending: true,
+        act: None,
+        sid: None,
+        jti: Some(random_alphanumeric(PENDING_JTI_LEN)),
+    };
+    encode_jwt(&claims, config)
+}
+
+/// Validate an MFA pending token from the first login step.
+///
+/// # Errors
+///
+/// Returns `Unauthorized` if the token is invalid, expired, or not a
+/// pending token
+pub fn validate_mfa_pending_token(token: &str) -> DomainResult<Claims> {
+    let config = Config::try_get().ok_or(DomainError::ConfigNotInitialized)?;
+    let claims = decode_jwt_with_config(token, config).map_err(|_| DomainError::Unauthorized)?;
+    if !claims.mfa_pending {
+        return Err(DomainError::Unauthorized);
+    }
+    Ok(claims)
+}
+
+/// Storage for MFA replay protection.
+#[async_trait::async_trait]
+pub trait MfaStore: Send + Sync {
+    /// Record `step` as the last TOTP time step used by `account`.
+    ///
+    /// Returns `false`, keeping the stored step, if `step` is not greater
+    /// than the last recorded one.
+    async fn record_step(&self, account: &str, step: u64) -> DomainResult<bool>;
+
+    /// Record a pending token's `jti` as used until `expires_at`.
+    ///
+    /// Returns `false` if it had already been used.
+    async fn consume_pending(&self, jti: &str, expires_at: DateTime<Utc>) -> DomainResult<bool>;
+
+    /// Check whether a pending token's `jti` has already been used.
+    async fn is_pending_used(&self, jti: &str) -> DomainResult<bool>;
+}
+
+/// Checks for the second login step: single-use pending tokens, TOTP
+/// replay protection and attempt throttling.
+#[derive(Clone)]
+pub struct MfaVerifier {
+    config: TotpConfig,
+    store: Arc<dyn MfaStore>,
+    throttle: LoginThrottle,
+}
+
+impl MfaVerifier {
+    /// Create a verifier.
+    ///
+    /// Failed codes are counted by `throttle` under the account
+    /// `mfa:<tenant>/<user id>`.
+    pub fn new(config: TotpConfig, store: Arc<dyn MfaStore>, throttle: LoginThrottle) -> Self {
+        Self {
+            config,
+            store,
+            throttle,
+        }
+    }
+
+    /// Get the TOTP parameters.
+    pub fn config(&self) -> &TotpConfig {
+        &self.config
+    }
+
+    /// Check the code entered for a pending login and consume the pending
+    /// token.
+    ///
+    /// # Errors
+    ///
+    /// Returns `TooManyAttempts` if the account or IP is locked,
+    /// `InvalidCredentials` if the code is wrong or its time step was
+    /// already used, or `Unauthorized` if the pending token was already
+    /// exchanged
+    pub async fn verify(
+        &self,
+        pending: &Claims,
+        secret: &str,
+        code: &str,
+        ip: Option<&str>,
+    ) -> DomainResult<()> {
+        let (jti, account) = self.begin(pending, ip).await?;
+
+        let step = verify_totp(&self.config, secret, code).map_err(DomainError::Internal)?;
+        let fresh = match step {
+            Some(step) => self.store.record_step(&account, step).await?,
+            None => false,
+        };
+        if !fresh {
+            self.throttle.record_failure(&account, ip).await?;
+            return Err(DomainError::InvalidCredentials);
+        }
+        self.finish(pending, jti, &account).await
+    }
+
+    /// Check a recovery code entered for a pending login instead of a TOTP
+    /// code, and consume the pending token.
+    ///
+    /// Returns the index of the matching hash; remove it from storage so
+    /// the code cannot be used again.
+    ///
+    /// # Errors
+    ///
+    /// Returns `TooManyAttempts` if the account or IP is locked,
+    /// `InvalidCredentials` if the code matches none of `hashes`, or
+    /// `Unauthorized` if the pending token was already exchanged
+    pub async fn verify_recovery_code(
+        &self,
+        pending: &Claims,
+        code: &str,
+        hashes: &[String],
+        ip: Option<&str>,
+    ) -> DomainResult<usize> {
+        let (jti, account) = self.begin(pending, ip).await?;
+
+        let Some(index) = verify_recovery_code(code, hashes) else {
+            self.throttle.record_failure(&account, ip).await?;
+            return Err(DomainError::InvalidCredentials);
+        };
+        self.finish(pending, jti, &account).await?;
+        Ok(index)
+    }
+
+    /// Check the pending token and the throttle before looking at the code.
+    async fn begin<'a>(
+        &self,
+        pending: &'a Claims,
+        ip: Option<&str>,
+    ) -> DomainResult<(&'a str, String)> {
+        if !pending.mfa_pending {
+            return Err(DomainError::Unauthorized);
+        }
+        let jti = pending.jti.as_deref().ok_or(DomainError::Unauthorized)?;
+        let account = mfa_account(pending);
+        self.throttle.check(&account, ip).await?;
+        // Refuse replayed tokens before the code's time step is burned
+        if self.store.is_pending_used(jti).await? {
+            return Err(DomainError::Unauthorized);
+        }
+        Ok((jti, account))
+    }
+
+    /// Consume the pending token once the code has been accepted.
+    async fn finish(&self, pending: &Claims, jti: &str, account: &str) -> DomainResult<()> {
+        // Keep the jti until the token can no longer pass validation
+        let leeway = Validation::default().leeway as i64;
+        let keep_until =
+            DateTime::from_timestamp(pending.exp as i64 + leeway, 0).unwrap_or_else(Utc::now);
+        if !self.store.consume_pending(jti, keep_until).await? {
+            return Err(DomainError::Unauthorized);
+        }
+        self.throttle.record_success(account).await
+    }
+}
+
+/// Throttle and replay key for the subject of a pending token.
+fn mfa_account(pending: &Claims) -> String {
+    format!(
+        "mfa:{}/{}",
+        pending.tenant.as_deref().unwrap_or_default(),
+        pending.sub
+    )
+}
+
+/// Finish an MFA login by checking the TOTP code and issuing a regular JWT.
+///
+/// # Arguments
+///
+/// * `pending` - Claims from [`validate_mfa_pending_token`]
+/// * `verifier` - TOTP parameters, replay store and throttle
+/// * `secret` - The user's base32 TOTP secret
+/// * `code` - The code entered by the user
+/// * `info` - Client details; the IP is counted by the throttle and the
+///   session is recorded when the session store is initialized
+///
+/// # Errors
+///
+/// Returns the errors of [`MfaVerifier::verify`], or an error if the
+/// session cannot be started
+pub async fn complete_mfa_login(
+    pending: &Claims,
+    verifier: &MfaVerifier,
+    secret: &str,
+    code: &str,
+    info: SessionInfo,
+) -> DomainResult<String> {
+    verifier
+        .verify(pending, secret, code, info.ip.as_deref())
+        .await?;
+    issue_session_token(pending, info).await
+}
+
+/// Finish an MFA login with a recovery code and issue a regular JWT.
+///
+/// Returns the token and the index of the used code in `hashes`; remove
+/// that hash from storage so the code cannot be used again.
+///
+/// # Arguments
+///
+/// * `pending` - Claims from [`validate_mfa_pending_token`]
+/// * `verifier` - Replay store and throttle
+/// * `code` - The recovery code entered by the user
+/// * `hashes` - The user's stored recovery code hashes
+/// * `info` - Client details; the IP is counted by the throttle and the
+///   session is recorded when the session store is initialized
+///
+/// # Errors
+///
+/// Returns the errors of [`MfaVerifier::verify_recovery_code`], or an
+/// error if the session cannot be started
+pub async fn complete_mfa_login_with_recovery_code(
+    pending: &Claims,
+    verifier: &MfaVerifier,
+    code: &str,
+    hashes: &[String],
+    info: SessionInfo,
+) -> DomainResult<(String, usize)> {
+    let index = verifier
+        .verify_recovery_code(pending, code, hashes, info.ip.as_deref())
+        .await?;
+    let token = issue_session_token(pending, info).await?;
+    Ok((token, index))
+}
+
+/// Issue the session token for a pending login whose second factor passed.
+async fn issue_session_token(pending: &Claims, info: SessionInfo) -> DomainResult<String> {
+    let config = Config::try_get().ok_or(DomainError::ConfigNotInitialized)?;
+    let claims = login_claims(
+        &pending.sub,
+        pending.tenant.as_deref(),
+        pending.role.clone(),
+        pending.roles.clone(),
+        config,
+    );
+    issue_login_token(claims, info).await
+}
+
+/// In-process MFA store.
+///
+/// Only protects a single process; use [`SeaOrmMfaStore`] when requests
+/// are spread across Lambda instances.
+#[derive(Debug, Default)]
+pub struct MemoryMfaStore {
+    steps: Mutex<HashMap<String, u64>>,
+    used: Mutex<HashMap<String, DateTime<Utc>>>,
+}
+
+impl MemoryMfaStore {
+    /// Create an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait::async_trait]
+impl MfaStore for MemoryMfaStore {
+    async fn record_step(&self, account: &str, step: u64) -> DomainResult<bool> {
+        let mut steps = self
+            .steps
+            .lock()
+            .map_err(|_| DomainError::Internal("MFA store lock poisoned".into()))?;
+        match steps.get(account) {
+            Some(last) if *last >= step => Ok(false),
+            _ => {
+                steps.insert(account.to_string(), step);
+                Ok(true)
+            }
+        }
+    }
+
+    async fn consume_pending(&self, jti: &str, expires_at: DateTime<Utc>) -> DomainResult<bool> {
+        let mut used = self
+            .used
+            .lock()
+            .map_err(|_| DomainError::Internal("MFA store lock poisoned".into()))?;
+        let now = Utc::now();
+        used.retain(|_, until| *until > now);
+        if used.contains_key(jti) {
+            return Ok(false);
+        }
+        used.insert(jti.to_string(), expires_at);
+        Ok(true)
+    }
+
+    async fn is_pending_used(&self, jti: &str) -> DomainResult<bool> {
+        let used = self
+            .used
+            .lock()
+            .map_err(|_| DomainError::Internal("MFA store lock poisoned".into()))?;
+        Ok(used.get(jti).is_some_and(|until| *until > Utc::now()))
+    }
+}
+
+/// SeaORM-backed MFA store using the `mfa_totp_steps` and
+/// `mfa_pending_tokens` tables.
+///
+/// Add [`migration::Migration`] to your migrator to create the tables.
+#[derive(Debug, Clone)]
+pub struct SeaOrmMfaStore {
+    db: DatabaseConnection,
+}
+
+impl SeaOrmMfaStore {
+    /// Create a store using the given connection.
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait::async_trait]
+impl MfaStore for SeaOrmMfaStore {
+    async fn record_step(&self, account: &str, step: u64) -> DomainResult<bool> {
+        use entity::totp_step;
+
+        let step = step as i64;
+        // Only move the step forward, so concurrent logins cannot both win
+        let updated = totp_step::Entity::update_many()
+            .col_expr(
+                totp_step::Column::LastStep,
+                sea_orm::sea_query::Expr::value(step),
+            )
+            .filter(totp_step::Column::Account.eq(account))
+            .filter(totp_step::Column::LastStep.lt(step))
+            .exec(&self.db)
+            .await?;
+        if updated.rows_affected > 0 {
+            return Ok(true);
+        }
+
+        let model = totp_step::ActiveModel {
+            account: Set(account.to_string()),
+            last_step: Set(step),
+        };
+        match totp_step::Entity::insert(model).exec(&self.db).await {
+            Ok(_) => Ok(true),
+            Err(e) if matches!(e.sql_err(), Some(SqlErr::UniqueConstraintViolation(_))) => {
+                Ok(false)
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn consume_pending(&self, jti: &str, expires_at: DateTime<Utc>) -> DomainResult<bool> {
+        use entity::pending_token;
+
+        pending_token::Entity::delete_many()
+            .filter(pending_token::Column::ExpiresAt.lt(Utc::now()))
+            .exec(&self.db)
+            .await?;
+
+        let model = pending_token::ActiveModel {
+            jti: Set(jti.to_string()),
+            expires_at: Set(expires_at),
+        };
+        match pending_token::Entity::insert(model).exec(&self.db).await {
+            Ok(_) => Ok(true),
+            Err(e) if matches!(e.sql_err(), Some(SqlErr::UniqueConstraintViolation(_))) => {
+                Ok(false)
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn is_pending_used(&self, jti: &str) -> DomainResult<bool> {
+        use entity::pending_token;
+
+        Ok(pending_token::Entity::find_by_id(jti.to_string())
+            .filter(pending_token::Column::ExpiresAt.gt(Utc::now()))
+            .one(&self.db)
+            .await?
+            .is_some())
+    }
+}
+
+/// SeaORM entities for the MFA tables.
+pub mod entity {
+    /// The `mfa_totp_steps` table.
+    pub mod totp_step {
+        use sea_orm::entity::prelude::*;
+
+        /// Last TOTP time step used by an account.
+        #[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+        #[sea_orm(table_name = "mfa_totp_steps")]
+        pub struct Model {
+            /// Account key (`mfa:<tenant>/<user id>`)
+            #[sea_orm(primary_key, auto_increment = false)]
+            pub account: String,
+            /// Last accepted time step
+            pub last_step: i64,
+        }
+
+        /// No relations.
+        #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+        pub enum Relation {}
+
+        impl ActiveModelBehavior for ActiveModel {}
+    }
+
+    /// The `mfa_pending_tokens` table.
+    pub mod pending_token {
+        use sea_orm::entity::prelude::*;
+
+        /// A consumed MFA pending token ID.
+        #[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+        #[sea_orm(table_name = "mfa_pending_tokens")]
+        pub struct Model {
+            /// Token ID (`jti` claim)
+            #[sea_orm(primary_key, auto_increment = false)]
+            pub jti: String,
+            /// When the row can be pruned
+            pub expires_at: DateTimeUtc,
+        }
+
+        /// No relations.
+        #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+        pub enum Relation {}
+
+        impl ActiveModelBehavior for ActiveModel {}
+    }
+}
+
+/// Migration creating the `mfa_totp_steps` and `mfa_pending_tokens` tables.
+pub mod migration {
+    use sea_orm_migration::prelude::*;
+
+    /// Creates the `mfa_totp_steps` and `mfa_pending_tokens` tables.
+    pub struct Migration;
+
+    impl MigrationName for Migration {
+        fn name(&self) -> &str {
+            "m20261018_000010_create_mfa_tables"
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl MigrationTrait for Migration {
+        async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+            manager
+                .create_table(
+                    Table::create()
+                        .table(MfaTotpSteps::Table)
+                        .if_not_exists()
+                        .col(
+                            ColumnDef::new(MfaTotpSteps::Account)
+                                .string_len(255)
+                                .not_null()
+                                .primary_key(),
+                        )
+                        .col(
+                            ColumnDef::new(MfaTotpSteps::LastStep)
+                                .big_integer()
+                                .not_null(),
+                        )
+                        .to_owned(),
+                )
+                .await?;
+            manager
+                .create_table(
+                    Table::create()
+                        .table(MfaPendingTokens::Table)
+                        .if_not_exists()
+                        .col(
+                            ColumnDef::new(MfaPendingTokens::Jti)
+                                .string_len(64)
+                                .not_null()
+                                .primary_key(),
+                        )
+                        .col(
+                            ColumnDef::new(MfaPendingTokens::ExpiresAt)
+                                .timestamp()
+                                .not_null(),
+                        )
+                        .to_owned(),
+                )
+                .await
+        }
+
+        async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+            manager
+                .drop_table(Table::drop().table(MfaPendingTokens::Table).to_owned())
+                .await?;
+            manager
+                .drop_table(Table::drop().table(MfaTotpSteps::Table).to_owned())
+                .await
+        }
+    }
+
+    #[derive(Iden)]
+    enum MfaTotpSteps {
+        Table,
+        Account,
+        LastStep,
+    }
+
+    #[derive(Iden)]
+    enum MfaPendingTokens {
+        Table,
+        Jti,
+        ExpiresAt,
+    }
+}
+
+/// RFC 4226 HOTP value for a counter.
+fn hotp(key: &[u8], counter: u64, digits: u32) -> String {
+    let mut mac = Hmac::<Sha1>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(&counter.to_be_bytes());
+    let hash = mac.finalize().into_bytes();
+
+    let offset = (hash[hash.len() - 1] & 0x0f) as usize;
+    let binary = u32::from_be_bytes([
+        hash[offset] & 0x7f,
+        hash[offset + 1],
+        hash[offset + 2],
+        hash[offset + 3],
+    ]);
+    let value = binary % 10u32.pow(digits);
+    format!("{:0width$}", value, width = digits as usize)
+}
+
+pub(super) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn base32_encode(data: &[u8]) -> String {
+    let mut out = String::with_capacity(data.len().div_ceil(5) * 8);
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for &byte in data {
+        buffer = (buffer << 8) | byte as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out.push(BASE32_ALPHABET[((buffer >> bits) & 0x1f) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        out.push(BASE32_ALPHABET[((buffer << (5 - bits)) & 0x1f) as usize] as char);
+    }
+    out
+}
+
+fn base32_decode(input: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(input.len() * 5 / 8);
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for c in input.chars().filter(|c| !c.is_whitespace() && *c != '=') {
+        let upper = c.to_ascii_uppercase() as u8;
+        let value = BASE32_ALPHABET.iter().position(|&b| b == upper)? as u32;
+        buffer = (buffer << 5) | value;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((buffer >> bits) as u8);
+        }
+    }
+    if out.is_empty() {
+        None
+    } else {
+        Some(out)
+    }
+}
+
+fn percent_encode(input: &str) -> String {
+    input
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// RFC 6238 test secret: ASCII "12345678901234567890"
+    const RFC_SECRET: &str = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ";
+
+    #[test]
+    fn test_base32_roundtrip() {
+        assert_eq!(base32_encode(b"12345678901234567890"), RFC_SECRET);
+        assert_eq!(
+            base32_decode(RFC_SECRET).unwrap(),
+            b"12345678901234567890".to_vec()
+        );
+        assert_eq!(
+            base32_decode(&RFC_SECRET.to_lowercase()).unwrap(),
+            b"12345678901234567890".to_vec()
+        );
+        assert!(base32_decode("not base32!").is_none());
+    }
+
+    #[test]
+    fn test_rfc6238_vectors() {
+        let config = TotpConfig::new("Test").with_digits(8);
+        assert_eq!(totp_code(&config, RFC_SECRET, 59).unwrap(), "94287082");
+        assert_eq!(totp_code(&config, RFC_SECRET, 1111111109).unwrap(), "07081804");
+        assert_eq!(totp_code(&config, RFC_SECRET, 1234567890).unwrap(), "89005924");
+    }
+
+    #[test]
+    fn test_verify_with_drift_window() {
+        let config = TotpConfig::new("Test");
+        let previous = totp_code(&config, RFC_SECRET, 1111111109 - 30).unwrap();
+        let too_old = totp_code(&config, RFC_SECRET, 1111111109 - 90).unwrap();
+
+        assert!(verify_totp_at(&config, RFC_SECRET, &previous, 1111111109)
+            .unwrap()
+            .is_some());
+        assert!(verify_totp_at(&config, RFC_SECRET, &too_old, 1111111109)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_generated_secret_verifies() {
+        let config = TotpConfig::new("Test");
+        let secret = generate_totp_secret();
+        assert_eq!(secret.len(), 32);
+
+        let code = totp_code(&config, &secret, 1_700_000_000).unwrap();
+        assert_eq!(
+            verify_totp_at(&config, &secret, &code, 1_700_000_000).unwrap(),
+            Some(1_700_000_000 / 30)
+        );
+    }
+
+    #[test]
+    fn test_provisioning_uri() {
+        let config = TotpConfig::new("Acme Corp");
+        let uri = provisioning_uri(&config, "ABC234", "jane@example.com");
+        assert_eq!(
+            uri,
+            "otpauth://totp/Acme%20Corp:jane%40example.com?secret=ABC234&issuer=Acme%20Corp&algorithm=SHA1&digits=6&period=30"
+        );
+    }
+
+    #[test]
+    fn test_recovery_codes() {
+        let recovery = generate_recovery_codes(3).unwrap();
+        assert_eq!(recovery.codes.len(), 3);
+        assert_eq!(recovery.codes[0].len(), RECOVERY_CODE_LEN + 1);
+
+        let second = recovery.codes[1].to_uppercase();
+        assert_eq!(verify_recovery_code(&second, &recovery.hashes), Some(1));
+        assert_eq!(verify_recovery_code("aaaaa-aaaaa", &recovery.hashes), None);
+    }
+
+    fn verifier(max_attempts: u32) -> MfaVerifier {
+        use crate::auth::throttle::{MemoryAttemptStore, ThrottleConfig};
+
+        let throttle = LoginThrottle::new(Arc::new(MemoryAttemptStore::new()))
+            .with_config(ThrottleConfig::new().with_max_attempts(max_attempts, 100));
+        MfaVerifier::new(
+            TotpConfig::new("Test"),
+            Arc::new(MemoryMfaStore::new()),
+            throttle,
+        )
+    }
+
+    fn current_code(secret: &str) -> String {
+        let now = Utc::now().timestamp() as u64;
+        totp_code(&TotpConfig::new("Test"), secret, now).unwrap()
+    }
+
+    #[test]
+    fn test_config_validation() {
+        assert!(TotpConfig::new("Test").validate().is_ok());
+        let too_long = TotpConfig::new("Test").with_digits(10);
+        assert!(totp_code(&too_long, RFC_SECRET, 59).is_err());
+        let no_period = TotpConfig::new("Test").with_period_secs(0);
+        assert!(totp_code(&no_period, RFC_SECRET, 59).is_err());
+        assert!(verify_totp_at(&no_period, RFC_SECRET, "123456", 59).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_pending_token_flow() {
+        crate::auth::tests::init_test_config();
+
+        let pending = issue_mfa_pending_token("42", None, Some("admin")).unwrap();
+
+        // Regular validation refuses the pending token
+        assert!(crate::auth::validate_jwt(&pending).is_err());
+
+        let claims = validate_mfa_pending_token(&pending).unwrap();
+        assert_eq!(claims.sub, "42");
+        assert!(claims.jti.is_some());
+
+        let verifier = verifier(5);
+        let secret = generate_totp_secret();
+        assert!(matches!(
+            complete_mfa_login(&claims, &verifier, &secret, "000000x", SessionInfo::new()).await,
+            Err(DomainError::InvalidCredentials)
+        ));
+
+        let code = current_code(&secret);
+        let session = complete_mfa_login(&claims, &verifier, &secret, &code, SessionInfo::new())
+            .await
+            .unwrap();
+        let session_claims = crate::auth::validate_jwt(&session).unwrap();
+        assert_eq!(session_claims.sub, "42");
+        assert_eq!(session_claims.role.as_deref(), Some("admin"));
+        assert!(!session_claims.mfa_pending);
+
+        // A regular session token is not accepted as a pending token
+        assert!(validate_mfa_pending_token(&session).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_pending_token_and_code_are_single_use() {
+        crate::auth::tests::init_test_config();
+        let verifier = verifier(5);
+        let secret = generate_totp_secret();
+        let code = current_code(&secret);
+
+        let first = validate_mfa_pending_token(&issue_mfa_pending_token("42", None, None).unwrap())
+            .unwrap();
+        complete_mfa_login(&first, &verifier, &secret, &code, SessionInfo::new())
+            .await
+            .unwrap();
+
+        // The same code cannot mint a second session, even with a new pending token
+        let second =
+            validate_mfa_pending_token(&issue_mfa_pending_token("42", None, None).unwrap())
+                .unwrap();
+        assert!(matches!(
+            complete_mfa_login(&second, &verifier, &secret, &code, SessionInfo::new()).await,
+            Err(DomainError::InvalidCredentials)
+        ));
+
+        // A used pending token is refused even with a fresh code
+        let later = totp_code(
+            verifier.config(),
+            &secret,
+            Utc::now().timestamp() as u64 + 30,
+        )
+        .unwrap();
+        assert!(matches!(
+            complete_mfa_login(&first, &verifier, &secret, &later, SessionInfo::new()).await,
+            Err(DomainError::Unauthorized)
+        ));
+
+        // ...and the replay did not burn that code's time step
+        let third =
+            validate_mfa_pending_token(&issue_mfa_pending_token("42", None, None).unwrap())
+                .unwrap();
+        complete_mfa_login(&third, &verifier, &secret, &later, SessionInfo::new())
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_recovery_code_login() {
+        crate::auth::tests::init_test_config();
+        let verifier = verifier(5);
+        let recovery = generate_recovery_codes(2).unwrap();
+        let pending =
+            validate_mfa_pending_token(&issue_mfa_pending_token("9", None, None).unwrap()).unwrap();
+
+        assert!(matches!(
+            complete_mfa_login_with_recovery_code(
+                &pending,
+                &verifier,
+                "aaaaa-aaaaa",
+                &recovery.hashes,
+                SessionInfo::new()
+            )
+            .await,
+            Err(DomainError::InvalidCredentials)
+        ));
+
+        let (token, index) = complete_mfa_login_with_recovery_code(
+            &pending,
+            &verifier,
+            &recovery.codes[1],
+            &recovery.hashes,
+            SessionInfo::new(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(index, 1);
+        assert_eq!(crate::auth::validate_jwt(&token).unwrap().sub, "9");
+
+        // The pending token is consumed
+        assert!(matches!(
+            complete_mfa_login_with_recovery_code(
+                &pending,
+                &verifier,
+                &recovery.codes[0],
+                &recovery.hashes,
+                SessionInfo::new()
+            )
+            .await,
+            Err(DomainError::Unauthorized)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_wrong_codes_are_throttled() {
+        crate::auth::tests::init_test_config();
+        let verifier = verifier(3);
+        let secret = generate_totp_secret();
+        let pending =
+            validate_mfa_pending_token(&issue_mfa_pending_token("7", None, None).unwrap()).unwrap();
+
+        for _ in 0..2 {
+            assert!(matches!(
+                complete_mfa_login(&pending, &verifier, &secret, "000000x", SessionInfo::new())
+                    .await,
+                Err(DomainError::InvalidCredentials)
+            ));
+        }
+        assert!(matches!(
+            complete_mfa_login(&pending, &verifier, &secret, "000000x", SessionInfo::new()).await,
+            Err(DomainError::TooManyAttempts { .. })
+        ));
+
+        // Locked out even with the right code
+        let code = current_code(&secret);
+        assert!(matches!(
+            complete_mfa_login(&pending, &verifier, &secret, &code, SessionInfo::new()).await,
+            Err(DomainError::TooManyAttempts { .. })
+        ));
+    }
+}
@@ -1,18 +1,28 @@
 //! JWT middleware for extracting authentication from HTTP requests.
 
+use super::api_key::{extract_api_key, verify_api_key, ApiKeyStore};
 use super::claims::{validate_jwt_with_config, JwtResult};
+use super::cookie::{extract_cookie, is_safe_method, verify_csrf, CookieConfig};
+use super::roles::AuthRole;
+use super::sessions::check_token_session;
+#[cfg(feature = "admin-override")]
+use super::sessions::SessionInfo;
 use crate::config::Config;
 use lambda_http::Request;
 
 #[cfg(feature = "admin-override")]
 use super::admin_override::{
-    extract_admin_override_header, validate_admin_override_token, AdminOverride,
-    AdminOverrideConfig,
+    extract_admin_override_header, redeem_admin_override_token, AdminOverride,
+    AdminOverrideConfig, OverrideNonceStore,
 };
 
-/// JWT middleware that extracts user_id and tenant from Authorization header.
+/// JWT middleware that extracts user_id, tenant and role from Authorization header.
 ///
-/// Returns JwtResult with both values (tenant is None for single-tenant mode).
+/// Returns JwtResult with all values (tenant is None for single-tenant mode,
+/// role is None when the token carries no `role`/`roles` claim).
+/// Tokens carrying a `sid` claim are checked against the session store and
+/// rejected once their session has been revoked; tokens without one are
+/// rejected after [`require_sessions`](super::sessions::require_sessions).
 ///
 /// # Arguments
 ///
@@ -20,28 +30,18 @@ use super::admin_override::{
 ///
 /// # Returns
 ///
-/// A JwtResult containing the user_id and tenant if authenticated
+/// A JwtResult containing the user_id, tenant and role if authenticated
 ///
 /// # Errors
 ///
-/// Returns an error if the token is invalid
+/// Returns an error if the token is invalid or its session revoked
 pub async fn jwt_middleware(request: &Request) -> Result<JwtResult, String> {
     let config = Config::try_get().ok_or("Config not initialized")?;
 
     if let Some(auth_header) = request.headers().get("Authorization") {
         if let Ok(auth_header_str) = auth_header.to_str() {
             if let Some(token) = auth_header_str.strip_prefix("Bearer ") {
-                match validate_jwt_with_config(token, config) {
-                    Ok(claims) => {
-                        return Ok(JwtResult {
-                            user_id: Some(claims.sub),
-                            tenant: claims.tenant,
-                        });
-                    }
-                    Err(e) => {
-                        return Err(e);
-                    }
-                }
+                return jwt_result_from_token(token, config).await;
             }
         }
     }
@@ -49,6 +49,115 @@ pub async fn jwt_middleware(request: &Request) -> Result<JwtResult, String> {
     Ok(JwtResult::empty())
 }
 
+/// JWT middleware with cookie-based sessions for browser clients.
+///
+/// A Bearer `Authorization` header still takes precedence. Without one, the
+/// token is read from the session cookie named in `cookies`; for every
+/// method other than GET, HEAD and OPTIONS the request must also pass the
+/// double-submit CSRF check (the CSRF header must equal the CSRF cookie).
+/// GET requests are only safe because
+/// [`graphql_request_from_get`](crate::handler::graphql_request_from_get)
+/// refuses mutations; do not execute GET mutations through another parser.
+///
+/// # Arguments
+///
+/// * `request` - The Lambda HTTP request
+/// * `cookies` - Cookie and CSRF settings
+///
+/// # Errors
+///
+/// Returns an error if the token is invalid or the CSRF check fails
+pub async fn jwt_middleware_with_cookies(
+    request: &Request,
+    cookies: &CookieConfig,
+) -> Result<JwtResult, String> {
+    if extract_bearer_token(request).is_some() {
+        return jwt_middleware(request).await;
+    }
+
+    let Some(token) = extract_cookie(request, &cookies.name) else {
+        return Ok(JwtResult::empty());
+    };
+
+    let config = Config::try_get().ok_or("Config not initialized")?;
+    if !is_safe_method(request.method()) {
+        verify_csrf(request, cookies)?;
+    }
+    jwt_result_from_token(&token, config).await
+}
+
+/// Authenticate a raw bearer token, as [`jwt_middleware`] does for the
+/// `Authorization` header.
+///
+/// Use this where tokens do not arrive in HTTP headers, e.g. the
+/// `connection_init` payload of a WebSocket connection.
+///
+/// # Errors
+///
+/// Returns an error if the token is invalid or its session revoked
+pub async fn authenticate_token(token: &str) -> Result<JwtResult, String> {
+    let config = Config::try_get().ok_or("Config not initialized")?;
+    jwt_result_from_token(token, config).await
+}
+
+async fn jwt_result_from_token(token: &str, config: &Config) -> Result<JwtResult, String> {
+    let claims = validate_jwt_with_config(token, config)?;
+    check_token_session(&claims).await?;
+    let role = AuthRole::from_claims(&claims);
+    Ok(JwtResult {
+        user_id: Some(claims.sub),
+        tenant: claims.tenant,
+        role,
+        scopes: Vec::new(),
+        impersonator: claims.act.map(|act| act.sub),
+        session_id: claims.sid,
+    })
+}
+
+/// Authentication middleware accepting API keys as well as Bearer JWTs.
+///
+/// If the request carries `Authorization: ApiKey <key>` or `X-Api-Key`,
+/// the key is verified against `store` and the result has the key owner as
+/// `user_id`, [`AuthRole::ApiKey`] as `role`, the key's scopes and the
+/// store's [`tenant`](ApiKeyStore::tenant). Keys do not get their owner's
+/// ownership rights, see
+/// [`ContextData::owner_user_id`](crate::graphql::ContextData::owner_user_id).
+/// Otherwise this behaves exactly like [`jwt_middleware`].
+///
+/// # Arguments
+///
+/// * `request` - The Lambda HTTP request
+/// * `store` - API key storage (typically the tenant's `SeaOrmApiKeyStore`)
+///
+/// # Errors
+///
+/// Returns an error if an API key or token is present but invalid
+pub async fn jwt_middleware_with_api_keys(
+    request: &Request,
+    store: &dyn ApiKeyStore,
+) -> Result<JwtResult, String> {
+    let Some(raw_key) = extract_api_key(request) else {
+        return jwt_middleware(request).await;
+    };
+
+    let key = verify_api_key(store, &raw_key).await.map_err(|e| {
+        tracing::debug!(error = %e, "API key verification failed");
+        "Invalid API key".to_string()
+    })?;
+
+    Ok(JwtResult {
+        user_id: Some(key.owner_id.to_string()),
+        tenant: store.tenant().map(String::from),
+        role: Some(AuthRole::ApiKey {
+            key_id: key.id,
+            owner_id: key.owner_id,
+        }),
+        scopes: key.scopes,
+        impersonator: None,
+        session_id: None,
+    })
+}
+
 /// Extract Bearer token from Authorization header.
 ///
 /// # Arguments
@@ -69,25 +178,40 @@ pub fn extract_bearer_token(request: &Request) -> Option<String> {
 
 /// Extract and validate an admin override token from the request.
 ///
-/// Reads the `X-Admin-Override` header and validates it against the config.
+/// Reads the `X-Admin-Override` header, validates it against the config and
+/// consumes its nonce so the token cannot be replayed.
 /// Returns `None` if the header is not present, or an error if it is present but invalid.
 ///
 /// # Arguments
 ///
 /// * `request` - The Lambda HTTP request
 /// * `config` - Admin override configuration
+/// * `store` - Store recording consumed override tokens
+/// * `auth` - The authenticated user (from [`jwt_middleware`]); its tenant
+///   is recorded with the audit event, along with the client IP and user agent
 ///
 /// # Errors
 ///
-/// Returns an error if the header is present but the token is invalid or expired
+/// Returns an error if the header is present but the token is invalid, expired,
+/// already used, or bound to another user
 #[cfg(feature = "admin-override")]
-pub fn admin_override_middleware(
+pub async fn admin_override_middleware(
     request: &Request,
     config: &AdminOverrideConfig,
+    store: &dyn OverrideNonceStore,
+    auth: &JwtResult,
 ) -> Result<Option<AdminOverride>, String> {
     match extract_admin_override_header(request) {
         Some(token) => {
-            let admin_override = validate_admin_override_token(&token, config)?;
+            let admin_override = redeem_admin_override_token(
+                &token,
+                config,
+                store,
+                auth.user_id.as_deref(),
+                auth.tenant.as_deref(),
+                &SessionInfo::from_request(request),
+            )
+            .await?;
             tracing::debug!(
                 admin_id = admin_override.admin_id,
                 admin_name = %admin_override.admin_name,
@@ -10,6 +10,9 @@
 //! // Issue a token
 //! let token = issue_jwt("user_123", Some("tenant_name"))?;
 //!
+//! // Issue a token carrying a role claim
+//! let admin_token = issue_jwt_with_role("1", None, "admin")?;
+//!
 //! // Validate in middleware (done automatically by the framework)
 //! let result = jwt_middleware(&request).await?;
 //! if let Some(user_id) = result.user_id {
@@ -24,13 +27,54 @@ mod password;
 /// Multi-role authentication support.
 pub mod roles;
 
+/// Permission-based authorization (RBAC).
+pub mod permissions;
+
+/// Resource ownership and attribute-based access policies.
+pub mod policy;
+
+/// API key authentication for machine-to-machine clients.
+pub mod api_key;
+
+/// TOTP multi-factor authentication.
+pub mod mfa;
+
+/// Single-use tokens for password reset and email verification.
+pub mod tokens;
+
+/// Passwordless login via emailed magic links.
+pub mod magic_link;
+
+/// Login brute-force protection and account lockout.
+pub mod throttle;
+
+/// Cookie-based sessions with CSRF protection for browser clients.
+pub mod cookie;
+
+/// OAuth2 / OpenID Connect social login (authorization code + PKCE).
+pub mod oauth;
+
+/// Impersonation ("log in as user") for support staff.
+pub mod impersonation;
+
+/// Device/session management: list and revoke active sessions.
+pub mod sessions;
+
 /// Admin override for temporary elevated access.
 #[cfg(feature = "admin-override")]
 pub mod admin_override;
 
-pub use claims::{Claims, JwtResult};
-pub use middleware::jwt_middleware;
-pub use password::{hash_password, verify_password, generate_temp_password};
+pub use claims::{ActorClaim, Claims, JwtResult};
+pub use middleware::{
+    authenticate_token, jwt_middleware, jwt_middleware_with_api_keys, jwt_middleware_with_cookies,
+};
+#[cfg(feature = "admin-override")]
+pub use middleware::admin_override_middleware;
+pub use password::{
+    generate_temp_password, hash_password, init_password_hashing, password_hashing,
+    verify_and_rehash, verify_password, PasswordHashConfig, PasswordVerification,
+};
+pub(crate) use password::random_alphanumeric;
 
 use crate::config::Config;
 use chrono::{Duration, Utc};
@@ -51,18 +95,87 @@ use jsonwebtoken::{encode, EncodingKey, Header};
 ///
 /// Returns an error if the config is not initialized or token encoding fails
 pub fn issue_jwt(sub: &str, tenant: Option<&str>) -> Result<String, String> {
+    issue_jwt_with_claims(sub, tenant, None, Vec::new())
+}
+
+/// Issues a JWT carrying an explicit `role` claim.
+///
+/// The role is read back by [`jwt_middleware`] into [`JwtResult::role`]
+/// after a single signature check, so all roles can share one secret.
+///
+/// # Arguments
+///
+/// * `sub` - The JWT subject, typically the user ID
+/// * `tenant` - Optional tenant name for multi-tenant mode
+/// * `role` - The role name (e.g. "user", "admin", or a custom role)
+///
+/// # Errors
+///
+/// Returns an error if the config is not initialized or token encoding fails
+pub fn issue_jwt_with_role(sub: &str, tenant: Option<&str>, role: &str) -> Result<String, String> {
+    issue_jwt_with_claims(sub, tenant, Some(role.to_string()), Vec::new())
+}
+
+/// Issues a JWT carrying a `roles` claim with several role names.
+///
+/// The first role is treated as the primary role when populating
+/// [`JwtResult::role`].
+///
+/// # Arguments
+///
+/// * `sub` - The JWT subject, typically the user ID
+/// * `tenant` - Optional tenant name for multi-tenant mode
+/// * `roles` - The role names granted to the subject
+///
+/// # Errors
+///
+/// Returns an error if the config is not initialized or token encoding fails
+pub fn issue_jwt_with_roles(
+    sub: &str,
+    tenant: Option<&str>,
+    roles: &[&str],
+) -> Result<String, String> {
+    let roles = roles.iter().map(|r| r.to_string()).collect();
+    issue_jwt_with_claims(sub, tenant, None, roles)
+}
+
+fn issue_jwt_with_claims(
+    sub: &str,
+    tenant: Option<&str>,
+    role: Option<String>,
+    roles: Vec<String>,
+) -> Result<String, String> {
     let config = Config::try_get().ok_or("Config not initialized")?;
+    encode_jwt(&login_claims(sub, tenant, role, roles, config), config)
+}
 
+/// Claims for a regular login token expiring after `JWT_EXP_DAYS`.
+pub(crate) fn login_claims(
+    sub: &str,
+    tenant: Option<&str>,
+    role: Option<String>,
+    roles: Vec<String>,
+    config: &Config,
+) -> Claims {
     let exp_timestamp = Utc::now() + Duration::days(config.jwt.exp_days);
-    let claims = Claims {
+    Claims {
         sub: sub.to_string(),
         tenant: tenant.map(String::from),
         exp: exp_timestamp.timestamp() as usize,
-    };
+        role,
+        roles,
+        mfa_pending: false,
+        act: None,
+        sid: None,
+        jti: None,
+    }
+}
 
+/// Sign claims with the configured JWT secret.
+pub(crate) fn encode_jwt(claims: &Claims, config: &Config) -> Result<String, String> {
     encode(
         &Header::default(),
-        &claims,
+        claims,
         &EncodingKey::from_secret(config.jwt.secret.as_ref()),
     )
     .map_err(|_| "Failed to issue token".to_string())
@@ -85,3 +198,52 @@ pub fn validate_jwt(token: &str) -> Result<Claims, String> {
     let config = Config::try_get().ok_or("Config not initialized")?;
     claims::validate_jwt_with_config(token, config)
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::config::ConfigBuilder;
+
+    /// Initialize the global config with a fixed JWT secret shared by all tests.
+    pub(crate) fn init_test_config() {
+        let config = ConfigBuilder::new()
+            .jwt_secret("test-jwt-secret")
+            .build()
+            .unwrap();
+        let _ = Config::init_with(config);
+    }
+
+    #[test]
+    fn test_issue_jwt_without_role() {
+        init_test_config();
+        let token = issue_jwt("42", Some("acme")).unwrap();
+        let claims = validate_jwt(&token).unwrap();
+        assert_eq!(claims.sub, "42");
+        assert_eq!(claims.tenant.as_deref(), Some("acme"));
+        assert!(roles::AuthRole::from_claims(&claims).is_none());
+    }
+
+    #[test]
+    fn test_issue_jwt_with_role() {
+        init_test_config();
+        let token = issue_jwt_with_role("42", None, "admin").unwrap();
+        let claims = validate_jwt(&token).unwrap();
+        assert_eq!(claims.role.as_deref(), Some("admin"));
+        assert_eq!(
+            roles::AuthRole::from_claims(&claims),
+            Some(roles::AuthRole::Admin(42))
+        );
+    }
+
+    #[test]
+    fn test_issue_jwt_with_roles() {
+        init_test_config();
+        let token = issue_jwt_with_roles("7", None, &["editor", "user"]).unwrap();
+        let claims = validate_jwt(&token).unwrap();
+        assert!(claims.has_role("user"));
+        assert_eq!(
+            roles::AuthRole::from_claims(&claims),
+            Some(roles::AuthRole::Custom("editor".to_string(), 7))
+        );
+    }
+}
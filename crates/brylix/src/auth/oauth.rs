@@ -0,0 +1,824 @@
+//! OAuth2 / OpenID Connect social login (authorization code + PKCE).
+//!
+//! The flow has two steps:
+//!
+//! 1. [`authorization_request`] builds the provider URL to redirect to, with
+//!    a random `state`, a PKCE challenge and (for OpenID providers) a `nonce`.
+//!    The returned [`PendingAuthorization`] holds the secrets needed for the
+//!    callback; keep it in an `HttpOnly` cookie via
+//!    [`PendingAuthorization::encode`], which signs it with the JWT secret.
+//! 2. On the callback, [`complete_oauth_login`] checks the state, exchanges the
+//!    code (sending the PKCE verifier), checks the nonce, fetches the user
+//!    profile, hands it to your [`OAuthAccountLinker`] and issues a regular
+//!    session with [`issue_jwt`](super::issue_jwt).
+//!
+//! HTTP calls go through the [`OAuthHttpClient`] trait. Enable the `oauth`
+//! feature for the `reqwest`-based [`ReqwestOAuthClient`].
+//!
+//! # Usage
+//!
+//! ```rust,ignore
+//! use brylix::auth::oauth::{authorization_request, complete_oauth_login, OAuthProvider, PendingAuthorization};
+//!
+//! let google = OAuthProvider::google(client_id, client_secret, "https://app.acme.com/auth/google/callback");
+//!
+//! // GET /auth/google
+//! let (url, pending) = authorization_request(&google)?;
+//! // redirect to `url`, set cookie `oauth_pending=pending.encode()?`
+//!
+//! // GET /auth/google/callback?code=...&state=...
+//! let pending = PendingAuthorization::decode(&cookie_value)?;
+//! let login = complete_oauth_login(&ReqwestOAuthClient::new(), &linker, &google, &pending, &state, &code, None, SessionInfo::from_request(&request)).await?;
+//! // login.token is a normal session JWT
+//! ```
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{Duration, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use super::mfa::constant_time_eq;
+use super::password::random_alphanumeric;
+use super::sessions::{issue_login_token, SessionInfo};
+use crate::audit::{actions, AuditEvent};
+use crate::config::Config;
+use crate::errors::{DomainError, DomainResult};
+
+/// Length of generated `state` and `nonce` values.
+const STATE_LEN: usize = 32;
+
+/// Length of generated PKCE verifiers (RFC 7636 allows 43-128).
+const VERIFIER_LEN: usize = 64;
+
+/// How long a pending authorization stays valid.
+const PENDING_TTL_MINUTES: i64 = 10;
+
+/// An OAuth2 provider configuration.
+#[derive(Debug, Clone)]
+pub struct OAuthProvider {
+    /// Provider name (e.g. "google"), recorded on linked accounts
+    pub name: String,
+    /// OAuth client ID
+    pub client_id: String,
+    /// OAuth client secret (optional for public clients using PKCE only)
+    pub client_secret: Option<String>,
+    /// Authorization endpoint
+    pub auth_url: String,
+    /// Token endpoint
+    pub token_url: String,
+    /// User profile endpoint
+    pub userinfo_url: String,
+    /// Requested scopes
+    pub scopes: Vec<String>,
+    /// Callback URL registered with the provider
+    pub redirect_uri: String,
+    /// Send and verify an OpenID Connect `nonce`
+    pub use_nonce: bool,
+}
+
+impl OAuthProvider {
+    /// Create a custom provider.
+    pub fn new(
+        name: impl Into<String>,
+        client_id: impl Into<String>,
+        auth_url: impl Into<String>,
+        token_url: impl Into<String>,
+        userinfo_url: impl Into<String>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            client_id: client_id.into(),
+            client_secret: None,
+            auth_url: auth_url.into(),
+            token_url: token_url.into(),
+            userinfo_url: userinfo_url.into(),
+            scopes: Vec::new(),
+            redirect_uri: String::new(),
+            use_nonce: false,
+        }
+    }
+
+    /// Google (OpenID Connect).
+    pub fn google(
+        client_id: impl Into<String>,
+        client_secret: impl Into<String>,
+        redirect_uri: impl Into<String>,
+    ) -> Self {
+        Self::new(
+            "google",
+            client_id,
+            "https://accounts.google.com/o/oauth2/v2/auth",
+            "https://oauth2.googleapis.com/token",
+            "https://openidconnect.googleapis.com/v1/userinfo",
+        )
+        .with_client_secret(client_secret)
+        .with_redirect_uri(redirect_uri)
+        .with_scopes(["openid", "email", "profile"])
+        .with_nonce(true)
+    }
+
+    /// GitHub (OAuth2 only; no `nonce`).
+    pub fn github(
+        client_id: impl Into<String>,
+        client_secret: impl Into<String>,
+        redirect_uri: impl Into<String>,
+    ) -> Self {
+        Self::new(
+            "github",
+            client_id,
+            "https://github.com/login/oauth/authorize",
+            "https://github.com/login/oauth/access_token",
+            "https://api.github.com/user",
+        )
+        .with_client_secret(client_secret)
+        .with_redirect_uri(redirect_uri)
+        .with_scopes(["read:user", "user:email"])
+    }
+
+    /// Microsoft identity platform (OpenID Connect).
+    ///
+    /// `tenant` is a directory ID, or `common` / `organizations` / `consumers`.
+    pub fn microsoft(
+        client_id: impl Into<String>,
+        client_secret: impl Into<String>,
+        redirect_uri: impl Into<String>,
+        tenant: &str,
+    ) -> Self {
+        Self::new(
+            "microsoft",
+            client_id,
+            format!(
+                "https://login.microsoftonline.com/{}/oauth2/v2.0/authorize",
+                tenant
+            ),
+            format!(
+                "https://login.microsoftonline.com/{}/oauth2/v2.0/token",
+                tenant
+            ),
+            "https://graph.microsoft.com/oidc/userinfo",
+        )
+        .with_client_secret(client_secret)
+        .with_redirect_uri(redirect_uri)
+        .with_scopes(["openid", "email", "profile"])
+        .with_nonce(true)
+    }
+
+    /// Set the client secret.
+    pub fn with_client_secret(mut self, secret: impl Into<String>) -> Self {
+        self.client_secret = Some(secret.into());
+        self
+    }
+
+    /// Set the callback URL.
+    pub fn with_redirect_uri(mut self, redirect_uri: impl Into<String>) -> Self {
+        self.redirect_uri = redirect_uri.into();
+        self
+    }
+
+    /// Replace the requested scopes.
+    pub fn with_scopes<I, S>(mut self, scopes: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.scopes = scopes.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Enable or disable the OpenID Connect `nonce`.
+    pub fn with_nonce(mut self, use_nonce: bool) -> Self {
+        self.use_nonce = use_nonce;
+        self
+    }
+}
+
+/// Secrets kept between the redirect and the callback.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PendingAuthorization {
+    /// Provider name
+    pub provider: String,
+    /// Expected `state` parameter
+    pub state: String,
+    /// Expected ID token `nonce`
+    pub nonce: Option<String>,
+    /// PKCE code verifier
+    pub pkce_verifier: String,
+    /// Expiry (Unix seconds)
+    pub expires_at: i64,
+}
+
+impl PendingAuthorization {
+    /// Serialize and sign for storage in a cookie.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ConfigNotInitialized` if the config is missing
+    pub fn encode(&self) -> DomainResult<String> {
+        let payload = serde_json::to_vec(self)
+            .map_err(|e| DomainError::Internal(format!("Failed to encode OAuth state: {}", e)))?;
+        let payload = URL_SAFE_NO_PAD.encode(payload);
+        let signature = sign(signing_secret()?, &payload);
+        Ok(format!("{}.{}", payload, signature))
+    }
+
+    /// Verify and deserialize a value produced by [`encode`](Self::encode).
+    ///
+    /// # Errors
+    ///
+    /// Returns `InvalidCredentials` if the value is malformed, tampered with,
+    /// or expired
+    pub fn decode(value: &str) -> DomainResult<Self> {
+        let (payload, signature) = value
+            .split_once('.')
+            .ok_or(DomainError::InvalidCredentials)?;
+        let expected = sign(signing_secret()?, payload);
+        if !constant_time_eq(expected.as_bytes(), signature.as_bytes()) {
+            return Err(DomainError::InvalidCredentials);
+        }
+
+        let bytes = URL_SAFE_NO_PAD
+            .decode(payload)
+            .map_err(|_| DomainError::InvalidCredentials)?;
+        let pending: Self =
+            serde_json::from_slice(&bytes).map_err(|_| DomainError::InvalidCredentials)?;
+        if pending.expires_at < Utc::now().timestamp() {
+            return Err(DomainError::InvalidCredentials);
+        }
+        Ok(pending)
+    }
+}
+
+/// Profile returned by the provider.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OAuthUserInfo {
+    /// Provider name
+    pub provider: String,
+    /// Stable user ID at the provider
+    pub subject: String,
+    /// Email address, if shared
+    pub email: Option<String>,
+    /// Whether the provider verified the email
+    pub email_verified: bool,
+    /// Display name
+    pub name: Option<String>,
+    /// Avatar URL
+    pub avatar_url: Option<String>,
+    /// Raw profile response
+    pub raw: Value,
+}
+
+/// Local account the provider identity was linked to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkedAccount {
+    /// Local user ID (JWT subject)
+    pub user_id: String,
+    /// Role claim to issue, if any
+    pub role: Option<String>,
+}
+
+/// Result of a completed social login.
+#[derive(Debug, Clone)]
+pub struct OAuthLogin {
+    /// Local user ID
+    pub user_id: String,
+    /// Session JWT
+    pub token: String,
+    /// Provider profile
+    pub info: OAuthUserInfo,
+}
+
+/// Maps a provider identity to a local account.
+///
+/// Typical implementations look up `(provider, subject)` in a linked
+/// identities table, fall back to matching a verified email, and create the
+/// user on first login.
+#[async_trait::async_trait]
+pub trait OAuthAccountLinker: Send + Sync {
+    /// Find or create the local account for a provider identity.
+    async fn link_account(&self, info: &OAuthUserInfo) -> DomainResult<LinkedAccount>;
+}
+
+/// HTTP transport used for the token exchange and profile request.
+#[async_trait::async_trait]
+pub trait OAuthHttpClient: Send + Sync {
+    /// POST a form and parse the JSON response.
+    ///
+    /// Non-2xx responses must be returned as errors, not parsed.
+    async fn post_form(&self, url: &str, form: &[(&str, &str)]) -> DomainResult<Value>;
+
+    /// GET a JSON document with a Bearer token.
+    async fn get_json(&self, url: &str, access_token: &str) -> DomainResult<Value>;
+}
+
+/// Build the authorization URL and the secrets to keep for the callback.
+///
+/// # Errors
+///
+/// Returns `InvalidInput` if the provider has no redirect URI
+pub fn authorization_request(
+    provider: &OAuthProvider,
+) -> DomainResult<(String, PendingAuthorization)> {
+    if provider.redirect_uri.is_empty() {
+        return Err(DomainError::InvalidInput(format!(
+            "OAuth provider '{}' has no redirect URI",
+            provider.name
+        )));
+    }
+
+    let pending = PendingAuthorization {
+        provider: provider.name.clone(),
+        state: random_alphanumeric(STATE_LEN),
+        nonce: provider.use_nonce.then(|| random_alphanumeric(STATE_LEN)),
+        pkce_verifier: random_alphanumeric(VERIFIER_LEN),
+        expires_at: (Utc::now() + Duration::minutes(PENDING_TTL_MINUTES)).timestamp(),
+    };
+
+    let challenge = pkce_challenge(&pending.pkce_verifier);
+    let scope = provider.scopes.join(" ");
+    let mut params = vec![
+        ("response_type", "code"),
+        ("client_id", provider.client_id.as_str()),
+        ("redirect_uri", provider.redirect_uri.as_str()),
+        ("scope", scope.as_str()),
+        ("state", pending.state.as_str()),
+        ("code_challenge", challenge.as_str()),
+        ("code_challenge_method", "S256"),
+    ];
+    if let Some(nonce) = &pending.nonce {
+        params.push(("nonce", nonce.as_str()));
+    }
+
+    let separator = if provider.auth_url.contains('?') {
+        '&'
+    } else {
+        '?'
+    };
+    let url = format!(
+        "{}{}{}",
+        provider.auth_url,
+        separator,
+        form_urlencode(&params)
+    );
+    Ok((url, pending))
+}
+
+/// Exchange the callback code for the provider profile.
+///
+/// # Errors
+///
+/// Returns `InvalidCredentials` if the state or nonce does not match or the
+/// pending authorization belongs to another provider, and `ExternalService`
+/// if the provider rejects the exchange
+pub async fn fetch_user_info(
+    client: &dyn OAuthHttpClient,
+    provider: &OAuthProvider,
+    pending: &PendingAuthorization,
+    state: &str,
+    code: &str,
+) -> DomainResult<OAuthUserInfo> {
+    if pending.provider != provider.name
+        || !constant_time_eq(pending.state.as_bytes(), state.as_bytes())
+        || pending.expires_at < Utc::now().timestamp()
+    {
+        return Err(DomainError::InvalidCredentials);
+    }
+
+    let mut form = vec![
+        ("grant_type", "authorization_code"),
+        ("code", code),
+        ("redirect_uri", provider.redirect_uri.as_str()),
+        ("client_id", provider.client_id.as_str()),
+        ("code_verifier", pending.pkce_verifier.as_str()),
+    ];
+    if let Some(secret) = &provider.client_secret {
+        form.push(("client_secret", secret.as_str()));
+    }
+
+    let token = client.post_form(&provider.token_url, &form).await?;
+    if let Some(error) = token.get("error").and_then(Value::as_str) {
+        return Err(DomainError::ExternalService(format!(
+            "OAuth token exchange failed: {}",
+            error
+        )));
+    }
+    let access_token = token
+        .get("access_token")
+        .and_then(Value::as_str)
+        .ok_or_else(|| {
+            DomainError::ExternalService("OAuth token response has no access_token".into())
+        })?;
+
+    if let Some(expected) = &pending.nonce {
+        let id_token = token
+            .get("id_token")
+            .and_then(Value::as_str)
+            .ok_or(DomainError::InvalidCredentials)?;
+        // The ID token comes straight from the token endpoint over TLS, so
+        // OpenID Connect Core 3.1.3.7 allows skipping its signature check.
+        let nonce = id_token_claim(id_token, "nonce").ok_or(DomainError::InvalidCredentials)?;
+        if !constant_time_eq(nonce.as_bytes(), expected.as_bytes()) {
+            return Err(DomainError::InvalidCredentials);
+        }
+    }
+
+    let profile = client
+        .get_json(&provider.userinfo_url, access_token)
+        .await?;
+    user_info_from_profile(&provider.name, profile)
+}
+
+/// Complete a social login: verify the callback, link the account and issue
+/// a session JWT.
+///
+/// `info` is recorded with the session when the session store is
+/// initialized (see [`super::sessions`]).
+///
+/// # Errors
+///
+/// Returns the errors of [`fetch_user_info`] and of the linker, or an error
+/// if the session cannot be started
+#[allow(clippy::too_many_arguments)]
+pub async fn complete_oauth_login(
+    client: &dyn OAuthHttpClient,
+    linker: &dyn OAuthAccountLinker,
+    provider: &OAuthProvider,
+    pending: &PendingAuthorization,
+    state: &str,
+    code: &str,
+    tenant: Option<&str>,
+    session: SessionInfo,
+) -> DomainResult<OAuthLogin> {
+    let info = fetch_user_info(client, provider, pending, state, code).await?;
+    let account = linker.link_account(&info).await?;
+
+    let config = Config::try_get().ok_or(DomainError::ConfigNotInitialized)?;
+    let claims = super::login_claims(
+        &account.user_id,
+        tenant,
+        account.role.clone(),
+        Vec::new(),
+        config,
+    );
+    let event = AuditEvent::new(actions::OAUTH_LOGIN)
+        .with_actor(&account.user_id)
+        .with_tenant(tenant)
+        .with_ip(session.ip.as_deref())
+        .with_user_agent(session.user_agent.as_deref())
+        .with_metadata(serde_json::json!({
+            "provider": info.provider,
+            "subject": info.subject,
+        }));
+    let token = issue_login_token(claims, session).await?;
+    crate::audit::emit(event).await;
+
+    Ok(OAuthLogin {
+        user_id: account.user_id,
+        token,
+        info,
+    })
+}
+
+/// Compute the S256 PKCE challenge for a verifier.
+pub fn pkce_challenge(verifier: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()))
+}
+
+/// Normalize a provider profile (OpenID Connect `userinfo` or GitHub `/user`).
+fn user_info_from_profile(provider: &str, profile: Value) -> DomainResult<OAuthUserInfo> {
+    let text = |key: &str| profile.get(key).and_then(Value::as_str).map(String::from);
+
+    let subject = match profile.get("sub").or_else(|| profile.get("id")) {
+        Some(Value::String(s)) => s.clone(),
+        Some(Value::Number(n)) => n.to_string(),
+        _ => {
+            return Err(DomainError::ExternalService(
+                "OAuth profile has no subject".into(),
+            ))
+        }
+    };
+
+    Ok(OAuthUserInfo {
+        provider: provider.to_string(),
+        subject,
+        email: text("email"),
+        email_verified: profile
+            .get("email_verified")
+            .and_then(Value::as_bool)
+            .unwrap_or(false),
+        name: text("name").or_else(|| text("login")),
+        avatar_url: text("picture").or_else(|| text("avatar_url")),
+        raw: profile,
+    })
+}
+
+/// Read a string claim from a JWT payload without verifying it.
+fn id_token_claim(id_token: &str, claim: &str) -> Option<String> {
+    let payload = id_token.split('.').nth(1)?;
+    let bytes = URL_SAFE_NO_PAD.decode(payload).ok()?;
+    let claims: Value = serde_json::from_slice(&bytes).ok()?;
+    claims.get(claim)?.as_str().map(String::from)
+}
+
+fn form_urlencode(params: &[(&str, &str)]) -> String {
+    params
+        .iter()
+        .map(|(key, value)| format!("{}={}", percent_encode(key), percent_encode(value)))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+fn percent_encode(input: &str) -> String {
+    input
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+fn signing_secret() -> DomainResult<&'static str> {
+    Config::try_get()
+        .map(|config| config.jwt.secret.as_str())
+        .ok_or(DomainError::ConfigNotInitialized)
+}
+
+fn sign(secret: &str, payload: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(b"oauth:");
+    mac.update(payload.as_bytes());
+    URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes())
+}
+
+/// Timeout for requests to the provider's token and userinfo endpoints.
+#[cfg(feature = "oauth")]
+const HTTP_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// `reqwest`-based HTTP client.
+///
+/// Requests time out after 10 seconds and non-2xx responses are returned as
+/// `ExternalService` errors.
+#[cfg(feature = "oauth")]
+#[derive(Debug, Clone)]
+pub struct ReqwestOAuthClient {
+    client: reqwest::Client,
+}
+
+#[cfg(feature = "oauth")]
+impl ReqwestOAuthClient {
+    /// Create a client.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[cfg(feature = "oauth")]
+impl Default for ReqwestOAuthClient {
+    fn default() -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(HTTP_TIMEOUT)
+            .build()
+            .expect("reqwest client configuration is valid");
+        Self { client }
+    }
+}
+
+#[cfg(feature = "oauth")]
+async fn send_json(request: reqwest::RequestBuilder) -> DomainResult<Value> {
+    request
+        .header("Accept", "application/json")
+        .header("User-Agent", "brylix")
+        .send()
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .map_err(|e| DomainError::ExternalService(format!("OAuth request failed: {}", e)))?
+        .json()
+        .await
+        .map_err(|e| DomainError::ExternalService(e.to_string()))
+}
+
+#[cfg(feature = "oauth")]
+#[async_trait::async_trait]
+impl OAuthHttpClient for ReqwestOAuthClient {
+    async fn post_form(&self, url: &str, form: &[(&str, &str)]) -> DomainResult<Value> {
+        send_json(self.client.post(url).form(form)).await
+    }
+
+    async fn get_json(&self, url: &str, access_token: &str) -> DomainResult<Value> {
+        send_json(self.client.get(url).bearer_auth(access_token)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::tests::init_test_config;
+    use serde_json::json;
+    use std::sync::Mutex;
+
+    /// Stand-in for the provider's token and profile endpoints.
+    struct StubProvider {
+        token_response: Value,
+        profile: Value,
+        forms: Mutex<Vec<Vec<(String, String)>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl OAuthHttpClient for StubProvider {
+        async fn post_form(&self, _url: &str, form: &[(&str, &str)]) -> DomainResult<Value> {
+            self.forms.lock().unwrap().push(
+                form.iter()
+                    .map(|(k, v)| (k.to_string(), v.to_string()))
+                    .collect(),
+            );
+            Ok(self.token_response.clone())
+        }
+
+        async fn get_json(&self, _url: &str, access_token: &str) -> DomainResult<Value> {
+            assert_eq!(access_token, "access-123");
+            Ok(self.profile.clone())
+        }
+    }
+
+    struct Linker;
+
+    #[async_trait::async_trait]
+    impl OAuthAccountLinker for Linker {
+        async fn link_account(&self, info: &OAuthUserInfo) -> DomainResult<LinkedAccount> {
+            Ok(LinkedAccount {
+                user_id: format!("{}-{}", info.provider, info.subject),
+                role: None,
+            })
+        }
+    }
+
+    fn id_token(nonce: &str) -> String {
+        let payload = URL_SAFE_NO_PAD.encode(json!({ "nonce": nonce }).to_string());
+        format!("eyJhbGciOiJub25lIn0.{}.", payload)
+    }
+
+    #[test]
+    fn test_pkce_challenge_s256() {
+        let challenge = pkce_challenge("dBjftJeZ4CVP-mJ92Z1fVn6NsA6pDS6b5DkpLp0jG1o");
+        assert_eq!(challenge, "EeTvU2ps-5pbubWIYI5eFZWceAIkvWPycPiFqDFvlf0");
+        assert_eq!(challenge.len(), 43);
+    }
+
+    #[test]
+    fn test_authorization_request_url() {
+        let google = OAuthProvider::google("cid", "secret", "https://app.test/cb");
+        let (url, pending) = authorization_request(&google).unwrap();
+
+        assert!(url.starts_with("https://accounts.google.com/o/oauth2/v2/auth?response_type=code"));
+        assert!(url.contains("redirect_uri=https%3A%2F%2Fapp.test%2Fcb"));
+        assert!(url.contains("scope=openid%20email%20profile"));
+        assert!(url.contains(&format!("state={}", pending.state)));
+        assert!(url.contains(&format!(
+            "code_challenge={}",
+            pkce_challenge(&pending.pkce_verifier)
+        )));
+        assert!(url.contains("nonce="));
+
+        let github = OAuthProvider::github("cid", "secret", "https://app.test/cb");
+        let (_, pending) = authorization_request(&github).unwrap();
+        assert!(pending.nonce.is_none());
+    }
+
+    #[test]
+    fn test_pending_round_trip_and_tamper() {
+        init_test_config();
+        let google = OAuthProvider::google("cid", "secret", "https://app.test/cb");
+        let (_, pending) = authorization_request(&google).unwrap();
+
+        let encoded = pending.encode().unwrap();
+        assert_eq!(PendingAuthorization::decode(&encoded).unwrap(), pending);
+
+        let tampered = format!("{}x", encoded);
+        assert!(PendingAuthorization::decode(&tampered).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_complete_login_google() {
+        init_test_config();
+        let sink = crate::audit::tests::test_sink();
+        let google = OAuthProvider::google("cid", "secret", "https://app.test/cb");
+        let (_, pending) = authorization_request(&google).unwrap();
+        let stub = StubProvider {
+            token_response: json!({
+                "access_token": "access-123",
+                "id_token": id_token(pending.nonce.as_deref().unwrap()),
+            }),
+            profile: json!({ "sub": "g-1", "email": "jane@test.com", "email_verified": true, "name": "Jane" }),
+            forms: Mutex::new(Vec::new()),
+        };
+
+        let login = complete_oauth_login(
+            &stub,
+            &Linker,
+            &google,
+            &pending,
+            &pending.state,
+            "code-1",
+            Some("acme"),
+            SessionInfo {
+                ip: Some("10.0.0.7".into()),
+                user_agent: Some("Firefox".into()),
+                ..SessionInfo::new()
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(login.user_id, "google-g-1");
+
+        let event = sink
+            .events()
+            .into_iter()
+            .find(|e| e.action == actions::OAUTH_LOGIN && e.ip.as_deref() == Some("10.0.0.7"))
+            .unwrap();
+        assert_eq!(event.user_agent.as_deref(), Some("Firefox"));
+        assert_eq!(event.tenant.as_deref(), Some("acme"));
+        assert!(login.info.email_verified);
+        assert_eq!(
+            crate::auth::validate_jwt(&login.token).unwrap().sub,
+            "google-g-1"
+        );
+
+        let forms = stub.forms.lock().unwrap();
+        assert!(forms[0].contains(&("code_verifier".to_string(), pending.pkce_verifier.clone())));
+    }
+
+    #[tokio::test]
+    async fn test_rejects_state_and_nonce_mismatch() {
+        let google = OAuthProvider::google("cid", "secret", "https://app.test/cb");
+        let (_, pending) = authorization_request(&google).unwrap();
+        let stub = StubProvider {
+            token_response: json!({ "access_token": "access-123", "id_token": id_token("other") }),
+            profile: json!({ "sub": "g-1" }),
+            forms: Mutex::new(Vec::new()),
+        };
+
+        assert!(matches!(
+            fetch_user_info(&stub, &google, &pending, "wrong-state", "code").await,
+            Err(DomainError::InvalidCredentials)
+        ));
+        assert!(matches!(
+            fetch_user_info(&stub, &google, &pending, &pending.state, "code").await,
+            Err(DomainError::InvalidCredentials)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_github_profile_mapping() {
+        let github = OAuthProvider::github("cid", "secret", "https://app.test/cb");
+        let (_, pending) = authorization_request(&github).unwrap();
+        let stub = StubProvider {
+            token_response: json!({ "access_token": "access-123" }),
+            profile: json!({ "id": 42, "login": "octo", "avatar_url": "https://img.test/a.png", "email": null }),
+            forms: Mutex::new(Vec::new()),
+        };
+
+        let info = fetch_user_info(&stub, &github, &pending, &pending.state, "code")
+            .await
+            .unwrap();
+        assert_eq!(info.subject, "42");
+        assert_eq!(info.name.as_deref(), Some("octo"));
+        assert_eq!(info.email, None);
+        assert!(!info.email_verified);
+    }
+
+    #[cfg(feature = "oauth")]
+    #[tokio::test]
+    async fn test_reqwest_client_rejects_error_status() {
+        use std::io::{Read, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/token", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let _ = stream.read(&mut [0; 4096]);
+            let body = r#"{"error":"invalid_grant"}"#;
+            write!(
+                stream,
+                "HTTP/1.1 400 Bad Request\r\nContent-Type: application/json\r\n\
+                 Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            )
+            .unwrap();
+        });
+
+        // A JSON error body must not be mistaken for a token response
+        let err = ReqwestOAuthClient::new()
+            .post_form(&url, &[("code", "abc")])
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), "BAD_GATEWAY");
+        assert!(err.to_string().contains("400"));
+    }
+}
@@ -1,12 +1,265 @@
 //! Password hashing and verification using Argon2.
+//!
+//! Hashing parameters and an optional server-side pepper are configured once
+//! with [`init_password_hashing`]. Hashes created with older parameters keep
+//! verifying; [`verify_and_rehash`] reports when one should be replaced and
+//! also upgrades hashes created before the pepper was introduced.
+
+use std::env;
+use std::sync::OnceLock;
 
 use argon2::{
     password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
-    Argon2,
+    Algorithm, Argon2, Params, Version,
 };
 
+/// Global password hashing configuration
+static HASHING: OnceLock<PasswordHashConfig> = OnceLock::new();
+
+/// Argon2id parameters and optional pepper used for password hashes.
+///
+/// The defaults match `Argon2::default()` (19 MiB memory, 2 iterations,
+/// 1 lane), so hashes created before this config existed stay current.
+#[derive(Clone)]
+pub struct PasswordHashConfig {
+    /// Memory cost in KiB
+    pub memory_kib: u32,
+    /// Number of iterations
+    pub iterations: u32,
+    /// Degree of parallelism
+    pub parallelism: u32,
+    /// Server-side secret mixed into every hash (never stored in the database)
+    pub pepper: Option<String>,
+    /// Let [`verify`](Self::verify) accept hashes created before the pepper
+    /// was configured, at the cost of a second Argon2 run on every failed check
+    pub accept_unpeppered: bool,
+}
+
+impl std::fmt::Debug for PasswordHashConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PasswordHashConfig")
+            .field("memory_kib", &self.memory_kib)
+            .field("iterations", &self.iterations)
+            .field("parallelism", &self.parallelism)
+            .field("pepper", &self.pepper.as_ref().map(|_| "[REDACTED]"))
+            .field("accept_unpeppered", &self.accept_unpeppered)
+            .finish()
+    }
+}
+
+impl Default for PasswordHashConfig {
+    fn default() -> Self {
+        Self {
+            memory_kib: Params::DEFAULT_M_COST,
+            iterations: Params::DEFAULT_T_COST,
+            parallelism: Params::DEFAULT_P_COST,
+            pepper: None,
+            accept_unpeppered: false,
+        }
+    }
+}
+
+impl PasswordHashConfig {
+    /// Create a config with the default Argon2id parameters and no pepper.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load the config from environment variables.
+    ///
+    /// Reads `PASSWORD_PEPPER`, `PASSWORD_ACCEPT_UNPEPPERED`,
+    /// `ARGON2_MEMORY_KIB`, `ARGON2_ITERATIONS` and `ARGON2_PARALLELISM`;
+    /// unset values keep their defaults.
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let number = |name: &str, default: u32| {
+            env::var(name)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        };
+
+        Self {
+            memory_kib: number("ARGON2_MEMORY_KIB", defaults.memory_kib),
+            iterations: number("ARGON2_ITERATIONS", defaults.iterations),
+            parallelism: number("ARGON2_PARALLELISM", defaults.parallelism),
+            pepper: env::var("PASSWORD_PEPPER").ok().filter(|p| !p.is_empty()),
+            accept_unpeppered: env::var("PASSWORD_ACCEPT_UNPEPPERED")
+                .map(|v| v == "true" || v == "1")
+                .unwrap_or(false),
+        }
+    }
+
+    /// Set the Argon2id cost parameters.
+    pub fn with_params(mut self, memory_kib: u32, iterations: u32, parallelism: u32) -> Self {
+        self.memory_kib = memory_kib;
+        self.iterations = iterations;
+        self.parallelism = parallelism;
+        self
+    }
+
+    /// Set the server-side pepper.
+    pub fn with_pepper(mut self, pepper: impl Into<String>) -> Self {
+        self.pepper = Some(pepper.into());
+        self
+    }
+
+    /// Let [`verify`](Self::verify) accept hashes created before the pepper
+    /// was configured, e.g. API keys or recovery codes that cannot be
+    /// rehashed. Every failed check then runs Argon2 twice.
+    pub fn with_unpeppered_fallback(mut self) -> Self {
+        self.accept_unpeppered = true;
+        self
+    }
+
+    fn argon2(&self, use_pepper: bool) -> Result<Argon2<'_>, String> {
+        let params = Params::new(self.memory_kib, self.iterations, self.parallelism, None)
+            .map_err(|e| format!("Invalid Argon2 parameters: {}", e))?;
+
+        match self.pepper.as_deref().filter(|_| use_pepper) {
+            Some(pepper) => Argon2::new_with_secret(
+                pepper.as_bytes(),
+                Algorithm::Argon2id,
+                Version::V0x13,
+                params,
+            )
+            .map_err(|e| format!("Invalid password pepper: {}", e)),
+            None => Ok(Argon2::new(Algorithm::Argon2id, Version::V0x13, params)),
+        }
+    }
+
+    /// Hash a password with this config.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the parameters are invalid or hashing fails
+    pub fn hash(&self, password: &str) -> Result<String, String> {
+        let salt = SaltString::generate(&mut OsRng);
+
+        self.argon2(true)?
+            .hash_password(password.as_bytes(), &salt)
+            .map(|h| h.to_string())
+            .map_err(|e| format!("Failed to hash password: {}", e))
+    }
+
+    /// Verify a password against a hash with this config.
+    ///
+    /// The cost parameters are read from the hash itself, so hashes created
+    /// with older parameters still verify. Hashes created before the pepper
+    /// was introduced are only accepted with
+    /// [`with_unpeppered_fallback`](Self::with_unpeppered_fallback); use
+    /// [`verify_and_rehash`](Self::verify_and_rehash) at login to upgrade them.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the hash is invalid
+    pub fn verify(&self, password: &str, hash: &str) -> Result<bool, String> {
+        Ok(self.verify_with(password, hash, true)?
+            || (self.pepper.is_some()
+                && self.accept_unpeppered
+                && self.verify_with(password, hash, false)?))
+    }
+
+    fn verify_with(&self, password: &str, hash: &str, use_pepper: bool) -> Result<bool, String> {
+        let parsed_hash =
+            PasswordHash::new(hash).map_err(|e| format!("Invalid password hash: {}", e))?;
+
+        Ok(self
+            .argon2(use_pepper)?
+            .verify_password(password.as_bytes(), &parsed_hash)
+            .is_ok())
+    }
+
+    /// Check if a hash was created with other parameters than this config.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the hash is invalid
+    pub fn needs_rehash(&self, hash: &str) -> Result<bool, String> {
+        let parsed_hash =
+            PasswordHash::new(hash).map_err(|e| format!("Invalid password hash: {}", e))?;
+
+        if parsed_hash.algorithm != Algorithm::Argon2id.ident()
+            || parsed_hash.version != Some(Version::V0x13.into())
+        {
+            return Ok(true);
+        }
+
+        let params = Params::try_from(&parsed_hash)
+            .map_err(|e| format!("Invalid password hash: {}", e))?;
+        Ok(params.m_cost() != self.memory_kib
+            || params.t_cost() != self.iterations
+            || params.p_cost() != self.parallelism)
+    }
+
+    /// Verify a password and, if it matches an outdated hash, compute a new one.
+    ///
+    /// When a pepper is configured, hashes created before it was introduced
+    /// are accepted and a peppered replacement is returned.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the hash is invalid or rehashing fails
+    pub fn verify_and_rehash(
+        &self,
+        password: &str,
+        hash: &str,
+    ) -> Result<PasswordVerification, String> {
+        if self.verify_with(password, hash, true)? {
+            let new_hash = if self.needs_rehash(hash)? {
+                Some(self.hash(password)?)
+            } else {
+                None
+            };
+            return Ok(PasswordVerification {
+                valid: true,
+                new_hash,
+            });
+        }
+
+        // Legacy hash created before the pepper was configured
+        if self.pepper.is_some() && self.verify_with(password, hash, false)? {
+            return Ok(PasswordVerification {
+                valid: true,
+                new_hash: Some(self.hash(password)?),
+            });
+        }
+
+        Ok(PasswordVerification {
+            valid: false,
+            new_hash: None,
+        })
+    }
+}
+
+/// Result of [`verify_and_rehash`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PasswordVerification {
+    /// Whether the password matched
+    pub valid: bool,
+    /// Replacement hash to persist, when the stored one is outdated
+    pub new_hash: Option<String>,
+}
+
+/// Initialize the global password hashing config.
+///
+/// Call once at startup, before any password is hashed. Later calls are ignored.
+pub fn init_password_hashing(config: PasswordHashConfig) -> &'static PasswordHashConfig {
+    if HASHING.set(config).is_err() {
+        tracing::debug!("Password hashing config already initialized");
+    }
+    password_hashing()
+}
+
+/// Get the global password hashing config (defaults if not initialized).
+pub fn password_hashing() -> &'static PasswordHashConfig {
+    HASHING.get_or_init(PasswordHashConfig::default)
+}
+
 /// Hash a password using Argon2id.
 ///
+/// Uses the parameters and pepper from [`init_password_hashing`].
+///
 /// # Arguments
 ///
 /// * `password` - The plaintext password to hash
@@ -19,17 +272,15 @@ use argon2::{
 ///
 /// Returns an error if hashing fails
 pub fn hash_password(password: &str) -> Result<String, String> {
-    let salt = SaltString::generate(&mut OsRng);
-    let argon2 = Argon2::default();
-
-    argon2
-        .hash_password(password.as_bytes(), &salt)
-        .map(|h| h.to_string())
-        .map_err(|e| format!("Failed to hash password: {}", e))
+    password_hashing().hash(password)
 }
 
 /// Verify a password against a hash.
 ///
+/// Hashes created before `PASSWORD_PEPPER` was set are only accepted when
+/// `PASSWORD_ACCEPT_UNPEPPERED` is enabled; passwords should go through
+/// [`verify_and_rehash`] instead, which upgrades them.
+///
 /// # Arguments
 ///
 /// * `password` - The plaintext password to verify
@@ -43,12 +294,31 @@ pub fn hash_password(password: &str) -> Result<String, String> {
 ///
 /// Returns an error if the hash is invalid
 pub fn verify_password(password: &str, hash: &str) -> Result<bool, String> {
-    let parsed_hash =
-        PasswordHash::new(hash).map_err(|e| format!("Invalid password hash: {}", e))?;
+    password_hashing().verify(password, hash)
+}
+
+/// Verify a password and return a replacement hash if the stored one is outdated.
+///
+/// Call this at login instead of [`verify_password`] and persist
+/// `new_hash` when it is `Some`, so hashes migrate to the current parameters
+/// (and pepper) as users sign in.
+///
+/// # Errors
+///
+/// Returns an error if the hash is invalid or rehashing fails
+pub fn verify_and_rehash(password: &str, hash: &str) -> Result<PasswordVerification, String> {
+    password_hashing().verify_and_rehash(password, hash)
+}
+
+/// Generate a random alphanumeric string (for key and token secrets).
+pub(crate) fn random_alphanumeric(len: usize) -> String {
+    use rand::RngExt;
+    const CHARSET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+    let mut rng = rand::rng();
 
-    Ok(Argon2::default()
-        .verify_password(password.as_bytes(), &parsed_hash)
-        .is_ok())
+    (0..len)
+        .map(|_| CHARSET[rng.random_range(0..CHARSET.len())] as char)
+        .collect()
 }
 
 /// Generate a temporary password for user invitations.
@@ -82,6 +352,66 @@ mod tests {
         assert!(!verify_password("wrong_password", &hash).unwrap());
     }
 
+    #[test]
+    fn test_needs_rehash_on_parameter_change() {
+        let old = PasswordHashConfig::new().with_params(8 * 1024, 1, 1);
+        let hash = old.hash("SecurePassword123!").unwrap();
+
+        let current = PasswordHashConfig::new();
+        assert!(current.needs_rehash(&hash).unwrap());
+        assert!(!old.needs_rehash(&hash).unwrap());
+
+        let result = current.verify_and_rehash("SecurePassword123!", &hash).unwrap();
+        assert!(result.valid);
+        let new_hash = result.new_hash.unwrap();
+        assert!(!current.needs_rehash(&new_hash).unwrap());
+        assert!(current.verify("SecurePassword123!", &new_hash).unwrap());
+    }
+
+    #[test]
+    fn test_verify_and_rehash_current_hash() {
+        let config = PasswordHashConfig::new();
+        let hash = config.hash("SecurePassword123!").unwrap();
+
+        let ok = config.verify_and_rehash("SecurePassword123!", &hash).unwrap();
+        assert_eq!(ok, PasswordVerification { valid: true, new_hash: None });
+
+        let wrong = config.verify_and_rehash("nope", &hash).unwrap();
+        assert!(!wrong.valid);
+        assert!(wrong.new_hash.is_none());
+    }
+
+    #[test]
+    fn test_pepper() {
+        let peppered = PasswordHashConfig::new().with_pepper("server-secret");
+        let hash = peppered.hash("SecurePassword123!").unwrap();
+
+        assert!(peppered.verify("SecurePassword123!", &hash).unwrap());
+        assert!(!PasswordHashConfig::new().verify("SecurePassword123!", &hash).unwrap());
+        assert!(!PasswordHashConfig::new()
+            .with_pepper("other-secret")
+            .verify("SecurePassword123!", &hash)
+            .unwrap());
+    }
+
+    #[test]
+    fn test_pepper_upgrades_legacy_hash() {
+        let legacy = PasswordHashConfig::new().hash("SecurePassword123!").unwrap();
+        let peppered = PasswordHashConfig::new().with_pepper("server-secret");
+
+        // Plain verification only falls back when asked to
+        assert!(!peppered.verify("SecurePassword123!", &legacy).unwrap());
+        let fallback = peppered.clone().with_unpeppered_fallback();
+        assert!(fallback.verify("SecurePassword123!", &legacy).unwrap());
+        assert!(!fallback.verify("wrong", &legacy).unwrap());
+
+        let result = peppered.verify_and_rehash("SecurePassword123!", &legacy).unwrap();
+        assert!(result.valid);
+        assert!(peppered
+            .verify("SecurePassword123!", &result.new_hash.unwrap())
+            .unwrap());
+    }
+
     #[test]
     fn test_generate_temp_password() {
         let password = generate_temp_password();
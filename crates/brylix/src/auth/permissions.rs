@@ -0,0 +1,373 @@
+//! Permission-based authorization (RBAC).
+//!
+//! Roles are mapped to fine-grained permission strings such as
+//! `"invoice.delete"`. The mapping can be static ([`RolePermissions`]) or
+//! loaded per tenant from the `role_permissions` table
+//! ([`DbRolePermissions`]). The resolved [`PermissionSet`] is attached to
+//! [`ContextData`](crate::graphql::ContextData) for the request and checked
+//! with [`ContextData::has_permission`](crate::graphql::ContextData::has_permission)
+//! or the [`Perm`](crate::graphql::Perm) guard.
+//!
+//! Permission patterns support a trailing wildcard: `"invoice.*"` grants
+//! every permission starting with `"invoice."`, and `"*"` grants everything.
+//!
+//! # Usage
+//!
+//! ```rust,ignore
+//! use brylix::auth::permissions::{PermissionResolver, RolePermissions};
+//!
+//! let rbac = RolePermissions::new()
+//!     .grant("admin", ["*"])
+//!     .grant("accountant", ["invoice.read", "invoice.create", "invoice.delete"])
+//!     .grant("user", ["invoice.read"]);
+//!
+//! // Per request, after jwt_middleware
+//! let permissions = match &jwt.role {
+//!     Some(role) => rbac.permissions_for(&db, role.role_name()).await?,
+//!     None => PermissionSet::new(),
+//! };
+//! let ctx = ContextData::single_tenant(db, jwt.user_id, jwt.role).with_permissions(permissions);
+//!
+//! // In the schema
+//! #[graphql(guard = "Perm(\"invoice.delete\")")]
+//! async fn delete_invoice(&self, ctx: &Context<'_>, id: i64) -> Result<bool> { ... }
+//! ```
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
+
+use crate::errors::DomainResult;
+
+/// Wildcard permission granting every action.
+pub const WILDCARD: &str = "*";
+
+/// A single permission, e.g. `"invoice.delete"` or `"invoice.*"`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Permission(String);
+
+impl Permission {
+    /// Create a new permission.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self(name.into())
+    }
+
+    /// Get the permission string.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Check if this permission (possibly a wildcard pattern) grants `required`.
+    pub fn grants(&self, required: &str) -> bool {
+        if self.0 == WILDCARD || self.0 == required {
+            return true;
+        }
+        match self.0.strip_suffix('*') {
+            Some(prefix) if prefix.ends_with('.') => required.starts_with(prefix),
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Permission {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<&str> for Permission {
+    fn from(name: &str) -> Self {
+        Self::new(name)
+    }
+}
+
+impl From<String> for Permission {
+    fn from(name: String) -> Self {
+        Self(name)
+    }
+}
+
+/// The set of permissions granted to the current request.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PermissionSet {
+    permissions: HashSet<Permission>,
+}
+
+impl PermissionSet {
+    /// Create an empty permission set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a permission to the set.
+    pub fn insert(&mut self, permission: impl Into<Permission>) {
+        self.permissions.insert(permission.into());
+    }
+
+    /// Add every permission from another set.
+    pub fn extend(&mut self, other: PermissionSet) {
+        self.permissions.extend(other.permissions);
+    }
+
+    /// Check if any permission in the set grants `required`.
+    pub fn allows(&self, required: &str) -> bool {
+        self.permissions.iter().any(|p| p.grants(required))
+    }
+
+    /// Check if the set is empty.
+    pub fn is_empty(&self) -> bool {
+        self.permissions.is_empty()
+    }
+
+    /// Iterate over the permissions in the set.
+    pub fn iter(&self) -> impl Iterator<Item = &Permission> {
+        self.permissions.iter()
+    }
+}
+
+impl<P: Into<Permission>> FromIterator<P> for PermissionSet {
+    fn from_iter<I: IntoIterator<Item = P>>(iter: I) -> Self {
+        Self {
+            permissions: iter.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+/// Resolves the permissions granted to a role.
+///
+/// The database connection is the one for the current request, so
+/// implementations backed by a table resolve permissions per tenant.
+#[async_trait::async_trait]
+pub trait PermissionResolver: Send + Sync {
+    /// Get the permissions granted to `role`.
+    async fn permissions_for(
+        &self,
+        db: &DatabaseConnection,
+        role: &str,
+    ) -> DomainResult<PermissionSet>;
+}
+
+/// Static role → permission mapping configured in code.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use brylix::auth::permissions::RolePermissions;
+///
+/// let rbac = RolePermissions::new()
+///     .grant("admin", ["*"])
+///     .grant("editor", ["post.*"]);
+///
+/// assert!(rbac.role_allows("editor", "post.publish"));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct RolePermissions {
+    roles: HashMap<String, PermissionSet>,
+}
+
+impl RolePermissions {
+    /// Create an empty mapping.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Grant permissions to a role.
+    ///
+    /// Can be called several times for the same role; grants accumulate.
+    pub fn grant<I, P>(mut self, role: &str, permissions: I) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: Into<Permission>,
+    {
+        let entry = self.roles.entry(role.to_string()).or_default();
+        for permission in permissions {
+            entry.insert(permission);
+        }
+        self
+    }
+
+    /// Get the permissions granted to a role (empty if the role is unknown).
+    pub fn permissions(&self, role: &str) -> PermissionSet {
+        self.roles.get(role).cloned().unwrap_or_default()
+    }
+
+    /// Check if a role is granted a permission.
+    pub fn role_allows(&self, role: &str, required: &str) -> bool {
+        self.roles.get(role).is_some_and(|set| set.allows(required))
+    }
+}
+
+#[async_trait::async_trait]
+impl PermissionResolver for RolePermissions {
+    async fn permissions_for(
+        &self,
+        _db: &DatabaseConnection,
+        role: &str,
+    ) -> DomainResult<PermissionSet> {
+        Ok(self.permissions(role))
+    }
+}
+
+/// Role → permission mapping loaded from the `role_permissions` table.
+///
+/// Queries the request's database connection, so in multi-tenant mode each
+/// tenant has its own mapping. Add [`migration::Migration`] to the tenant
+/// migrator to create the table.
+#[derive(Debug, Clone, Default)]
+pub struct DbRolePermissions;
+
+impl DbRolePermissions {
+    /// Create a new database-backed resolver.
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[async_trait::async_trait]
+impl PermissionResolver for DbRolePermissions {
+    async fn permissions_for(
+        &self,
+        db: &DatabaseConnection,
+        role: &str,
+    ) -> DomainResult<PermissionSet> {
+        let rows = entity::Entity::find()
+            .filter(entity::Column::Role.eq(role))
+            .all(db)
+            .await?;
+        Ok(rows.into_iter().map(|row| row.permission).collect())
+    }
+}
+
+/// SeaORM entity for the `role_permissions` table.
+pub mod entity {
+    use sea_orm::entity::prelude::*;
+
+    /// A single role → permission grant.
+    #[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+    #[sea_orm(table_name = "role_permissions")]
+    pub struct Model {
+        /// Row ID
+        #[sea_orm(primary_key)]
+        pub id: i64,
+        /// Role name
+        pub role: String,
+        /// Permission string (may be a wildcard pattern)
+        pub permission: String,
+    }
+
+    /// No relations.
+    #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+    pub enum Relation {}
+
+    impl ActiveModelBehavior for ActiveModel {}
+}
+
+/// Migration creating the `role_permissions` table.
+pub mod migration {
+    use sea_orm_migration::prelude::*;
+
+    /// Creates the `role_permissions` table.
+    pub struct Migration;
+
+    impl MigrationName for Migration {
+        fn name(&self) -> &str {
+            "m20261018_000001_create_role_permissions_table"
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl MigrationTrait for Migration {
+        async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+            manager
+                .create_table(
+                    Table::create()
+                        .table(RolePermissions::Table)
+                        .if_not_exists()
+                        .col(
+                            ColumnDef::new(RolePermissions::Id)
+                                .big_integer()
+                                .not_null()
+                                .auto_increment()
+                                .primary_key(),
+                        )
+                        .col(ColumnDef::new(RolePermissions::Role).string_len(100).not_null())
+                        .col(
+                            ColumnDef::new(RolePermissions::Permission)
+                                .string_len(255)
+                                .not_null(),
+                        )
+                        .index(
+                            Index::create()
+                                .name("idx_role_permissions_role_permission")
+                                .col(RolePermissions::Role)
+                                .col(RolePermissions::Permission)
+                                .unique(),
+                        )
+                        .to_owned(),
+                )
+                .await
+        }
+
+        async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+            manager
+                .drop_table(Table::drop().table(RolePermissions::Table).to_owned())
+                .await
+        }
+    }
+
+    #[derive(Iden)]
+    enum RolePermissions {
+        Table,
+        Id,
+        Role,
+        Permission,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_permission_exact() {
+        let p = Permission::new("invoice.delete");
+        assert!(p.grants("invoice.delete"));
+        assert!(!p.grants("invoice.read"));
+    }
+
+    #[test]
+    fn test_permission_wildcard() {
+        assert!(Permission::new("*").grants("anything.at.all"));
+
+        let p = Permission::new("invoice.*");
+        assert!(p.grants("invoice.delete"));
+        assert!(p.grants("invoice.line.update"));
+        assert!(!p.grants("invoices.delete"));
+        assert!(!p.grants("user.delete"));
+    }
+
+    #[test]
+    fn test_permission_set_allows() {
+        let set: PermissionSet = ["post.read", "comment.*"].into_iter().collect();
+        assert!(set.allows("post.read"));
+        assert!(set.allows("comment.delete"));
+        assert!(!set.allows("post.delete"));
+        assert!(!PermissionSet::new().allows("post.read"));
+    }
+
+    #[test]
+    fn test_role_permissions_grant_accumulates() {
+        let rbac = RolePermissions::new()
+            .grant("editor", ["post.create"])
+            .grant("editor", ["post.publish"])
+            .grant("admin", ["*"]);
+
+        assert!(rbac.role_allows("editor", "post.create"));
+        assert!(rbac.role_allows("editor", "post.publish"));
+        assert!(!rbac.role_allows("editor", "user.delete"));
+        assert!(rbac.role_allows("admin", "user.delete"));
+        assert!(!rbac.role_allows("guest", "post.create"));
+        assert!(rbac.permissions("guest").is_empty());
+    }
+}
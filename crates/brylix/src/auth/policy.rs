@@ -0,0 +1,349 @@
+//! Resource ownership and attribute-based access policies.
+//!
+//! Instead of repeating "load row, compare `owner_id` to the current user,
+//! else `Forbidden`" in every resolver, register one [`Policy`] per entity
+//! type and ask it through [`authorize!`](crate::authorize).
+//!
+//! # Usage
+//!
+//! ```rust,ignore
+//! use brylix::auth::policy::{init_policies, OwnerOrAdmin, Owned, PolicyRegistry};
+//!
+//! impl Owned for task::Model {
+//!     fn owner_id(&self) -> Option<i64> {
+//!         self.assignee_id
+//!     }
+//! }
+//!
+//! // At startup
+//! init_policies(
+//!     PolicyRegistry::new()
+//!         .register::<task::Model>(OwnerOrAdmin)
+//!         .register::<invoice::Model>(|data: &ContextData, action: &str, invoice: &invoice::Model| {
+//!             action == "read" || data.has_permission("invoice.manage")
+//!         }),
+//! );
+//!
+//! // In a resolver
+//! let task = TaskService::get_by_id(&data.db, id).await.map_err(gql_from_domain)?;
+//! authorize!(ctx, "update", &task).map_err(gql_from_domain)?;
+//! ```
+
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock};
+
+use async_graphql::Context;
+
+use crate::errors::{DomainError, DomainResult};
+use crate::graphql::ContextData;
+
+/// Global policy registry
+static POLICIES: OnceLock<PolicyRegistry> = OnceLock::new();
+
+/// Access policy for a resource type.
+///
+/// Closures of the form `Fn(&ContextData, &str, &R) -> bool` implement this
+/// trait, so simple rules can be registered inline.
+pub trait Policy<R>: Send + Sync {
+    /// Decide whether the current request may perform `action` on `resource`.
+    fn can(&self, data: &ContextData, action: &str, resource: &R) -> bool;
+}
+
+impl<R, F> Policy<R> for F
+where
+    F: Fn(&ContextData, &str, &R) -> bool + Send + Sync,
+{
+    fn can(&self, data: &ContextData, action: &str, resource: &R) -> bool {
+        self(data, action, resource)
+    }
+}
+
+/// Resources that belong to a user.
+pub trait Owned {
+    /// The owning user's ID, if the resource has an owner.
+    fn owner_id(&self) -> Option<i64>;
+}
+
+/// Allows the resource owner and admins (including admin overrides) to
+/// perform any action.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OwnerOrAdmin;
+
+impl<R: Owned> Policy<R> for OwnerOrAdmin {
+    fn can(&self, data: &ContextData, _action: &str, resource: &R) -> bool {
+        data.is_admin() || is_owner(data, resource)
+    }
+}
+
+/// Allows only the resource owner to perform any action.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OwnerOnly;
+
+impl<R: Owned> Policy<R> for OwnerOnly {
+    fn can(&self, data: &ContextData, _action: &str, resource: &R) -> bool {
+        is_owner(data, resource)
+    }
+}
+
+/// Check if the authenticated user owns the resource.
+///
+/// Always `false` for API keys, see [`ContextData::owner_user_id`].
+pub fn is_owner<R: Owned>(data: &ContextData, resource: &R) -> bool {
+    let user_id = data.owner_user_id().and_then(|id| id.parse::<i64>().ok());
+    match (user_id, resource.owner_id()) {
+        (Some(user_id), Some(owner_id)) => user_id == owner_id,
+        _ => false,
+    }
+}
+
+/// Policies registered per resource type.
+#[derive(Default)]
+pub struct PolicyRegistry {
+    policies: HashMap<TypeId, Box<dyn Any + Send + Sync>>,
+}
+
+impl PolicyRegistry {
+    /// Create an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register the policy for resource type `R`, replacing any previous one.
+    pub fn register<R: 'static>(mut self, policy: impl Policy<R> + 'static) -> Self {
+        let policy: Arc<dyn Policy<R>> = Arc::new(policy);
+        self.policies.insert(TypeId::of::<R>(), Box::new(policy));
+        self
+    }
+
+    /// Check if a policy is registered for resource type `R`.
+    pub fn has_policy<R: 'static>(&self) -> bool {
+        self.policies.contains_key(&TypeId::of::<R>())
+    }
+
+    /// Ask the policy for `R` whether `action` is allowed.
+    ///
+    /// Returns `false` when no policy is registered for `R`.
+    pub fn can<R: 'static>(&self, data: &ContextData, action: &str, resource: &R) -> bool {
+        match self
+            .policies
+            .get(&TypeId::of::<R>())
+            .and_then(|p| p.downcast_ref::<Arc<dyn Policy<R>>>())
+        {
+            Some(policy) => policy.can(data, action, resource),
+            None => {
+                tracing::warn!(
+                    resource = std::any::type_name::<R>(),
+                    action,
+                    "No policy registered for resource type; denying"
+                );
+                false
+            }
+        }
+    }
+
+    /// Like [`PolicyRegistry::can`], but returns `DomainError::Forbidden` when denied.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Unauthorized` if the request is not authenticated, or
+    /// `Forbidden` if the policy denies the action
+    pub fn authorize<R: 'static>(
+        &self,
+        data: &ContextData,
+        action: &str,
+        resource: &R,
+    ) -> DomainResult<()> {
+        if !data.is_authenticated() {
+            return Err(DomainError::Unauthorized);
+        }
+        if self.can(data, action, resource) {
+            Ok(())
+        } else {
+            Err(DomainError::Forbidden(format!(
+                "Not allowed to {} this resource",
+                action
+            )))
+        }
+    }
+}
+
+/// Initialize the global policy registry.
+///
+/// Call once at startup. Later calls are ignored.
+pub fn init_policies(registry: PolicyRegistry) -> &'static PolicyRegistry {
+    if POLICIES.set(registry).is_err() {
+        tracing::debug!("Policy registry already initialized");
+    }
+    policies()
+}
+
+/// Get the global policy registry (empty if not initialized).
+pub fn policies() -> &'static PolicyRegistry {
+    POLICIES.get_or_init(PolicyRegistry::new)
+}
+
+/// Authorize an action against the global policy registry.
+///
+/// # Errors
+///
+/// Returns `Unauthorized` if the request is not authenticated, or
+/// `Forbidden` if the policy denies the action or none is registered
+pub fn authorize<R: 'static>(
+    data: &ContextData,
+    action: &str,
+    resource: &R,
+) -> DomainResult<()> {
+    policies().authorize(data, action, resource)
+}
+
+/// Sources of [`ContextData`] accepted by [`authorize!`](crate::authorize).
+pub trait AsContextData {
+    /// Get the request's context data.
+    fn context_data(&self) -> &ContextData;
+}
+
+impl AsContextData for ContextData {
+    fn context_data(&self) -> &ContextData {
+        self
+    }
+}
+
+impl AsContextData for Context<'_> {
+    fn context_data(&self) -> &ContextData {
+        self.data_unchecked::<ContextData>()
+    }
+}
+
+/// Authorize an action on a resource against the registered policies.
+///
+/// Accepts either a GraphQL `Context` or a `ContextData` and evaluates to
+/// `DomainResult<()>`, with `DomainError::Forbidden` when denied.
+///
+/// # Usage
+///
+/// ```rust,ignore
+/// use brylix::authorize;
+///
+/// // In a resolver
+/// authorize!(ctx, "update", &task).map_err(gql_from_domain)?;
+///
+/// // In a service returning DomainResult
+/// authorize!(data, "delete", &task)?;
+/// ```
+#[macro_export]
+macro_rules! authorize {
+    ($ctx:expr, $action:expr, $resource:expr) => {
+        $crate::auth::policy::authorize(
+            $crate::auth::policy::AsContextData::context_data($ctx),
+            $action,
+            $resource,
+        )
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sea_orm::DatabaseConnection;
+
+    struct Task {
+        owner: Option<i64>,
+    }
+
+    impl Owned for Task {
+        fn owner_id(&self) -> Option<i64> {
+            self.owner
+        }
+    }
+
+    struct Invoice;
+
+    fn context(user: Option<&str>, admin: bool) -> ContextData {
+        let role = user.and_then(|u| u.parse().ok()).map(|id| {
+            if admin {
+                crate::auth::roles::AuthRole::Admin(id)
+            } else {
+                crate::auth::roles::AuthRole::User(id)
+            }
+        });
+        ContextData::single_tenant(
+            DatabaseConnection::Disconnected,
+            user.map(String::from),
+            role,
+            #[cfg(feature = "admin-override")]
+            None,
+        )
+    }
+
+    #[test]
+    fn test_owner_or_admin() {
+        let registry = PolicyRegistry::new().register::<Task>(OwnerOrAdmin);
+        let task = Task { owner: Some(5) };
+
+        assert!(registry.can(&context(Some("5"), false), "update", &task));
+        assert!(!registry.can(&context(Some("6"), false), "update", &task));
+        assert!(registry.can(&context(Some("1"), true), "update", &task));
+    }
+
+    #[test]
+    fn test_owner_only_ignores_admin() {
+        let registry = PolicyRegistry::new().register::<Task>(OwnerOnly);
+        let task = Task { owner: Some(5) };
+
+        assert!(!registry.can(&context(Some("1"), true), "update", &task));
+        assert!(!registry.can(&context(Some("5"), false), "update", &Task { owner: None }));
+    }
+
+    #[test]
+    fn test_api_keys_are_not_owners() {
+        let registry = PolicyRegistry::new().register::<Task>(OwnerOrAdmin);
+        let task = Task { owner: Some(5) };
+        let mut key = context(Some("5"), false);
+        key.role = Some(crate::auth::roles::AuthRole::ApiKey {
+            key_id: 1,
+            owner_id: 5,
+        });
+
+        assert!(!registry.can(&key, "update", &task));
+    }
+
+    #[test]
+    fn test_closure_policy() {
+        let registry = PolicyRegistry::new()
+            .register::<Invoice>(|_: &ContextData, action: &str, _: &Invoice| action == "read");
+
+        assert!(registry.can(&context(Some("1"), false), "read", &Invoice));
+        assert!(!registry.can(&context(Some("1"), false), "delete", &Invoice));
+    }
+
+    #[test]
+    fn test_unregistered_type_denied() {
+        let registry = PolicyRegistry::new();
+        assert!(!registry.has_policy::<Invoice>());
+        assert!(!registry.can(&context(Some("1"), true), "read", &Invoice));
+    }
+
+    #[test]
+    fn test_authorize_errors() {
+        let registry = PolicyRegistry::new().register::<Task>(OwnerOrAdmin);
+        let task = Task { owner: Some(5) };
+
+        assert!(registry.authorize(&context(Some("5"), false), "update", &task).is_ok());
+        assert!(matches!(
+            registry.authorize(&context(Some("6"), false), "update", &task),
+            Err(DomainError::Forbidden(_))
+        ));
+        assert!(matches!(
+            registry.authorize(&context(None, false), "update", &task),
+            Err(DomainError::Unauthorized)
+        ));
+    }
+
+    #[test]
+    fn test_authorize_macro_accepts_context_data() {
+        let data = context(Some("5"), false);
+        // Nothing registered globally for Invoice, so it is denied
+        assert!(authorize!(&data, "read", &Invoice).is_err());
+    }
+}
@@ -1,11 +1,30 @@
 //! Multi-role authentication support.
 //!
-//! Provides role-based authentication with support for multiple JWT secrets,
-//! allowing different token types (user, admin, custom) to coexist.
+//! Roles are normally carried in the token itself via the `role`/`roles`
+//! claims (see [`issue_jwt_with_role`](super::issue_jwt_with_role)), and
+//! [`jwt_middleware`](super::jwt_middleware) resolves them after a single
+//! signature check.
+//!
+//! The older multi-secret mode, where each role has its own JWT secret and
+//! the role is decided by which secret verifies the token, is still supported
+//! through [`MultiRoleJwtConfig`].
 //!
 //! # Usage
 //!
 //! ```rust,ignore
+//! use brylix::auth::{issue_jwt_with_role, jwt_middleware};
+//!
+//! // Issue a token carrying an explicit role claim
+//! let token = issue_jwt_with_role("42", None, "admin")?;
+//!
+//! // The middleware populates the role from the verified claim
+//! let result = jwt_middleware(&request).await?;
+//! assert_eq!(result.role, Some(AuthRole::Admin(42)));
+//! ```
+//!
+//! Multi-secret mode:
+//!
+//! ```rust,ignore
 //! use brylix::auth::roles::{AuthRole, MultiRoleJwtConfig};
 //!
 //! // Configure multiple JWT secrets
@@ -41,15 +60,47 @@ pub enum AuthRole {
 
     /// Custom role with a role name and user ID
     Custom(String, i64),
+
+    /// Machine client authenticated with an API key
+    ApiKey {
+        /// The API key ID
+        key_id: i64,
+        /// The ID of the user the key acts on behalf of
+        owner_id: i64,
+    },
 }
 
 impl AuthRole {
+    /// Build a role from its name and user ID.
+    ///
+    /// `"user"` and `"admin"` map to their dedicated variants; any other
+    /// name becomes [`AuthRole::Custom`].
+    pub fn from_name(name: &str, id: i64) -> Self {
+        match name {
+            "user" => AuthRole::User(id),
+            "admin" => AuthRole::Admin(id),
+            other => AuthRole::Custom(other.to_string(), id),
+        }
+    }
+
+    /// Build a role from verified JWT claims.
+    ///
+    /// Uses [`Claims::primary_role`] for the role name and the subject as the
+    /// user ID. Returns `None` if the token carries no role claim or the
+    /// subject is not a numeric ID.
+    pub fn from_claims(claims: &Claims) -> Option<Self> {
+        let name = claims.primary_role()?;
+        let id: i64 = claims.sub.parse().ok()?;
+        Some(Self::from_name(name, id))
+    }
+
     /// Get the user ID regardless of role type.
     pub fn id(&self) -> i64 {
         match self {
             AuthRole::User(id) => *id,
             AuthRole::Admin(id) => *id,
             AuthRole::Custom(_, id) => *id,
+            AuthRole::ApiKey { owner_id, .. } => *owner_id,
         }
     }
 
@@ -63,12 +114,18 @@ impl AuthRole {
         matches!(self, AuthRole::User(_))
     }
 
+    /// Check if this request was authenticated with an API key.
+    pub fn is_api_key(&self) -> bool {
+        matches!(self, AuthRole::ApiKey { .. })
+    }
+
     /// Get the role name as a string.
     pub fn role_name(&self) -> &str {
         match self {
             AuthRole::User(_) => "user",
             AuthRole::Admin(_) => "admin",
             AuthRole::Custom(name, _) => name,
+            AuthRole::ApiKey { .. } => "api_key",
         }
     }
 }
@@ -78,6 +135,13 @@ impl AuthRole {
 /// Each role has its own JWT secret, allowing different token types
 /// to be issued and validated independently.
 ///
+/// This mode costs one signature check per configured role and is kept for
+/// backwards compatibility. New applications should prefer a single secret
+/// with role claims (see [`issue_jwt_with_role`](super::issue_jwt_with_role)).
+/// In this mode the secret that verifies the token decides the role; any
+/// `role` claim inside the token is ignored so a user-secret token cannot
+/// claim admin.
+///
 /// # Example
 ///
 /// ```rust,ignore
@@ -119,13 +183,11 @@ impl MultiRoleJwtConfig {
             let validation = Validation::default();
 
             if let Ok(decoded) = decode::<Claims>(token, &decoding_key, &validation) {
+                if decoded.claims.mfa_pending {
+                    return None;
+                }
                 let user_id: i64 = decoded.claims.sub.parse().ok()?;
-
-                return Some(match role_name.as_str() {
-                    "user" => AuthRole::User(user_id),
-                    "admin" => AuthRole::Admin(user_id),
-                    other => AuthRole::Custom(other.to_string(), user_id),
-                });
+                return Some(AuthRole::from_name(role_name, user_id));
             }
         }
         None
@@ -173,9 +235,10 @@ pub fn require_admin(ctx: &Context<'_>) -> Result<i64, GqlError> {
         }
     }
 
-    // Check admin override (feature-gated)
+    // Check admin override (feature-gated); action-scoped overrides only
+    // count in require_admin_override_for()
     #[cfg(feature = "admin-override")]
-    if let Some(ao) = &data.admin_override {
+    if let Some(ao) = crate::auth::admin_override::usable_admin_override(data, None) {
         return Ok(ao.admin_id);
     }
 
@@ -239,6 +302,75 @@ mod tests {
         assert_eq!(role.role_name(), "moderator");
     }
 
+    fn claims(sub: &str, role: Option<&str>, roles: &[&str]) -> Claims {
+        Claims {
+            sub: sub.to_string(),
+            tenant: None,
+            exp: 0,
+            role: role.map(String::from),
+            roles: roles.iter().map(|r| r.to_string()).collect(),
+            mfa_pending: false,
+            act: None,
+            sid: None,
+            jti: None,
+        }
+    }
+
+    #[test]
+    fn test_auth_role_api_key() {
+        let role = AuthRole::ApiKey {
+            key_id: 3,
+            owner_id: 42,
+        };
+        assert_eq!(role.id(), 42);
+        assert!(role.is_api_key());
+        assert!(!role.is_admin());
+        assert_eq!(role.role_name(), "api_key");
+    }
+
+    #[test]
+    fn test_auth_role_from_name() {
+        assert_eq!(AuthRole::from_name("user", 1), AuthRole::User(1));
+        assert_eq!(AuthRole::from_name("admin", 2), AuthRole::Admin(2));
+        assert_eq!(
+            AuthRole::from_name("moderator", 3),
+            AuthRole::Custom("moderator".to_string(), 3)
+        );
+    }
+
+    #[test]
+    fn test_auth_role_from_claims() {
+        assert_eq!(
+            AuthRole::from_claims(&claims("5", Some("admin"), &[])),
+            Some(AuthRole::Admin(5))
+        );
+        assert_eq!(
+            AuthRole::from_claims(&claims("5", None, &["editor"])),
+            Some(AuthRole::Custom("editor".to_string(), 5))
+        );
+        assert_eq!(AuthRole::from_claims(&claims("5", None, &[])), None);
+        assert_eq!(AuthRole::from_claims(&claims("abc", Some("user"), &[])), None);
+    }
+
+    #[test]
+    fn test_multi_role_secret_decides_role() {
+        use jsonwebtoken::{encode, EncodingKey, Header};
+
+        let mut c = claims("9", Some("admin"), &[]);
+        c.exp = (chrono::Utc::now().timestamp() + 3600) as usize;
+        let token = encode(
+            &Header::default(),
+            &c,
+            &EncodingKey::from_secret("user-secret".as_ref()),
+        )
+        .unwrap();
+
+        let config = MultiRoleJwtConfig::new()
+            .add_role("user", "user-secret".to_string())
+            .add_role("admin", "admin-secret".to_string());
+        assert_eq!(config.validate(&token), Some(AuthRole::User(9)));
+    }
+
     #[test]
     fn test_multi_role_config_builder() {
         let config = MultiRoleJwtConfig::new()
@@ -0,0 +1,802 @@
+//! Device/session management.
+//!
+//! Every login can be recorded as a [`Session`] (device, IP, user agent,
+//! created and last-seen times). The session ID is embedded in the JWT as
+//! the `sid` claim, so users can list where they are logged in and revoke
+//! individual sessions; [`jwt_middleware`] rejects tokens whose session has
+//! been revoked or has expired. Sessions are scoped by tenant and user ID,
+//! since user IDs repeat across tenants.
+//!
+//! Once the store is initialized, magic link, MFA, OAuth and impersonation
+//! logins are recorded as sessions too. Tokens from
+//! [`issue_jwt`](super::issue_jwt) carry no `sid` and cannot be listed or
+//! revoked; call [`require_sessions`] once every login goes through
+//! [`start_session`] to reject them.
+//!
+//! Register the store once at startup with [`init_session_store`]. In
+//! multi-tenant mode the store must be reachable for every tenant (for
+//! example a table in a shared database).
+//!
+//! # Usage
+//!
+//! ```rust,ignore
+//! use brylix::auth::sessions::{init_session_store, list_sessions, revoke_session, start_session, SeaOrmSessionStore, SessionInfo};
+//!
+//! // At startup
+//! init_session_store(Arc::new(SeaOrmSessionStore::new(db.clone())));
+//!
+//! // Login mutation
+//! let info = SessionInfo::from_request(&request).with_device("iPhone app");
+//! let (session, token) = start_session(&user.id.to_string(), None, Some("user"), info).await?;
+//!
+//! // "Where am I logged in?"
+//! let sessions = list_sessions(data.tenant_name(), &user_id).await?;
+//!
+//! // Sign out one device
+//! revoke_session(data.tenant_name(), &user_id, &session_id).await?;
+//! ```
+//!
+//! [`jwt_middleware`]: super::jwt_middleware
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use chrono::{DateTime, Duration, Utc};
+use lambda_http::Request;
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, Set};
+
+use super::claims::Claims;
+use super::password::random_alphanumeric;
+use super::throttle::client_ip;
+use crate::config::Config;
+use crate::errors::{DomainError, DomainResult};
+
+/// Global session store
+static STORE: OnceLock<Arc<dyn SessionStore>> = OnceLock::new();
+
+/// Whether tokens without a `sid` claim are rejected
+static REQUIRE_SID: AtomicBool = AtomicBool::new(false);
+
+/// Length of generated session IDs.
+const SESSION_ID_LEN: usize = 32;
+
+/// `last_seen_at` is only written when older than this, to limit writes.
+const TOUCH_INTERVAL_SECS: i64 = 60;
+
+/// A recorded login.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Session {
+    /// Session ID (`sid` claim)
+    pub id: String,
+    /// User ID (JWT subject)
+    pub user_id: String,
+    /// Tenant name (multi-tenant mode)
+    pub tenant: Option<String>,
+    /// Device label supplied by the client (e.g. "iPhone app")
+    pub device: Option<String>,
+    /// Client IP at login
+    pub ip: Option<String>,
+    /// Client user agent at login
+    pub user_agent: Option<String>,
+    /// Login time
+    pub created_at: DateTime<Utc>,
+    /// Last authenticated request
+    pub last_seen_at: DateTime<Utc>,
+    /// When the session's token expires
+    pub expires_at: DateTime<Utc>,
+    /// Set once the session has been revoked
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+impl Session {
+    /// Check whether the session is neither revoked nor expired.
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        self.revoked_at.is_none() && self.expires_at > now
+    }
+
+    fn belongs_to(&self, tenant: Option<&str>, user_id: &str) -> bool {
+        self.tenant.as_deref() == tenant && self.user_id == user_id
+    }
+}
+
+/// Client details recorded with a new session.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SessionInfo {
+    /// Device label
+    pub device: Option<String>,
+    /// Client IP
+    pub ip: Option<String>,
+    /// Client user agent
+    pub user_agent: Option<String>,
+}
+
+impl SessionInfo {
+    /// Create empty client details.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Read the client IP and user agent from the HTTP request.
+    pub fn from_request(request: &Request) -> Self {
+        Self {
+            device: None,
+            ip: client_ip(request),
+            user_agent: request
+                .headers()
+                .get("User-Agent")
+                .and_then(|v| v.to_str().ok())
+                .map(String::from),
+        }
+    }
+
+    /// Set the device label.
+    pub fn with_device(mut self, device: impl Into<String>) -> Self {
+        self.device = Some(device.into());
+        self
+    }
+}
+
+/// Storage for sessions.
+#[async_trait::async_trait]
+pub trait SessionStore: Send + Sync {
+    /// Persist a new session.
+    async fn create(&self, session: Session) -> DomainResult<()>;
+
+    /// Find a session by ID.
+    async fn find(&self, id: &str) -> DomainResult<Option<Session>>;
+
+    /// List a user's sessions in `tenant`, including revoked and expired
+    /// ones. User IDs are only unique within a tenant.
+    async fn list_for_user(
+        &self,
+        tenant: Option<&str>,
+        user_id: &str,
+    ) -> DomainResult<Vec<Session>>;
+
+    /// Update the last-seen time.
+    async fn touch(&self, id: &str, at: DateTime<Utc>) -> DomainResult<()>;
+
+    /// Revoke a session. Returns `false` if it was unknown or already revoked.
+    async fn revoke(&self, id: &str, at: DateTime<Utc>) -> DomainResult<bool>;
+}
+
+/// Initialize the global session store.
+///
+/// Call once at startup. Later calls are ignored.
+pub fn init_session_store(store: Arc<dyn SessionStore>) {
+    if STORE.set(store).is_err() {
+        tracing::debug!("Session store already initialized");
+    }
+}
+
+/// Reject tokens without a `sid` claim.
+///
+/// Call at startup, after [`init_session_store`], once every login goes
+/// through [`start_session`] or the other session-aware login helpers.
+/// Tokens issued earlier with [`issue_jwt`](super::issue_jwt) stop working
+/// immediately, so they can no longer outlive [`revoke_other_sessions`].
+pub fn require_sessions() {
+    REQUIRE_SID.store(true, Ordering::Relaxed);
+}
+
+/// Get the global session store, if initialized.
+pub fn session_store() -> Option<&'static Arc<dyn SessionStore>> {
+    STORE.get()
+}
+
+fn store() -> DomainResult<&'static Arc<dyn SessionStore>> {
+    session_store().ok_or_else(|| DomainError::Internal("Session store not initialized".into()))
+}
+
+/// Record a login and issue a JWT bound to the new session.
+///
+/// The token expires after the configured `JWT_EXP_DAYS`, like
+/// [`issue_jwt`](super::issue_jwt).
+///
+/// # Errors
+///
+/// Returns an error if the config or session store is missing, storage
+/// fails, or the token cannot be issued
+pub async fn start_session(
+    user_id: &str,
+    tenant: Option<&str>,
+    role: Option<&str>,
+    info: SessionInfo,
+) -> DomainResult<(Session, String)> {
+    let config = Config::try_get().ok_or(DomainError::ConfigNotInitialized)?;
+    let claims = super::login_claims(user_id, tenant, role.map(String::from), Vec::new(), config);
+    start_session_with_claims(claims, info).await
+}
+
+/// Record a session for `claims` and issue a JWT bound to it.
+///
+/// The session takes its user, tenant and expiry from the claims; `sid` is
+/// overwritten with the new session ID.
+pub(crate) async fn start_session_with_claims(
+    mut claims: Claims,
+    info: SessionInfo,
+) -> DomainResult<(Session, String)> {
+    let config = Config::try_get().ok_or(DomainError::ConfigNotInitialized)?;
+    let store = store()?;
+    let now = Utc::now();
+    let session = Session {
+        id: random_alphanumeric(SESSION_ID_LEN),
+        user_id: claims.sub.clone(),
+        tenant: claims.tenant.clone(),
+        device: info.device,
+        ip: info.ip,
+        user_agent: info.user_agent,
+        created_at: now,
+        last_seen_at: now,
+        expires_at: DateTime::from_timestamp(claims.exp as i64, 0).unwrap_or(now),
+        revoked_at: None,
+    };
+
+    claims.sid = Some(session.id.clone());
+    let token = super::encode_jwt(&claims, config).map_err(DomainError::TokenIssueFailed)?;
+
+    store.create(session.clone()).await?;
+    Ok((session, token))
+}
+
+/// Issue a login token for `claims`, bound to a new session when the
+/// session store is initialized. Without a store the token is a plain JWT.
+pub(crate) async fn issue_login_token(claims: Claims, info: SessionInfo) -> DomainResult<String> {
+    if session_store().is_some() {
+        return Ok(start_session_with_claims(claims, info).await?.1);
+    }
+    let config = Config::try_get().ok_or(DomainError::ConfigNotInitialized)?;
+    super::encode_jwt(&claims, config).map_err(DomainError::TokenIssueFailed)
+}
+
+/// List a user's active sessions in `tenant`, most recently used first.
+///
+/// # Errors
+///
+/// Returns an error if the session store is missing or storage fails
+pub async fn list_sessions(tenant: Option<&str>, user_id: &str) -> DomainResult<Vec<Session>> {
+    let now = Utc::now();
+    let mut sessions: Vec<Session> = store()?
+        .list_for_user(tenant, user_id)
+        .await?
+        .into_iter()
+        .filter(|s| s.is_active(now))
+        .collect();
+    sessions.sort_by_key(|s| std::cmp::Reverse(s.last_seen_at));
+    Ok(sessions)
+}
+
+/// Revoke one of the user's sessions.
+///
+/// # Errors
+///
+/// Returns `NotFound` if the session does not exist or belongs to another
+/// user or tenant, or an error if storage fails
+pub async fn revoke_session(
+    tenant: Option<&str>,
+    user_id: &str,
+    session_id: &str,
+) -> DomainResult<()> {
+    let store = store()?;
+    match store.find(session_id).await? {
+        Some(session) if session.belongs_to(tenant, user_id) => {
+            store.revoke(session_id, Utc::now()).await?;
+            Ok(())
+        }
+        _ => Err(DomainError::NotFound("Session not found".into())),
+    }
+}
+
+/// Revoke all of the user's sessions except `keep` (typically the current
+/// session). Returns the number of sessions revoked.
+///
+/// # Errors
+///
+/// Returns an error if the session store is missing or storage fails
+pub async fn revoke_other_sessions(
+    tenant: Option<&str>,
+    user_id: &str,
+    keep: Option<&str>,
+) -> DomainResult<usize> {
+    let store = store()?;
+    let now = Utc::now();
+    let mut revoked = 0;
+    for session in store.list_for_user(tenant, user_id).await? {
+        if session.is_active(now)
+            && Some(session.id.as_str()) != keep
+            && store.revoke(&session.id, now).await?
+        {
+            revoked += 1;
+        }
+    }
+    Ok(revoked)
+}
+
+/// Check the session of a validated token.
+///
+/// Called by the JWT middleware. Tokens carrying a `sid` claim must belong
+/// to an active session; tokens without one are accepted unless
+/// [`require_sessions`] was called.
+///
+/// # Errors
+///
+/// Returns an error if the session is not active or the token has none
+/// although sessions are required
+pub(crate) async fn check_token_session(claims: &Claims) -> Result<(), String> {
+    verify_token_session(claims, REQUIRE_SID.load(Ordering::Relaxed)).await
+}
+
+async fn verify_token_session(claims: &Claims, require_sid: bool) -> Result<(), String> {
+    match &claims.sid {
+        Some(sid) => check_session(sid, claims.tenant.as_deref(), &claims.sub).await,
+        None if require_sid => Err("Token is not bound to a session".into()),
+        None => Ok(()),
+    }
+}
+
+/// Check that a session is active and update its last-seen time.
+///
+/// Called for tokens carrying a `sid` claim.
+///
+/// # Errors
+///
+/// Returns an error if the session store is missing, or the session is
+/// unknown, revoked, expired, or belongs to another user or tenant
+async fn check_session(
+    session_id: &str,
+    tenant: Option<&str>,
+    user_id: &str,
+) -> Result<(), String> {
+    let store = store().map_err(|e| e.to_string())?;
+    let now = Utc::now();
+    let session = store
+        .find(session_id)
+        .await
+        .map_err(|e| e.to_string())?
+        .filter(|s| s.belongs_to(tenant, user_id) && s.is_active(now))
+        .ok_or("Session has been revoked")?;
+
+    if now - session.last_seen_at > Duration::seconds(TOUCH_INTERVAL_SECS) {
+        if let Err(e) = store.touch(session_id, now).await {
+            tracing::warn!(error = %e, "Failed to update session last-seen time");
+        }
+    }
+    Ok(())
+}
+
+/// In-process session store.
+///
+/// Sessions are lost on cold start and not shared between Lambda
+/// instances; use [`SeaOrmSessionStore`] in production.
+#[derive(Debug, Default)]
+pub struct MemorySessionStore {
+    sessions: Mutex<HashMap<String, Session>>,
+}
+
+impl MemorySessionStore {
+    /// Create an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn lock(&self) -> DomainResult<std::sync::MutexGuard<'_, HashMap<String, Session>>> {
+        self.sessions
+            .lock()
+            .map_err(|_| DomainError::Internal("Session store lock poisoned".into()))
+    }
+}
+
+#[async_trait::async_trait]
+impl SessionStore for MemorySessionStore {
+    async fn create(&self, session: Session) -> DomainResult<()> {
+        self.lock()?.insert(session.id.clone(), session);
+        Ok(())
+    }
+
+    async fn find(&self, id: &str) -> DomainResult<Option<Session>> {
+        Ok(self.lock()?.get(id).cloned())
+    }
+
+    async fn list_for_user(
+        &self,
+        tenant: Option<&str>,
+        user_id: &str,
+    ) -> DomainResult<Vec<Session>> {
+        Ok(self
+            .lock()?
+            .values()
+            .filter(|s| s.belongs_to(tenant, user_id))
+            .cloned()
+            .collect())
+    }
+
+    async fn touch(&self, id: &str, at: DateTime<Utc>) -> DomainResult<()> {
+        if let Some(session) = self.lock()?.get_mut(id) {
+            session.last_seen_at = at;
+        }
+        Ok(())
+    }
+
+    async fn revoke(&self, id: &str, at: DateTime<Utc>) -> DomainResult<bool> {
+        match self.lock()?.get_mut(id) {
+            Some(session) if session.revoked_at.is_none() => {
+                session.revoked_at = Some(at);
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+}
+
+/// SeaORM-backed session store using the `user_sessions` table.
+///
+/// Add [`migration::Migration`] to your migrator to create the table.
+#[derive(Debug, Clone)]
+pub struct SeaOrmSessionStore {
+    db: DatabaseConnection,
+}
+
+impl SeaOrmSessionStore {
+    /// Create a store using the given connection.
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self { db }
+    }
+}
+
+impl From<entity::Model> for Session {
+    fn from(m: entity::Model) -> Self {
+        Self {
+            id: m.id,
+            user_id: m.user_id,
+            tenant: m.tenant,
+            device: m.device,
+            ip: m.ip,
+            user_agent: m.user_agent,
+            created_at: m.created_at,
+            last_seen_at: m.last_seen_at,
+            expires_at: m.expires_at,
+            revoked_at: m.revoked_at,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl SessionStore for SeaOrmSessionStore {
+    async fn create(&self, session: Session) -> DomainResult<()> {
+        let model = entity::ActiveModel {
+            id: Set(session.id),
+            user_id: Set(session.user_id),
+            tenant: Set(session.tenant),
+            device: Set(session.device),
+            ip: Set(session.ip),
+            user_agent: Set(session.user_agent),
+            created_at: Set(session.created_at),
+            last_seen_at: Set(session.last_seen_at),
+            expires_at: Set(session.expires_at),
+            revoked_at: Set(session.revoked_at),
+        };
+        entity::Entity::insert(model)
+            .exec_without_returning(&self.db)
+            .await?;
+        Ok(())
+    }
+
+    async fn find(&self, id: &str) -> DomainResult<Option<Session>> {
+        Ok(entity::Entity::find_by_id(id.to_string())
+            .one(&self.db)
+            .await?
+            .map(Session::from))
+    }
+
+    async fn list_for_user(
+        &self,
+        tenant: Option<&str>,
+        user_id: &str,
+    ) -> DomainResult<Vec<Session>> {
+        let tenant = match tenant {
+            Some(tenant) => entity::Column::Tenant.eq(tenant),
+            None => entity::Column::Tenant.is_null(),
+        };
+        Ok(entity::Entity::find()
+            .filter(tenant)
+            .filter(entity::Column::UserId.eq(user_id))
+            .order_by_desc(entity::Column::LastSeenAt)
+            .all(&self.db)
+            .await?
+            .into_iter()
+            .map(Session::from)
+            .collect())
+    }
+
+    async fn touch(&self, id: &str, at: DateTime<Utc>) -> DomainResult<()> {
+        entity::Entity::update_many()
+            .col_expr(entity::Column::LastSeenAt, at.into())
+            .filter(entity::Column::Id.eq(id))
+            .exec(&self.db)
+            .await?;
+        Ok(())
+    }
+
+    async fn revoke(&self, id: &str, at: DateTime<Utc>) -> DomainResult<bool> {
+        let result = entity::Entity::update_many()
+            .col_expr(entity::Column::RevokedAt, Some(at).into())
+            .filter(entity::Column::Id.eq(id))
+            .filter(entity::Column::RevokedAt.is_null())
+            .exec(&self.db)
+            .await?;
+        Ok(result.rows_affected == 1)
+    }
+}
+
+/// SeaORM entity for the `user_sessions` table.
+pub mod entity {
+    use sea_orm::entity::prelude::*;
+
+    /// A recorded login.
+    #[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+    #[sea_orm(table_name = "user_sessions")]
+    pub struct Model {
+        /// Session ID (`sid` claim)
+        #[sea_orm(primary_key, auto_increment = false)]
+        pub id: String,
+        /// User ID (JWT subject)
+        pub user_id: String,
+        /// Tenant name
+        pub tenant: Option<String>,
+        /// Device label
+        pub device: Option<String>,
+        /// Client IP at login
+        pub ip: Option<String>,
+        /// Client user agent at login
+        pub user_agent: Option<String>,
+        /// Login time
+        pub created_at: DateTimeUtc,
+        /// Last authenticated request
+        pub last_seen_at: DateTimeUtc,
+        /// Token expiry
+        pub expires_at: DateTimeUtc,
+        /// Revocation time
+        pub revoked_at: Option<DateTimeUtc>,
+    }
+
+    /// No relations.
+    #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+    pub enum Relation {}
+
+    impl ActiveModelBehavior for ActiveModel {}
+}
+
+/// Migration creating the `user_sessions` table.
+pub mod migration {
+    use sea_orm_migration::prelude::*;
+
+    /// Creates the `user_sessions` table.
+    pub struct Migration;
+
+    impl MigrationName for Migration {
+        fn name(&self) -> &str {
+            "m20261018_000007_create_user_sessions_table"
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl MigrationTrait for Migration {
+        async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+            manager
+                .create_table(
+                    Table::create()
+                        .table(UserSessions::Table)
+                        .if_not_exists()
+                        .col(
+                            ColumnDef::new(UserSessions::Id)
+                                .string_len(64)
+                                .not_null()
+                                .primary_key(),
+                        )
+                        .col(
+                            ColumnDef::new(UserSessions::UserId)
+                                .string_len(255)
+                                .not_null(),
+                        )
+                        .col(ColumnDef::new(UserSessions::Tenant).string_len(255).null())
+                        .col(ColumnDef::new(UserSessions::Device).string_len(255).null())
+                        .col(ColumnDef::new(UserSessions::Ip).string_len(64).null())
+                        .col(
+                            ColumnDef::new(UserSessions::UserAgent)
+                                .string_len(512)
+                                .null(),
+                        )
+                        .col(
+                            ColumnDef::new(UserSessions::CreatedAt)
+                                .timestamp()
+                                .not_null(),
+                        )
+                        .col(
+                            ColumnDef::new(UserSessions::LastSeenAt)
+                                .timestamp()
+                                .not_null(),
+                        )
+                        .col(
+                            ColumnDef::new(UserSessions::ExpiresAt)
+                                .timestamp()
+                                .not_null(),
+                        )
+                        .col(ColumnDef::new(UserSessions::RevokedAt).timestamp().null())
+                        .to_owned(),
+                )
+                .await?;
+
+            manager
+                .create_index(
+                    Index::create()
+                        .name("idx_user_sessions_tenant_user_id")
+                        .table(UserSessions::Table)
+                        .col(UserSessions::Tenant)
+                        .col(UserSessions::UserId)
+                        .to_owned(),
+                )
+                .await
+        }
+
+        async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+            manager
+                .drop_table(Table::drop().table(UserSessions::Table).to_owned())
+                .await
+        }
+    }
+
+    #[derive(Iden)]
+    enum UserSessions {
+        Table,
+        Id,
+        UserId,
+        Tenant,
+        Device,
+        Ip,
+        UserAgent,
+        CreatedAt,
+        LastSeenAt,
+        ExpiresAt,
+        RevokedAt,
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::auth::jwt_middleware;
+    use crate::auth::tests::init_test_config;
+
+    /// Initialize the test config and a shared in-memory session store.
+    pub(crate) fn init() {
+        init_test_config();
+        static TEST_STORE: OnceLock<Arc<MemorySessionStore>> = OnceLock::new();
+        init_session_store(
+            TEST_STORE
+                .get_or_init(|| Arc::new(MemorySessionStore::new()))
+                .clone(),
+        );
+    }
+
+    fn bearer(token: &str) -> Request {
+        http::Request::builder()
+            .header("Authorization", format!("Bearer {}", token))
+            .body(lambda_http::Body::Empty)
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_session_list_and_revoke() {
+        init();
+        let request = http::Request::builder()
+            .header("X-Forwarded-For", "10.0.0.1")
+            .header("User-Agent", "Safari")
+            .body(lambda_http::Body::Empty)
+            .unwrap();
+
+        let (laptop, laptop_token) = start_session(
+            "sess-1",
+            None,
+            Some("user"),
+            SessionInfo::from_request(&request).with_device("Laptop"),
+        )
+        .await
+        .unwrap();
+        let (phone, _) = start_session("sess-1", None, None, SessionInfo::new())
+            .await
+            .unwrap();
+        assert_eq!(laptop.ip.as_deref(), Some("10.0.0.1"));
+        assert_eq!(laptop.user_agent.as_deref(), Some("Safari"));
+
+        let result = jwt_middleware(&bearer(&laptop_token)).await.unwrap();
+        assert_eq!(result.session_id.as_deref(), Some(laptop.id.as_str()));
+        assert_eq!(list_sessions(None, "sess-1").await.unwrap().len(), 2);
+
+        // Other users cannot revoke the session
+        assert!(matches!(
+            revoke_session(None, "sess-2", &laptop.id).await,
+            Err(DomainError::NotFound(_))
+        ));
+
+        revoke_session(None, "sess-1", &laptop.id).await.unwrap();
+        assert!(jwt_middleware(&bearer(&laptop_token)).await.is_err());
+
+        let remaining = list_sessions(None, "sess-1").await.unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].id, phone.id);
+    }
+
+    #[tokio::test]
+    async fn test_sid_less_tokens_can_be_refused() {
+        init();
+        let plain = crate::auth::validate_jwt(&crate::auth::issue_jwt("sess-5", None).unwrap())
+            .unwrap();
+        let (_, token) = start_session("sess-5", None, None, SessionInfo::new())
+            .await
+            .unwrap();
+        let bound = crate::auth::validate_jwt(&token).unwrap();
+
+        assert!(verify_token_session(&plain, false).await.is_ok());
+        assert_eq!(
+            verify_token_session(&plain, true).await.unwrap_err(),
+            "Token is not bound to a session"
+        );
+        assert!(verify_token_session(&bound, true).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_revoke_other_sessions() {
+        init();
+        let (current, current_token) = start_session("sess-3", None, None, SessionInfo::new())
+            .await
+            .unwrap();
+        let (_, other_token) = start_session("sess-3", None, None, SessionInfo::new())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            revoke_other_sessions(None, "sess-3", Some(&current.id))
+                .await
+                .unwrap(),
+            1
+        );
+        assert!(jwt_middleware(&bearer(&current_token)).await.is_ok());
+        assert!(jwt_middleware(&bearer(&other_token)).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_sessions_are_scoped_by_tenant() {
+        init();
+        let (acme, _) = start_session("sess-4", Some("acme"), None, SessionInfo::new())
+            .await
+            .unwrap();
+        start_session("sess-4", Some("globex"), None, SessionInfo::new())
+            .await
+            .unwrap();
+
+        // The same user ID in another tenant is another user
+        let sessions = list_sessions(Some("acme"), "sess-4").await.unwrap();
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].id, acme.id);
+        assert!(matches!(
+            revoke_session(Some("globex"), "sess-4", &acme.id).await,
+            Err(DomainError::NotFound(_))
+        ));
+        assert!(check_session(&acme.id, Some("globex"), "sess-4")
+            .await
+            .is_err());
+        assert!(check_session(&acme.id, Some("acme"), "sess-4")
+            .await
+            .is_ok());
+        assert_eq!(
+            revoke_other_sessions(Some("globex"), "sess-4", None)
+                .await
+                .unwrap(),
+            1
+        );
+        assert!(check_session(&acme.id, Some("acme"), "sess-4")
+            .await
+            .is_ok());
+    }
+}
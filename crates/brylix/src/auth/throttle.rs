@@ -0,0 +1,675 @@
+//! Login brute-force protection.
+//!
+//! Failed login attempts are counted per account and per client IP. Once a
+//! key reaches its allowance within the tracking window it is locked with
+//! exponential backoff (1s, 2s, 4s, ... up to [`ThrottleConfig::max_lockout`]),
+//! and checks fail with [`DomainError::TooManyAttempts`] carrying a
+//! retry-after hint. A successful login clears the account's counter.
+//!
+//! Counters live in an [`AttemptStore`]: [`MemoryAttemptStore`] for a single
+//! process or tests, [`SeaOrmAttemptStore`] to share them across Lambda
+//! instances. Failures are counted with an atomic increment, so parallel
+//! guesses cannot overwrite each other's counts.
+//!
+//! # Usage
+//!
+//! ```rust,ignore
+//! use brylix::auth::throttle::{client_ip, LoginThrottle, SeaOrmAttemptStore};
+//!
+//! let throttle = LoginThrottle::new(Arc::new(SeaOrmAttemptStore::new(db.clone())));
+//! let ip = client_ip(&request);
+//!
+//! // In the login mutation
+//! throttle.check(&input.email, ip.as_deref()).await.map_err(gql_from_domain)?;
+//! if !verify_password(&input.password, &user.password_hash).map_err(gql_internal)? {
+//!     throttle.record_failure(&input.email, ip.as_deref()).await.map_err(gql_from_domain)?;
+//!     return Err(gql_from_domain(DomainError::InvalidCredentials));
+//! }
+//! throttle.record_success(&input.email).await.map_err(gql_from_domain)?;
+//! ```
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Duration, Utc};
+use lambda_http::request::RequestContext;
+use lambda_http::Request;
+use lambda_http::RequestExt;
+use sea_orm::sea_query::{Expr, OnConflict};
+use sea_orm::{ColumnTrait, Condition, DatabaseConnection, EntityTrait, QueryFilter, Set};
+
+use crate::audit::{actions, AuditEvent};
+use crate::errors::{DomainError, DomainResult};
+
+/// Throttling limits.
+#[derive(Debug, Clone)]
+pub struct ThrottleConfig {
+    /// Failures allowed per account before it is locked (default: 5)
+    pub max_account_attempts: u32,
+    /// Failures allowed per IP before it is locked (default: 50)
+    pub max_ip_attempts: u32,
+    /// Failures older than this are forgotten (default: 15 minutes)
+    pub window: Duration,
+    /// First lockout duration; doubles with every further failure (default: 1 second)
+    pub base_lockout: Duration,
+    /// Longest lockout (default: 15 minutes)
+    pub max_lockout: Duration,
+}
+
+impl Default for ThrottleConfig {
+    fn default() -> Self {
+        Self {
+            max_account_attempts: 5,
+            max_ip_attempts: 50,
+            window: Duration::minutes(15),
+            base_lockout: Duration::seconds(1),
+            max_lockout: Duration::minutes(15),
+        }
+    }
+}
+
+impl ThrottleConfig {
+    /// Create a config with the defaults.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the failures allowed per account and per IP.
+    pub fn with_max_attempts(mut self, per_account: u32, per_ip: u32) -> Self {
+        self.max_account_attempts = per_account;
+        self.max_ip_attempts = per_ip;
+        self
+    }
+
+    /// Set the window after which failures are forgotten.
+    pub fn with_window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Set the first and longest lockout durations.
+    pub fn with_lockout(mut self, base: Duration, max: Duration) -> Self {
+        self.base_lockout = base;
+        self.max_lockout = max;
+        self
+    }
+
+    /// Lockout for a key that has failed `failures` times, if any.
+    fn lockout_for(&self, failures: u32, allowed: u32) -> Option<Duration> {
+        if failures < allowed {
+            return None;
+        }
+        let doublings = (failures - allowed).min(20);
+        let lockout = self
+            .base_lockout
+            .checked_mul(1 << doublings)
+            .unwrap_or(self.max_lockout);
+        Some(lockout.min(self.max_lockout))
+    }
+}
+
+/// Failure counter for one key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AttemptRecord {
+    /// Consecutive failures within the window
+    pub failures: u32,
+    /// Time of the last failure
+    pub last_failure_at: DateTime<Utc>,
+    /// The key is rejected until this time
+    pub locked_until: Option<DateTime<Utc>>,
+}
+
+/// Storage backend for failure counters.
+#[async_trait::async_trait]
+pub trait AttemptStore: Send + Sync {
+    /// Get the counter for a key.
+    async fn get(&self, key: &str) -> DomainResult<Option<AttemptRecord>>;
+
+    /// Atomically count a failure for a key at `now` and return the new
+    /// count.
+    ///
+    /// The count restarts at 1 when the previous failure is at least
+    /// `window` old. An existing lock is kept.
+    async fn increment(&self, key: &str, now: DateTime<Utc>, window: Duration)
+        -> DomainResult<u32>;
+
+    /// Lock a key until `until`, unless it is already locked for longer.
+    async fn lock(&self, key: &str, until: DateTime<Utc>) -> DomainResult<()>;
+
+    /// Remove the counter for a key.
+    async fn clear(&self, key: &str) -> DomainResult<()>;
+}
+
+/// Login throttle combining a store and limits.
+#[derive(Clone)]
+pub struct LoginThrottle {
+    store: Arc<dyn AttemptStore>,
+    config: ThrottleConfig,
+}
+
+impl LoginThrottle {
+    /// Create a throttle with the default limits.
+    pub fn new(store: Arc<dyn AttemptStore>) -> Self {
+        Self {
+            store,
+            config: ThrottleConfig::default(),
+        }
+    }
+
+    /// Set the limits.
+    pub fn with_config(mut self, config: ThrottleConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Get the limits.
+    pub fn config(&self) -> &ThrottleConfig {
+        &self.config
+    }
+
+    /// Check if a login attempt is currently allowed.
+    ///
+    /// Call before verifying credentials.
+    ///
+    /// # Errors
+    ///
+    /// Returns `TooManyAttempts` if the account or IP is locked
+    pub async fn check(&self, account: &str, ip: Option<&str>) -> DomainResult<()> {
+        let now = Utc::now();
+        for key in keys(account, ip) {
+            if let Some(record) = self.store.get(&key).await? {
+                if let Some(until) = record.locked_until.filter(|until| *until > now) {
+                    return Err(too_many_attempts(until, now));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Record a failed login attempt.
+    ///
+    /// # Errors
+    ///
+    /// Returns `TooManyAttempts` if this failure locked the account or IP,
+    /// or an error if storage fails
+    pub async fn record_failure(&self, account: &str, ip: Option<&str>) -> DomainResult<()> {
+        let now = Utc::now();
+        let mut locked: Option<DateTime<Utc>> = None;
+
+        for key in keys(account, ip) {
+            let allowed = if key.starts_with("ip:") {
+                self.config.max_ip_attempts
+            } else {
+                self.config.max_account_attempts
+            };
+
+            let failures = self.store.increment(&key, now, self.config.window).await?;
+            let locked_until = self
+                .config
+                .lockout_for(failures, allowed)
+                .map(|lockout| now + lockout);
+
+            if let Some(until) = locked_until {
+                self.store.lock(&key, until).await?;
+                tracing::warn!(key = %key, failures, "Login throttled after repeated failures");
+                locked = locked.max(Some(until));
+            }
+        }
+
+        crate::audit::emit(
+            AuditEvent::new(actions::LOGIN_FAILED)
+                .with_target("account", account.trim().to_lowercase())
+                .with_ip(ip)
+                .with_metadata(serde_json::json!({ "locked": locked.is_some() })),
+        )
+        .await;
+
+        match locked {
+            Some(until) => Err(too_many_attempts(until, now)),
+            None => Ok(()),
+        }
+    }
+
+    /// Record a successful login, clearing the account's counter.
+    ///
+    /// The IP counter is kept so one valid account cannot be used to reset
+    /// an IP that is guessing other accounts.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if storage fails
+    pub async fn record_success(&self, account: &str) -> DomainResult<()> {
+        self.store.clear(&account_key(account)).await?;
+        crate::audit::emit(
+            AuditEvent::new(actions::LOGIN_SUCCEEDED)
+                .with_target("account", account.trim().to_lowercase()),
+        )
+        .await;
+        Ok(())
+    }
+}
+
+fn account_key(account: &str) -> String {
+    format!("account:{}", account.trim().to_lowercase())
+}
+
+fn keys(account: &str, ip: Option<&str>) -> Vec<String> {
+    let mut keys = vec![account_key(account)];
+    if let Some(ip) = ip {
+        keys.push(format!("ip:{}", ip));
+    }
+    keys
+}
+
+fn too_many_attempts(until: DateTime<Utc>, now: DateTime<Utc>) -> DomainError {
+    let millis = (until - now).num_milliseconds().max(0) as u64;
+    DomainError::TooManyAttempts {
+        retry_after_secs: millis.div_ceil(1000).max(1),
+    }
+}
+
+/// Get the client IP.
+///
+/// Uses the source IP that API Gateway records in the request context.
+/// Without one (ALB, local testing) it falls back to the last
+/// `X-Forwarded-For` entry, the address seen by the load balancer; earlier
+/// entries are supplied by the client and never trusted.
+pub fn client_ip(request: &Request) -> Option<String> {
+    let source_ip = match request.request_context_ref() {
+        Some(RequestContext::ApiGatewayV1(context)) => context.identity.source_ip.clone(),
+        Some(RequestContext::ApiGatewayV2(context)) => context.http.source_ip.clone(),
+        Some(RequestContext::WebSocket(context)) => context.identity.source_ip.clone(),
+        _ => None,
+    };
+    source_ip.filter(|ip| !ip.is_empty()).or_else(|| {
+        request
+            .headers()
+            .get_all("X-Forwarded-For")
+            .iter()
+            .next_back()
+            .and_then(|v| v.to_str().ok())
+            .and_then(|s| s.rsplit(',').next())
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+    })
+}
+
+/// In-process attempt store.
+///
+/// Counters are lost on cold start and not shared between Lambda
+/// instances; use [`SeaOrmAttemptStore`] in production.
+#[derive(Debug, Default)]
+pub struct MemoryAttemptStore {
+    records: Mutex<HashMap<String, AttemptRecord>>,
+}
+
+impl MemoryAttemptStore {
+    /// Create an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait::async_trait]
+impl AttemptStore for MemoryAttemptStore {
+    async fn get(&self, key: &str) -> DomainResult<Option<AttemptRecord>> {
+        let records = self
+            .records
+            .lock()
+            .map_err(|_| DomainError::Internal("Attempt store lock poisoned".into()))?;
+        Ok(records.get(key).cloned())
+    }
+
+    async fn increment(
+        &self,
+        key: &str,
+        now: DateTime<Utc>,
+        window: Duration,
+    ) -> DomainResult<u32> {
+        let mut records = self
+            .records
+            .lock()
+            .map_err(|_| DomainError::Internal("Attempt store lock poisoned".into()))?;
+        let record = records
+            .entry(key.to_string())
+            .or_insert_with(|| AttemptRecord {
+                failures: 0,
+                last_failure_at: now,
+                locked_until: None,
+            });
+        if now - record.last_failure_at >= window {
+            record.failures = 0;
+        }
+        record.failures += 1;
+        record.last_failure_at = now;
+        Ok(record.failures)
+    }
+
+    async fn lock(&self, key: &str, until: DateTime<Utc>) -> DomainResult<()> {
+        let mut records = self
+            .records
+            .lock()
+            .map_err(|_| DomainError::Internal("Attempt store lock poisoned".into()))?;
+        if let Some(record) = records.get_mut(key) {
+            record.locked_until = record.locked_until.max(Some(until));
+        }
+        Ok(())
+    }
+
+    async fn clear(&self, key: &str) -> DomainResult<()> {
+        let mut records = self
+            .records
+            .lock()
+            .map_err(|_| DomainError::Internal("Attempt store lock poisoned".into()))?;
+        records.remove(key);
+        Ok(())
+    }
+}
+
+/// SeaORM-backed attempt store using the `login_attempts` table.
+///
+/// Add [`migration::Migration`] to your migrator to create the table.
+#[derive(Debug, Clone)]
+pub struct SeaOrmAttemptStore {
+    db: DatabaseConnection,
+}
+
+impl SeaOrmAttemptStore {
+    /// Create a store using the given connection.
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait::async_trait]
+impl AttemptStore for SeaOrmAttemptStore {
+    async fn get(&self, key: &str) -> DomainResult<Option<AttemptRecord>> {
+        Ok(entity::Entity::find_by_id(key.to_string())
+            .one(&self.db)
+            .await?
+            .map(|m| AttemptRecord {
+                failures: m.failures.max(0) as u32,
+                last_failure_at: m.last_failure_at,
+                locked_until: m.locked_until,
+            }))
+    }
+
+    async fn increment(
+        &self,
+        key: &str,
+        now: DateTime<Utc>,
+        window: Duration,
+    ) -> DomainResult<u32> {
+        let failures = Expr::col((entity::Entity, entity::Column::Failures));
+        let last_failure_at = Expr::col((entity::Entity, entity::Column::LastFailureAt));
+        let model = entity::ActiveModel {
+            key: Set(key.to_string()),
+            failures: Set(1),
+            last_failure_at: Set(now),
+            locked_until: Set(None),
+        };
+        // Single upsert, so concurrent failures are all counted
+        entity::Entity::insert(model)
+            .on_conflict(
+                OnConflict::column(entity::Column::Key)
+                    .value(
+                        entity::Column::Failures,
+                        Expr::case(last_failure_at.lte(now - window), 1).finally(failures.add(1)),
+                    )
+                    .value(entity::Column::LastFailureAt, now)
+                    .to_owned(),
+            )
+            .exec(&self.db)
+            .await?;
+
+        let record = self.get(key).await?;
+        Ok(record.map_or(1, |r| r.failures))
+    }
+
+    async fn lock(&self, key: &str, until: DateTime<Utc>) -> DomainResult<()> {
+        entity::Entity::update_many()
+            .col_expr(entity::Column::LockedUntil, Expr::value(until))
+            .filter(entity::Column::Key.eq(key))
+            .filter(
+                Condition::any()
+                    .add(entity::Column::LockedUntil.is_null())
+                    .add(entity::Column::LockedUntil.lt(until)),
+            )
+            .exec(&self.db)
+            .await?;
+        Ok(())
+    }
+
+    async fn clear(&self, key: &str) -> DomainResult<()> {
+        entity::Entity::delete_by_id(key.to_string())
+            .exec(&self.db)
+            .await?;
+        Ok(())
+    }
+}
+
+/// SeaORM entity for the `login_attempts` table.
+pub mod entity {
+    use sea_orm::entity::prelude::*;
+
+    /// Failure counter for one account or IP.
+    #[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+    #[sea_orm(table_name = "login_attempts")]
+    pub struct Model {
+        /// Throttle key (`account:<name>` or `ip:<address>`)
+        #[sea_orm(primary_key, auto_increment = false)]
+        pub key: String,
+        /// Consecutive failures within the window
+        pub failures: i32,
+        /// Time of the last failure
+        pub last_failure_at: DateTimeUtc,
+        /// Locked until this time
+        pub locked_until: Option<DateTimeUtc>,
+    }
+
+    /// No relations.
+    #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+    pub enum Relation {}
+
+    impl ActiveModelBehavior for ActiveModel {}
+}
+
+/// Migration creating the `login_attempts` table.
+pub mod migration {
+    use sea_orm_migration::prelude::*;
+
+    /// Creates the `login_attempts` table.
+    pub struct Migration;
+
+    impl MigrationName for Migration {
+        fn name(&self) -> &str {
+            "m20261018_000004_create_login_attempts_table"
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl MigrationTrait for Migration {
+        async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+            manager
+                .create_table(
+                    Table::create()
+                        .table(LoginAttempts::Table)
+                        .if_not_exists()
+                        .col(
+                            ColumnDef::new(LoginAttempts::Key)
+                                .string_len(255)
+                                .not_null()
+                                .primary_key(),
+                        )
+                        .col(ColumnDef::new(LoginAttempts::Failures).integer().not_null())
+                        .col(
+                            ColumnDef::new(LoginAttempts::LastFailureAt)
+                                .timestamp()
+                                .not_null(),
+                        )
+                        .col(ColumnDef::new(LoginAttempts::LockedUntil).timestamp().null())
+                        .to_owned(),
+                )
+                .await
+        }
+
+        async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+            manager
+                .drop_table(Table::drop().table(LoginAttempts::Table).to_owned())
+                .await
+        }
+    }
+
+    #[derive(Iden)]
+    enum LoginAttempts {
+        Table,
+        Key,
+        Failures,
+        LastFailureAt,
+        LockedUntil,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn throttle(per_account: u32, per_ip: u32) -> LoginThrottle {
+        LoginThrottle::new(Arc::new(MemoryAttemptStore::new())).with_config(
+            ThrottleConfig::new()
+                .with_max_attempts(per_account, per_ip)
+                .with_lockout(Duration::seconds(10), Duration::seconds(60)),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_account_locked_after_max_attempts() {
+        let throttle = throttle(3, 100);
+
+        assert!(throttle.record_failure("jane@test.com", None).await.is_ok());
+        assert!(throttle.record_failure("jane@test.com", None).await.is_ok());
+        assert!(throttle.check("jane@test.com", None).await.is_ok());
+
+        let err = throttle.record_failure("jane@test.com", None).await.unwrap_err();
+        assert_eq!(err.code(), "TOO_MANY_ATTEMPTS");
+        assert_eq!(err.retry_after_secs(), Some(10));
+
+        // Account keys are case-insensitive
+        assert!(throttle.check("Jane@Test.com", None).await.is_err());
+        assert!(throttle.check("john@test.com", None).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_lockout_backs_off_exponentially() {
+        let throttle = throttle(1, 100);
+
+        let first = throttle.record_failure("jane", None).await.unwrap_err();
+        let second = throttle.record_failure("jane", None).await.unwrap_err();
+        let third = throttle.record_failure("jane", None).await.unwrap_err();
+        assert_eq!(first.retry_after_secs(), Some(10));
+        assert_eq!(second.retry_after_secs(), Some(20));
+        assert_eq!(third.retry_after_secs(), Some(40));
+
+        // Capped at max_lockout
+        let fourth = throttle.record_failure("jane", None).await.unwrap_err();
+        assert_eq!(fourth.retry_after_secs(), Some(60));
+    }
+
+    #[tokio::test]
+    async fn test_ip_locked_across_accounts() {
+        let throttle = throttle(100, 2);
+
+        assert!(throttle.record_failure("a", Some("10.0.0.1")).await.is_ok());
+        assert!(throttle.record_failure("b", Some("10.0.0.1")).await.is_err());
+        assert!(throttle.check("c", Some("10.0.0.1")).await.is_err());
+        assert!(throttle.check("c", Some("10.0.0.2")).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_attempts_are_audited() {
+        let sink = crate::audit::tests::test_sink();
+        let throttle = throttle(5, 100);
+
+        throttle.record_failure("Audit@Test.com", Some("10.0.0.9")).await.unwrap();
+        throttle.record_success("audit@test.com").await.unwrap();
+
+        let actions: Vec<_> = sink
+            .events()
+            .into_iter()
+            .filter(|e| e.target_id.as_deref() == Some("audit@test.com"))
+            .map(|e| e.action)
+            .collect();
+        assert_eq!(actions, [actions::LOGIN_FAILED, actions::LOGIN_SUCCEEDED]);
+    }
+
+    #[tokio::test]
+    async fn test_success_clears_account_counter() {
+        let throttle = throttle(2, 100);
+
+        assert!(throttle.record_failure("jane", None).await.is_ok());
+        throttle.record_success("jane").await.unwrap();
+        assert!(throttle.record_failure("jane", None).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_failures_are_all_counted() {
+        let store = Arc::new(MemoryAttemptStore::new());
+        let throttle = LoginThrottle::new(store.clone())
+            .with_config(ThrottleConfig::new().with_max_attempts(100, 1000));
+
+        let attempts = (0..20).map(|_| throttle.record_failure("jane", None));
+        futures::future::join_all(attempts).await;
+
+        let record = store.get("account:jane").await.unwrap().unwrap();
+        assert_eq!(record.failures, 20);
+    }
+
+    #[tokio::test]
+    async fn test_sea_orm_increment_is_a_single_upsert() {
+        use crate::db::tests::{recording_db, row};
+
+        let now = Utc::now();
+        let (db, statements) = recording_db(vec![vec![row([
+            ("key", "account:jane".into()),
+            ("failures", 3i32.into()),
+            ("last_failure_at", now.into()),
+            ("locked_until", Option::<DateTime<Utc>>::None.into()),
+        ])]])
+        .await;
+        let store = SeaOrmAttemptStore::new(db);
+
+        let failures = store
+            .increment("account:jane", now, Duration::minutes(15))
+            .await
+            .unwrap();
+        assert_eq!(failures, 3);
+
+        // The counter is incremented by the database, not read-modify-written
+        let statements = statements.lock().unwrap();
+        assert!(statements[0].starts_with(r#"INSERT INTO "login_attempts""#));
+        assert!(statements[0].contains(
+            r#"ON CONFLICT ("key") DO UPDATE SET "failures" = (CASE WHEN ("login_attempts"."last_failure_at" <= $5) THEN $6 ELSE "login_attempts"."failures" + $7 END)"#
+        ));
+    }
+
+    #[test]
+    fn test_client_ip() {
+        // The client-supplied first entry is ignored
+        let request = http::Request::builder()
+            .header("X-Forwarded-For", "203.0.113.7, 10.0.0.1")
+            .body(lambda_http::Body::Empty)
+            .unwrap();
+        assert_eq!(client_ip(&request).as_deref(), Some("10.0.0.1"));
+
+        // API Gateway's source IP wins over any header
+        let mut context =
+            lambda_http::aws_lambda_events::apigw::ApiGatewayProxyRequestContext::default();
+        context.identity.source_ip = Some("198.51.100.4".to_string());
+        let request = http::Request::builder()
+            .header("X-Forwarded-For", "203.0.113.7")
+            .body(lambda_http::Body::Empty)
+            .unwrap()
+            .with_request_context(RequestContext::ApiGatewayV1(context));
+        assert_eq!(client_ip(&request).as_deref(), Some("198.51.100.4"));
+    }
+}
@@ -0,0 +1,848 @@
+//! Single-use tokens for password reset and email verification.
+//!
+//! Tokens are random strings sent to the user (usually inside a link). Only a
+//! SHA-256 hash is stored, each token is scoped to a [`TokenPurpose`], expires
+//! after a purpose-specific TTL, and can be consumed exactly once. Issuing a
+//! new token invalidates any outstanding token for the same user and purpose.
+//!
+//! # Usage
+//!
+//! ```rust,ignore
+//! use brylix::auth::tokens::{
+//!     consume_token, send_password_reset_email, SeaOrmOneTimeTokenStore, TokenPurpose,
+//! };
+//!
+//! let store = SeaOrmOneTimeTokenStore::new(db.clone());
+//!
+//! // "Forgot password" mutation (requires the `email` feature)
+//! send_password_reset_email(
+//!     &store,
+//!     &email_provider,
+//!     user.id,
+//!     &user.email,
+//!     "Acme",
+//!     "https://app.acme.com/reset-password",
+//! )
+//! .await?;
+//!
+//! // "Reset password" mutation
+//! let user_id = consume_token(&store, TokenPurpose::PasswordReset, &token).await?;
+//! let hash = hash_password(&new_password).map_err(DomainError::Internal)?;
+//! UserService::set_password_hash(&db, user_id, hash).await?;
+//!
+//! // "Verify email" mutation: the token names the address it was sent to
+//! let (user_id, email) = consume_email_verification(&store, &token).await?;
+//! UserService::mark_email_verified(&db, user_id, &email).await?;
+//! ```
+
+use chrono::{DateTime, Duration, Utc};
+use sea_orm::sea_query::Expr;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder,
+    QuerySelect, Set,
+};
+use sha2::{Digest, Sha256};
+
+use super::password::random_alphanumeric;
+use crate::audit::{actions, AuditEvent};
+use crate::errors::{DomainError, DomainResult};
+
+/// Length of generated tokens.
+const TOKEN_LEN: usize = 48;
+
+/// Message returned for any unusable token, so callers cannot tell an
+/// unknown token from an expired or already used one.
+const INVALID_TOKEN: &str = "Invalid or expired token";
+
+/// What a one-time token may be used for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TokenPurpose {
+    /// Resetting a forgotten password
+    PasswordReset,
+    /// Confirming ownership of an email address
+    EmailVerification,
+    /// Passwordless login (see [`magic_link`](super::magic_link))
+    MagicLink,
+}
+
+impl TokenPurpose {
+    /// Get the purpose name stored alongside the token.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TokenPurpose::PasswordReset => "password_reset",
+            TokenPurpose::EmailVerification => "email_verification",
+            TokenPurpose::MagicLink => "magic_link",
+        }
+    }
+
+    /// Default lifetime of tokens issued for this purpose.
+    ///
+    /// Password reset tokens live for 1 hour, verification tokens for 24
+    /// hours and magic links for 15 minutes.
+    pub fn default_ttl(&self) -> Duration {
+        match self {
+            TokenPurpose::PasswordReset => Duration::hours(1),
+            TokenPurpose::EmailVerification => Duration::hours(24),
+            TokenPurpose::MagicLink => Duration::minutes(15),
+        }
+    }
+}
+
+/// A stored one-time token.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OneTimeToken {
+    /// Token ID
+    pub id: i64,
+    /// ID of the user the token was issued for
+    pub user_id: i64,
+    /// Normalized email the token was sent to, if recorded for rate limiting
+    pub email: Option<String>,
+    /// Purpose name (see [`TokenPurpose::as_str`])
+    pub purpose: String,
+    /// SHA-256 hash of the token (hex)
+    pub token_hash: String,
+    /// When the token was issued
+    pub created_at: DateTime<Utc>,
+    /// When the token stops being valid
+    pub expires_at: DateTime<Utc>,
+    /// When the token was consumed or invalidated
+    pub used_at: Option<DateTime<Utc>>,
+}
+
+impl OneTimeToken {
+    /// Check if the token is unused and not expired at `now`.
+    pub fn is_usable(&self, now: DateTime<Utc>) -> bool {
+        self.used_at.is_none() && self.expires_at > now
+    }
+}
+
+/// Data needed to persist a newly issued token.
+#[derive(Debug, Clone)]
+pub struct NewOneTimeToken {
+    /// ID of the user the token is issued for
+    pub user_id: i64,
+    /// Normalized email the token is sent to, if recorded for rate limiting
+    pub email: Option<String>,
+    /// Purpose name
+    pub purpose: String,
+    /// SHA-256 hash of the token (hex)
+    pub token_hash: String,
+    /// Expiry time
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Storage backend for one-time tokens.
+#[async_trait::async_trait]
+pub trait OneTimeTokenStore: Send + Sync {
+    /// Persist a new token.
+    async fn create(&self, token: NewOneTimeToken) -> DomainResult<OneTimeToken>;
+
+    /// Find a token by purpose and hash.
+    async fn find_by_hash(
+        &self,
+        purpose: &str,
+        token_hash: &str,
+    ) -> DomainResult<Option<OneTimeToken>>;
+
+    /// Mark a token as used.
+    ///
+    /// Must only succeed for a token that is still unused, returning `false`
+    /// if it was already used (e.g. by a concurrent request).
+    async fn mark_used(&self, id: i64, used_at: DateTime<Utc>) -> DomainResult<bool>;
+
+    /// Mark every unused token of a user and purpose as used.
+    async fn invalidate_for_user(
+        &self,
+        user_id: i64,
+        purpose: &str,
+        used_at: DateTime<Utc>,
+    ) -> DomainResult<()>;
+
+    /// Get the issue times of tokens sent to `email` for a purpose since
+    /// `since`, used or not, oldest first.
+    async fn issued_since(
+        &self,
+        email: &str,
+        purpose: &str,
+        since: DateTime<Utc>,
+    ) -> DomainResult<Vec<DateTime<Utc>>>;
+}
+
+/// Hash a raw token for storage and lookup.
+pub fn hash_token(raw: &str) -> String {
+    Sha256::digest(raw.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Issue a token with the purpose's default TTL.
+///
+/// Returns the raw token, which is only ever visible here.
+///
+/// # Errors
+///
+/// Returns an error if storage fails
+pub async fn issue_token(
+    store: &dyn OneTimeTokenStore,
+    user_id: i64,
+    purpose: TokenPurpose,
+) -> DomainResult<String> {
+    issue_token_with_ttl(store, user_id, purpose, purpose.default_ttl()).await
+}
+
+/// Issue a token with an explicit TTL.
+///
+/// Any outstanding token for the same user and purpose is invalidated.
+///
+/// # Errors
+///
+/// Returns an error if storage fails
+pub async fn issue_token_with_ttl(
+    store: &dyn OneTimeTokenStore,
+    user_id: i64,
+    purpose: TokenPurpose,
+    ttl: Duration,
+) -> DomainResult<String> {
+    issue_token_to(store, user_id, None, purpose, ttl).await
+}
+
+/// Issue an email verification token for `email`.
+///
+/// The address is stored with the token (trimmed and lowercased) and
+/// returned by [`consume_email_verification`], so a token cannot verify an
+/// address the user switched to after it was sent.
+///
+/// # Errors
+///
+/// Returns an error if storage fails
+pub async fn issue_email_verification_token(
+    store: &dyn OneTimeTokenStore,
+    user_id: i64,
+    email: &str,
+) -> DomainResult<String> {
+    let purpose = TokenPurpose::EmailVerification;
+    let email = Some(email.trim().to_lowercase());
+    issue_token_to(store, user_id, email, purpose, purpose.default_ttl()).await
+}
+
+/// Issue a token recording the normalized email it is sent to.
+pub(crate) async fn issue_token_to(
+    store: &dyn OneTimeTokenStore,
+    user_id: i64,
+    email: Option<String>,
+    purpose: TokenPurpose,
+    ttl: Duration,
+) -> DomainResult<String> {
+    let now = Utc::now();
+    store
+        .invalidate_for_user(user_id, purpose.as_str(), now)
+        .await?;
+
+    let raw = random_alphanumeric(TOKEN_LEN);
+    store
+        .create(NewOneTimeToken {
+            user_id,
+            email,
+            purpose: purpose.as_str().to_string(),
+            token_hash: hash_token(&raw),
+            expires_at: now + ttl,
+        })
+        .await?;
+    Ok(raw)
+}
+
+/// Check a token without consuming it.
+///
+/// Useful for rendering a "choose a new password" page before the form is
+/// submitted.
+///
+/// # Errors
+///
+/// Returns `InvalidInput` if the token is unknown, expired, or already used
+pub async fn peek_token(
+    store: &dyn OneTimeTokenStore,
+    purpose: TokenPurpose,
+    raw: &str,
+) -> DomainResult<OneTimeToken> {
+    let token = store
+        .find_by_hash(purpose.as_str(), &hash_token(raw))
+        .await?
+        .ok_or_else(|| DomainError::InvalidInput(INVALID_TOKEN.into()))?;
+
+    if !token.is_usable(Utc::now()) {
+        return Err(DomainError::InvalidInput(INVALID_TOKEN.into()));
+    }
+    Ok(token)
+}
+
+/// Verify and consume a token, returning the user ID it was issued for.
+///
+/// # Errors
+///
+/// Returns `InvalidInput` if the token is unknown, expired, or already used
+pub async fn consume_token(
+    store: &dyn OneTimeTokenStore,
+    purpose: TokenPurpose,
+    raw: &str,
+) -> DomainResult<i64> {
+    Ok(consume(store, purpose, raw).await?.user_id)
+}
+
+/// Verify and consume an email verification token, returning the user ID
+/// and the (normalized) address it was sent to.
+///
+/// Mark only this address as verified, and only if it is still the user's
+/// current one.
+///
+/// # Errors
+///
+/// Returns `InvalidInput` if the token is unknown, expired, already used,
+/// or was not issued with [`issue_email_verification_token`]
+pub async fn consume_email_verification(
+    store: &dyn OneTimeTokenStore,
+    raw: &str,
+) -> DomainResult<(i64, String)> {
+    let token = peek_token(store, TokenPurpose::EmailVerification, raw).await?;
+    // Tokens without an address cannot say which email they verify
+    let email = token
+        .email
+        .clone()
+        .ok_or_else(|| DomainError::InvalidInput(INVALID_TOKEN.into()))?;
+    let token = mark_consumed(store, TokenPurpose::EmailVerification, token).await?;
+    Ok((token.user_id, email))
+}
+
+async fn consume(
+    store: &dyn OneTimeTokenStore,
+    purpose: TokenPurpose,
+    raw: &str,
+) -> DomainResult<OneTimeToken> {
+    let token = peek_token(store, purpose, raw).await?;
+    mark_consumed(store, purpose, token).await
+}
+
+async fn mark_consumed(
+    store: &dyn OneTimeTokenStore,
+    purpose: TokenPurpose,
+    token: OneTimeToken,
+) -> DomainResult<OneTimeToken> {
+
+    if !store.mark_used(token.id, Utc::now()).await? {
+        return Err(DomainError::InvalidInput(INVALID_TOKEN.into()));
+    }
+    crate::audit::emit(
+        AuditEvent::new(actions::TOKEN_USED)
+            .with_actor(token.user_id)
+            .with_metadata(serde_json::json!({ "purpose": purpose.as_str() })),
+    )
+    .await;
+    Ok(token)
+}
+
+/// Ready-made emails for the token flows.
+#[cfg(feature = "email")]
+mod email {
+    use super::*;
+    use crate::provider::email::{EmailMessage, EmailProvider};
+
+    /// Escape text interpolated into an HTML email body.
+    pub(in crate::auth) fn escape_html(s: &str) -> String {
+        s.replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+            .replace('"', "&quot;")
+    }
+
+    /// Append the token to a link as the `token` query parameter.
+    pub fn token_link(base_url: &str, token: &str) -> String {
+        let separator = if base_url.contains('?') { '&' } else { '?' };
+        format!("{}{}token={}", base_url, separator, token)
+    }
+
+    /// Build the password reset email.
+    pub fn password_reset_email(to: &str, app_name: &str, link: &str) -> EmailMessage {
+        let app_name = escape_html(app_name);
+        let link = escape_html(link);
+        EmailMessage::new(
+            to,
+            format!("Reset your {} password", app_name),
+            format!(
+                "<p>We received a request to reset your {app} password.</p>\
+                 <p><a href=\"{link}\">Choose a new password</a></p>\
+                 <p>This link expires in 1 hour. If you did not request a reset, \
+                 you can ignore this email.</p>",
+                app = app_name,
+                link = link,
+            ),
+        )
+    }
+
+    /// Build the email verification email.
+    pub fn email_verification_email(to: &str, app_name: &str, link: &str) -> EmailMessage {
+        let app_name = escape_html(app_name);
+        let link = escape_html(link);
+        EmailMessage::new(
+            to,
+            format!("Verify your email for {}", app_name),
+            format!(
+                "<p>Please confirm your email address for {app}.</p>\
+                 <p><a href=\"{link}\">Verify email</a></p>\
+                 <p>This link expires in 24 hours.</p>",
+                app = app_name,
+                link = link,
+            ),
+        )
+    }
+
+    /// Issue a password reset token and email the reset link.
+    ///
+    /// `link_base` is the page that handles the reset; the token is appended
+    /// as the `token` query parameter.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if storage or sending fails
+    pub async fn send_password_reset_email(
+        store: &dyn OneTimeTokenStore,
+        provider: &dyn EmailProvider,
+        user_id: i64,
+        to: &str,
+        app_name: &str,
+        link_base: &str,
+    ) -> DomainResult<()> {
+        let token = issue_token(store, user_id, TokenPurpose::PasswordReset).await?;
+        let link = token_link(link_base, &token);
+        provider
+            .send(password_reset_email(to, app_name, &link))
+            .await
+    }
+
+    /// Issue an email verification token and email the verification link.
+    ///
+    /// The token records `to`; redeem it with [`consume_email_verification`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if storage or sending fails
+    pub async fn send_verification_email(
+        store: &dyn OneTimeTokenStore,
+        provider: &dyn EmailProvider,
+        user_id: i64,
+        to: &str,
+        app_name: &str,
+        link_base: &str,
+    ) -> DomainResult<()> {
+        let token = issue_email_verification_token(store, user_id, to).await?;
+        let link = token_link(link_base, &token);
+        provider
+            .send(email_verification_email(to, app_name, &link))
+            .await
+    }
+}
+
+#[cfg(feature = "email")]
+pub(super) use email::escape_html;
+#[cfg(feature = "email")]
+pub use email::{
+    email_verification_email, password_reset_email, send_password_reset_email,
+    send_verification_email, token_link,
+};
+
+/// SeaORM-backed token store using the `one_time_tokens` table.
+///
+/// Add [`migration::Migration`] to your migrator to create the table.
+#[derive(Debug, Clone)]
+pub struct SeaOrmOneTimeTokenStore {
+    db: DatabaseConnection,
+}
+
+impl SeaOrmOneTimeTokenStore {
+    /// Create a store using the given connection.
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait::async_trait]
+impl OneTimeTokenStore for SeaOrmOneTimeTokenStore {
+    async fn create(&self, token: NewOneTimeToken) -> DomainResult<OneTimeToken> {
+        let model = entity::ActiveModel {
+            user_id: Set(token.user_id),
+            email: Set(token.email),
+            purpose: Set(token.purpose),
+            token_hash: Set(token.token_hash),
+            created_at: Set(Utc::now()),
+            expires_at: Set(token.expires_at),
+            used_at: Set(None),
+            ..Default::default()
+        }
+        .insert(&self.db)
+        .await?;
+        Ok(model.into())
+    }
+
+    async fn find_by_hash(
+        &self,
+        purpose: &str,
+        token_hash: &str,
+    ) -> DomainResult<Option<OneTimeToken>> {
+        Ok(entity::Entity::find()
+            .filter(entity::Column::Purpose.eq(purpose))
+            .filter(entity::Column::TokenHash.eq(token_hash))
+            .one(&self.db)
+            .await?
+            .map(Into::into))
+    }
+
+    async fn mark_used(&self, id: i64, used_at: DateTime<Utc>) -> DomainResult<bool> {
+        let result = entity::Entity::update_many()
+            .col_expr(entity::Column::UsedAt, Expr::value(used_at))
+            .filter(entity::Column::Id.eq(id))
+            .filter(entity::Column::UsedAt.is_null())
+            .exec(&self.db)
+            .await?;
+        Ok(result.rows_affected == 1)
+    }
+
+    async fn invalidate_for_user(
+        &self,
+        user_id: i64,
+        purpose: &str,
+        used_at: DateTime<Utc>,
+    ) -> DomainResult<()> {
+        entity::Entity::update_many()
+            .col_expr(entity::Column::UsedAt, Expr::value(used_at))
+            .filter(entity::Column::UserId.eq(user_id))
+            .filter(entity::Column::Purpose.eq(purpose))
+            .filter(entity::Column::UsedAt.is_null())
+            .exec(&self.db)
+            .await?;
+        Ok(())
+    }
+
+    async fn issued_since(
+        &self,
+        email: &str,
+        purpose: &str,
+        since: DateTime<Utc>,
+    ) -> DomainResult<Vec<DateTime<Utc>>> {
+        Ok(entity::Entity::find()
+            .select_only()
+            .column(entity::Column::CreatedAt)
+            .filter(entity::Column::Email.eq(email))
+            .filter(entity::Column::Purpose.eq(purpose))
+            .filter(entity::Column::CreatedAt.gte(since))
+            .order_by_asc(entity::Column::CreatedAt)
+            .into_tuple()
+            .all(&self.db)
+            .await?)
+    }
+}
+
+impl From<entity::Model> for OneTimeToken {
+    fn from(m: entity::Model) -> Self {
+        Self {
+            id: m.id,
+            user_id: m.user_id,
+            email: m.email,
+            purpose: m.purpose,
+            token_hash: m.token_hash,
+            created_at: m.created_at,
+            expires_at: m.expires_at,
+            used_at: m.used_at,
+        }
+    }
+}
+
+/// SeaORM entity for the `one_time_tokens` table.
+pub mod entity {
+    use sea_orm::entity::prelude::*;
+
+    /// A stored one-time token row.
+    #[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+    #[sea_orm(table_name = "one_time_tokens")]
+    pub struct Model {
+        /// Token ID
+        #[sea_orm(primary_key)]
+        pub id: i64,
+        /// User the token was issued for
+        pub user_id: i64,
+        /// Normalized recipient email (rate limiting)
+        pub email: Option<String>,
+        /// Purpose name
+        pub purpose: String,
+        /// SHA-256 hash of the token (hex)
+        #[sea_orm(unique)]
+        pub token_hash: String,
+        /// Issue time
+        pub created_at: DateTimeUtc,
+        /// Expiry time
+        pub expires_at: DateTimeUtc,
+        /// Consumption or invalidation time
+        pub used_at: Option<DateTimeUtc>,
+    }
+
+    /// No relations.
+    #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+    pub enum Relation {}
+
+    impl ActiveModelBehavior for ActiveModel {}
+}
+
+/// Migration creating the `one_time_tokens` table.
+pub mod migration {
+    use sea_orm_migration::prelude::*;
+
+    /// Creates the `one_time_tokens` table.
+    pub struct Migration;
+
+    impl MigrationName for Migration {
+        fn name(&self) -> &str {
+            "m20261018_000003_create_one_time_tokens_table"
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl MigrationTrait for Migration {
+        async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+            manager
+                .create_table(
+                    Table::create()
+                        .table(OneTimeTokens::Table)
+                        .if_not_exists()
+                        .col(
+                            ColumnDef::new(OneTimeTokens::Id)
+                                .big_integer()
+                                .not_null()
+                                .auto_increment()
+                                .primary_key(),
+                        )
+                        .col(ColumnDef::new(OneTimeTokens::UserId).big_integer().not_null())
+                        .col(ColumnDef::new(OneTimeTokens::Email).string_len(255).null())
+                        .col(ColumnDef::new(OneTimeTokens::Purpose).string_len(50).not_null())
+                        .col(
+                            ColumnDef::new(OneTimeTokens::TokenHash)
+                                .string_len(64)
+                                .not_null()
+                                .unique_key(),
+                        )
+                        .col(
+                            ColumnDef::new(OneTimeTokens::CreatedAt)
+                                .timestamp()
+                                .not_null()
+                                .default(Expr::current_timestamp()),
+                        )
+                        .col(ColumnDef::new(OneTimeTokens::ExpiresAt).timestamp().not_null())
+                        .col(ColumnDef::new(OneTimeTokens::UsedAt).timestamp().null())
+                        .index(
+                            Index::create()
+                                .name("idx_one_time_tokens_user_purpose")
+                                .col(OneTimeTokens::UserId)
+                                .col(OneTimeTokens::Purpose),
+                        )
+                        .to_owned(),
+                )
+                .await?;
+            manager
+                .create_index(
+                    Index::create()
+                        .name("idx_one_time_tokens_email_purpose")
+                        .table(OneTimeTokens::Table)
+                        .col(OneTimeTokens::Email)
+                        .col(OneTimeTokens::Purpose)
+                        .to_owned(),
+                )
+                .await
+        }
+
+        async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+            manager
+                .drop_table(Table::drop().table(OneTimeTokens::Table).to_owned())
+                .await
+        }
+    }
+
+    #[derive(Iden)]
+    enum OneTimeTokens {
+        Table,
+        Id,
+        UserId,
+        Email,
+        Purpose,
+        TokenHash,
+        CreatedAt,
+        ExpiresAt,
+        UsedAt,
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// In-memory token store shared with the magic link tests.
+    #[derive(Default)]
+    pub(crate) struct MemoryStore {
+        pub(crate) tokens: Mutex<Vec<OneTimeToken>>,
+    }
+
+    #[async_trait::async_trait]
+    impl OneTimeTokenStore for MemoryStore {
+        async fn create(&self, token: NewOneTimeToken) -> DomainResult<OneTimeToken> {
+            let mut tokens = self.tokens.lock().unwrap();
+            let stored = OneTimeToken {
+                id: tokens.len() as i64 + 1,
+                user_id: token.user_id,
+                email: token.email,
+                purpose: token.purpose,
+                token_hash: token.token_hash,
+                created_at: Utc::now(),
+                expires_at: token.expires_at,
+                used_at: None,
+            };
+            tokens.push(stored.clone());
+            Ok(stored)
+        }
+
+        async fn find_by_hash(
+            &self,
+            purpose: &str,
+            token_hash: &str,
+        ) -> DomainResult<Option<OneTimeToken>> {
+            let tokens = self.tokens.lock().unwrap();
+            Ok(tokens
+                .iter()
+                .find(|t| t.purpose == purpose && t.token_hash == token_hash)
+                .cloned())
+        }
+
+        async fn mark_used(&self, id: i64, used_at: DateTime<Utc>) -> DomainResult<bool> {
+            let mut tokens = self.tokens.lock().unwrap();
+            match tokens.iter_mut().find(|t| t.id == id && t.used_at.is_none()) {
+                Some(t) => {
+                    t.used_at = Some(used_at);
+                    Ok(true)
+                }
+                None => Ok(false),
+            }
+        }
+
+        async fn invalidate_for_user(
+            &self,
+            user_id: i64,
+            purpose: &str,
+            used_at: DateTime<Utc>,
+        ) -> DomainResult<()> {
+            let mut tokens = self.tokens.lock().unwrap();
+            for t in tokens
+                .iter_mut()
+                .filter(|t| t.user_id == user_id && t.purpose == purpose && t.used_at.is_none())
+            {
+                t.used_at = Some(used_at);
+            }
+            Ok(())
+        }
+
+        async fn issued_since(
+            &self,
+            email: &str,
+            purpose: &str,
+            since: DateTime<Utc>,
+        ) -> DomainResult<Vec<DateTime<Utc>>> {
+            let tokens = self.tokens.lock().unwrap();
+            Ok(tokens
+                .iter()
+                .filter(|t| {
+                    t.email.as_deref() == Some(email)
+                        && t.purpose == purpose
+                        && t.created_at >= since
+                })
+                .map(|t| t.created_at)
+                .collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_issue_and_consume_once() {
+        let store = MemoryStore::default();
+        let token = issue_token(&store, 7, TokenPurpose::PasswordReset).await.unwrap();
+
+        assert_eq!(
+            consume_token(&store, TokenPurpose::PasswordReset, &token).await.unwrap(),
+            7
+        );
+        assert!(matches!(
+            consume_token(&store, TokenPurpose::PasswordReset, &token).await,
+            Err(DomainError::InvalidInput(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_token_is_stored_hashed_and_purpose_scoped() {
+        let store = MemoryStore::default();
+        let token = issue_token(&store, 7, TokenPurpose::EmailVerification).await.unwrap();
+
+        let stored = store.tokens.lock().unwrap()[0].clone();
+        assert_ne!(stored.token_hash, token);
+        assert_eq!(stored.token_hash, hash_token(&token));
+
+        assert!(consume_token(&store, TokenPurpose::PasswordReset, &token).await.is_err());
+        assert!(consume_token(&store, TokenPurpose::EmailVerification, &token).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_email_verification_returns_the_address() {
+        let store = MemoryStore::default();
+        let token = issue_email_verification_token(&store, 7, " Old@Example.com ")
+            .await
+            .unwrap();
+        assert_eq!(
+            consume_email_verification(&store, &token).await.unwrap(),
+            (7, "old@example.com".to_string())
+        );
+        assert!(consume_email_verification(&store, &token).await.is_err());
+
+        // Tokens issued without an address are refused
+        let bare = issue_token(&store, 7, TokenPurpose::EmailVerification)
+            .await
+            .unwrap();
+        assert!(matches!(
+            consume_email_verification(&store, &bare).await,
+            Err(DomainError::InvalidInput(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_expired_token_rejected() {
+        let store = MemoryStore::default();
+        let token = issue_token_with_ttl(&store, 7, TokenPurpose::PasswordReset, Duration::seconds(-1))
+            .await
+            .unwrap();
+
+        assert!(peek_token(&store, TokenPurpose::PasswordReset, &token).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_new_token_invalidates_previous() {
+        let store = MemoryStore::default();
+        let first = issue_token(&store, 7, TokenPurpose::PasswordReset).await.unwrap();
+        let second = issue_token(&store, 7, TokenPurpose::PasswordReset).await.unwrap();
+
+        assert!(consume_token(&store, TokenPurpose::PasswordReset, &first).await.is_err());
+        assert!(consume_token(&store, TokenPurpose::PasswordReset, &second).await.is_ok());
+    }
+
+    #[cfg(feature = "email")]
+    #[test]
+    fn test_email_bodies() {
+        let link = token_link("https://app.test/reset?lang=en", "abc");
+        assert_eq!(link, "https://app.test/reset?lang=en&token=abc");
+
+        let message = password_reset_email("a@test.com", "Acme", &link);
+        assert_eq!(message.to, "a@test.com");
+        assert!(message.subject.contains("Acme"));
+        assert!(message.html_body.contains("token=abc"));
+    }
+}
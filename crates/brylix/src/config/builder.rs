@@ -1,6 +1,6 @@
 //! Builder pattern for creating Config instances.
 
-use super::{Config, DatabaseConfig, JwtConfig, MultiTenantConfig};
+use super::{Config, DatabaseConfig, JwtConfig, MultiTenantConfig, QueryLimitsConfig};
 
 #[cfg(feature = "admin-override")]
 use crate::auth::admin_override::AdminOverrideConfig;
@@ -29,6 +29,7 @@ pub struct ConfigBuilder {
     jwt: JwtConfig,
     multi_tenant: MultiTenantConfig,
     log_level: String,
+    query_limits: QueryLimitsConfig,
     #[cfg(feature = "admin-override")]
     admin_override: Option<AdminOverrideConfig>,
 }
@@ -41,6 +42,7 @@ impl ConfigBuilder {
             jwt: JwtConfig::default(),
             multi_tenant: MultiTenantConfig::default(),
             log_level: "info".to_string(),
+            query_limits: QueryLimitsConfig::default(),
             #[cfg(feature = "admin-override")]
             admin_override: None,
         }
@@ -146,6 +148,46 @@ impl ConfigBuilder {
         self
     }
 
+    // =========================================================================
+    // GraphQL query limits
+    // =========================================================================
+
+    /// Set the maximum query depth (default: 15)
+    pub fn max_query_depth(mut self, depth: usize) -> Self {
+        self.query_limits.max_depth = depth;
+        self
+    }
+
+    /// Set the maximum query complexity (default: 1000)
+    pub fn max_query_complexity(mut self, complexity: usize) -> Self {
+        self.query_limits.max_complexity = complexity;
+        self
+    }
+
+    /// Set the maximum number of aliases per query (default: 30)
+    pub fn max_query_aliases(mut self, aliases: usize) -> Self {
+        self.query_limits.max_aliases = aliases;
+        self
+    }
+
+    /// Set the maximum number of operations per batch request (default: 10)
+    pub fn max_batch_size(mut self, size: usize) -> Self {
+        self.query_limits.max_batch_size = size;
+        self
+    }
+
+    /// Set the maximum request body size in bytes (default: 1 MiB)
+    pub fn max_body_bytes(mut self, bytes: usize) -> Self {
+        self.query_limits.max_body_bytes = bytes;
+        self
+    }
+
+    /// Set the maximum file upload (multipart) body size in bytes (default: 6 MiB)
+    pub fn max_upload_bytes(mut self, bytes: usize) -> Self {
+        self.query_limits.max_upload_bytes = bytes;
+        self
+    }
+
     // =========================================================================
     // Admin override configuration (feature-gated)
     // =========================================================================
@@ -206,6 +248,7 @@ impl ConfigBuilder {
             jwt: self.jwt,
             multi_tenant: self.multi_tenant,
             log_level: self.log_level,
+            query_limits: self.query_limits,
             #[cfg(feature = "admin-override")]
             admin_override: self.admin_override,
         })
@@ -152,8 +152,65 @@ impl PoolConfig {
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
+    use sea_orm::{
+        DatabaseBackend, DatabaseConnection, ProxyDatabaseTrait, ProxyExecResult, ProxyRow,
+        Statement, Value,
+    };
+    use std::collections::{BTreeMap, VecDeque};
+    use std::sync::{Arc, Mutex};
+
+    /// Proxy database that records every statement and answers queries
+    /// from a queue of canned result sets.
+    #[derive(Debug, Default)]
+    pub(crate) struct RecordingDb {
+        statements: Arc<Mutex<Vec<String>>>,
+        results: Mutex<VecDeque<Vec<ProxyRow>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl ProxyDatabaseTrait for RecordingDb {
+        async fn query(&self, statement: Statement) -> Result<Vec<ProxyRow>, DbErr> {
+            self.statements.lock().unwrap().push(statement.sql);
+            Ok(self.results.lock().unwrap().pop_front().unwrap_or_default())
+        }
+
+        async fn execute(&self, statement: Statement) -> Result<ProxyExecResult, DbErr> {
+            self.statements.lock().unwrap().push(statement.sql);
+            Ok(ProxyExecResult::new(0, 1))
+        }
+    }
+
+    /// Build a row for [`recording_db`] results.
+    pub(crate) fn row<const N: usize>(values: [(&str, Value); N]) -> BTreeMap<String, Value> {
+        values
+            .into_iter()
+            .map(|(column, value)| (column.to_string(), value))
+            .collect()
+    }
+
+    /// Connect to a [`RecordingDb`] answering queries with `results` in
+    /// order. Returns the connection and the log of executed SQL.
+    pub(crate) async fn recording_db(
+        results: Vec<Vec<BTreeMap<String, Value>>>,
+    ) -> (DatabaseConnection, Arc<Mutex<Vec<String>>>) {
+        let statements = Arc::new(Mutex::new(Vec::new()));
+        let proxy = RecordingDb {
+            statements: statements.clone(),
+            results: Mutex::new(
+                results
+                    .into_iter()
+                    .map(|rows| rows.into_iter().map(ProxyRow::new).collect())
+                    .collect(),
+            ),
+        };
+        let db =
+            sea_orm::Database::connect_proxy(DatabaseBackend::Postgres, Arc::new(Box::new(proxy)))
+                .await
+                .unwrap();
+        (db, statements)
+    }
 
     #[test]
     fn test_pool_config_default() {
@@ -24,6 +24,13 @@ pub enum DomainError {
     #[error("Invalid user id in token")]
     InvalidTokenUserId,
 
+    /// Too many failed attempts; the caller is locked out for a while
+    #[error("Too many attempts, try again in {retry_after_secs} seconds")]
+    TooManyAttempts {
+        /// Seconds until another attempt is allowed
+        retry_after_secs: u64,
+    },
+
     // ============================================================================
     // Not found errors
     // ============================================================================
@@ -54,6 +61,10 @@ pub enum DomainError {
     #[error("Duplicate entry: {0}")]
     DuplicateEntry(String),
 
+    /// A query, batch or request body exceeds the configured query limits
+    #[error("Query too complex: {0}")]
+    QueryTooComplex(String),
+
     // ============================================================================
     // Database errors
     // ============================================================================
@@ -107,6 +118,7 @@ impl DomainError {
             DomainError::Unauthorized => "UNAUTHORIZED",
             DomainError::Forbidden(_) => "FORBIDDEN",
             DomainError::InvalidTokenUserId => "BAD_REQUEST",
+            DomainError::TooManyAttempts { .. } => "TOO_MANY_ATTEMPTS",
 
             // Not found
             DomainError::UserNotFound => "NOT_FOUND",
@@ -120,6 +132,7 @@ impl DomainError {
             // Validation
             DomainError::InvalidInput(_) => "BAD_REQUEST",
             DomainError::DuplicateEntry(_) => "CONFLICT",
+            DomainError::QueryTooComplex(_) => "QUERY_TOO_COMPLEX",
 
             // Database
             DomainError::DatabaseError(_) => "INTERNAL",
@@ -163,6 +176,14 @@ impl DomainError {
         )
     }
 
+    /// Seconds the client should wait before retrying, if this error carries a hint
+    pub fn retry_after_secs(&self) -> Option<u64> {
+        match self {
+            DomainError::TooManyAttempts { retry_after_secs } => Some(*retry_after_secs),
+            _ => None,
+        }
+    }
+
     /// Check if this is a validation error
     pub fn is_validation_error(&self) -> bool {
         matches!(
@@ -186,7 +207,9 @@ impl From<sea_orm::DbErr> for DomainError {
         let msg = err.to_string();
         // Check for duplicate entry errors (SeaORM usually wraps SQL errors)
         if msg.contains("Duplicate entry") || msg.contains("1062") {
-            DomainError::DuplicateEntry(msg)
+            // CONFLICT reaches clients, so keep the SQL text in the logs
+            tracing::warn!(error = %msg, "Duplicate entry");
+            DomainError::DuplicateEntry("Record already exists".into())
         } else {
             DomainError::DatabaseError(msg)
         }
@@ -211,10 +234,24 @@ mod tests {
     fn test_error_codes() {
         assert_eq!(DomainError::InvalidCredentials.code(), "INVALID_CREDENTIALS");
         assert_eq!(DomainError::Unauthorized.code(), "UNAUTHORIZED");
+        assert_eq!(
+            DomainError::TooManyAttempts { retry_after_secs: 30 }.code(),
+            "TOO_MANY_ATTEMPTS"
+        );
         assert_eq!(DomainError::NotFound("test".into()).code(), "NOT_FOUND");
         assert_eq!(DomainError::InvalidInput("test".into()).code(), "BAD_REQUEST");
     }
 
+    #[test]
+    fn test_duplicate_entry_hides_sql() {
+        let err: DomainError = sea_orm::DbErr::Custom(
+            "Duplicate entry 'ada@example.com' for key 'users.email'".into(),
+        )
+        .into();
+        assert_eq!(err.code(), "CONFLICT");
+        assert!(!err.to_string().contains("ada@example.com"));
+    }
+
     #[test]
     fn test_is_not_found() {
         assert!(DomainError::UserNotFound.is_not_found());
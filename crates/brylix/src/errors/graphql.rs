@@ -25,6 +25,16 @@ pub fn gql_unauthorized() -> GqlError {
     gql_error("UNAUTHORIZED", "Unauthorized")
 }
 
+/// Create a forbidden error
+pub fn gql_forbidden(message: impl Into<String>) -> GqlError {
+    gql_error("FORBIDDEN", message)
+}
+
+/// Create a too-many-attempts error with a `retryAfter` extension (seconds)
+pub fn gql_too_many_attempts(retry_after_secs: u64) -> GqlError {
+    gql_from_domain(DomainError::TooManyAttempts { retry_after_secs })
+}
+
 /// Create a not found error
 pub fn gql_not_found(message: impl Into<String>) -> GqlError {
     gql_error("NOT_FOUND", message)
@@ -62,22 +72,48 @@ pub fn gql_upgrade_required(feature: &str, current: i32, required: i32) -> GqlEr
 /// Convert anyhow::Error to GraphQL error.
 /// First tries to downcast to DomainError for proper error codes,
 /// then falls back to string classification for legacy errors.
+/// Classified errors get a generic message for their code, since the raw
+/// text may contain SQL or other internals; it is logged instead.
 pub fn gql_from_anyhow(err: anyhow::Error) -> GqlError {
     // Try to downcast to DomainError first
     if let Some(domain_err) = err.downcast_ref::<DomainError>() {
-        return gql_error(domain_err.code(), domain_err.to_string());
+        return with_retry_after(
+            gql_error(domain_err.code(), domain_err.to_string()),
+            domain_err,
+        );
     }
 
     // Fallback to string-based classification for legacy errors
-    let msg = err.to_string();
-    let code = classify_message_code(&msg);
-    gql_error(code, msg)
+    gql_from_message(err.to_string())
 }
 
-/// Convert a message string to a GraphQL error with classified code
+/// Convert a message string to a GraphQL error with classified code.
+///
+/// Unless the code is INTERNAL (masked by
+/// [`ErrorPolicy::Production`](super::ErrorPolicy::Production)), the
+/// message is replaced with a generic one for the code and logged.
 pub fn gql_from_message(message: String) -> GqlError {
     let code = classify_message_code(&message);
-    gql_error(code, message)
+    match classified_message(code) {
+        Some(generic) => {
+            tracing::debug!(code, error = %message, "Replaced classified error message");
+            gql_error(code, generic)
+        }
+        None => gql_error(code, message),
+    }
+}
+
+/// Client-facing message for a code assigned by [`classify_message_code`].
+fn classified_message(code: &str) -> Option<&'static str> {
+    match code {
+        "INVALID_CREDENTIALS" => Some("Invalid credentials"),
+        "NOT_FOUND" => Some("Not found"),
+        "QUERY_TOO_COMPLEX" => Some("Query too complex"),
+        "SERVICE_UNAVAILABLE" => Some("Service unavailable"),
+        "BAD_REQUEST" => Some("Invalid request"),
+        "FORBIDDEN" => Some("Forbidden"),
+        _ => None,
+    }
 }
 
 /// Legacy string-based classifier for backwards compatibility.
@@ -87,6 +123,8 @@ fn classify_message_code(msg: &str) -> &'static str {
         "INVALID_CREDENTIALS"
     } else if msg.contains("not found") || msg.contains("Not found") {
         "NOT_FOUND"
+    } else if msg.contains("Query too complex") {
+        "QUERY_TOO_COMPLEX"
     } else if msg.contains("Provider not configured") || msg.contains("Config not initialized") {
         "SERVICE_UNAVAILABLE"
     } else if msg.contains("Invalid user id in token") || msg.contains("Invalid") {
@@ -101,7 +139,15 @@ fn classify_message_code(msg: &str) -> &'static str {
 /// Convert DomainError directly to GraphQL error.
 /// Use this when the service/repository returns DomainResult.
 pub fn gql_from_domain(err: DomainError) -> GqlError {
-    gql_error(err.code(), err.to_string())
+    with_retry_after(gql_error(err.code(), err.to_string()), &err)
+}
+
+/// Add the `retryAfter` extension for errors that carry a retry hint
+fn with_retry_after(gql_err: GqlError, err: &DomainError) -> GqlError {
+    match err.retry_after_secs() {
+        Some(secs) => gql_err.extend_with(|_, e| e.set("retryAfter", secs)),
+        None => gql_err,
+    }
 }
 
 #[cfg(test)]
@@ -121,10 +167,32 @@ mod tests {
         assert!(gql_err.message.contains("User"));
     }
 
+    #[test]
+    fn test_gql_too_many_attempts_has_retry_after() {
+        let gql_err = gql_too_many_attempts(42);
+        let extensions = gql_err.extensions.unwrap();
+        assert_eq!(
+            extensions.get("code"),
+            Some(&async_graphql::Value::from("TOO_MANY_ATTEMPTS"))
+        );
+        assert_eq!(extensions.get("retryAfter"), Some(&async_graphql::Value::from(42u64)));
+    }
+
     #[test]
     fn test_classify_message_code() {
         assert_eq!(classify_message_code("User not found"), "NOT_FOUND");
         assert_eq!(classify_message_code("Invalid credentials"), "INVALID_CREDENTIALS");
+        assert_eq!(classify_message_code("Query too complex: depth"), "QUERY_TOO_COMPLEX");
         assert_eq!(classify_message_code("Something went wrong"), "INTERNAL");
     }
+
+    #[test]
+    fn test_classified_messages_are_replaced() {
+        let err = gql_from_message("Invalid column `password_hash` in users".into());
+        assert_eq!(err.message, "Invalid request");
+
+        // INTERNAL keeps the text for the error policy to mask
+        let err = gql_from_message("Something went wrong".into());
+        assert_eq!(err.message, "Something went wrong");
+    }
 }
@@ -19,11 +19,13 @@
 mod domain;
 mod graphql;
 mod http;
+mod policy;
 
 pub use domain::{DomainError, DomainResult};
 pub use graphql::{
-    gql_bad_request, gql_error, gql_from_anyhow, gql_from_domain, gql_from_message,
+    gql_bad_request, gql_error, gql_forbidden, gql_from_anyhow, gql_from_domain, gql_from_message,
     gql_internal, gql_not_found, gql_tenant_invalid, gql_tenant_mismatch,
-    gql_tenant_not_found, gql_unauthorized, gql_upgrade_required,
+    gql_tenant_not_found, gql_too_many_attempts, gql_unauthorized, gql_upgrade_required,
 };
 pub use http::{ClientError, ServerError};
+pub use policy::{CorrelationId, ErrorPolicy, CLIENT_SAFE_CODES, MASKED_MESSAGE};
@@ -0,0 +1,419 @@
+//! Error masking for production deployments.
+//!
+//! Internal errors (`gql_internal`, `gql_from_anyhow`, database errors
+//! converted through `DomainError` or returned with `?`) carry raw
+//! messages such as SQL errors. [`ErrorPolicy::Production`] replaces the
+//! message of every error without a code listed in [`CLIENT_SAFE_CODES`]
+//! with a generic one, adds the request's [`CorrelationId`] as the
+//! `correlationId` extension and logs the original error with `tracing`,
+//! so support can find it from the ID the client reports. This covers
+//! errors raised before execution too, e.g. by a persisted query store;
+//! parse and validation errors are tagged `GRAPHQL_PARSE_FAILED` and
+//! `GRAPHQL_VALIDATION_FAILED` and keep their message.
+//! [`ErrorPolicy::Development`] leaves errors untouched.
+//!
+//! # Usage
+//!
+//! ```rust,ignore
+//! use brylix::errors::{CorrelationId, ErrorPolicy};
+//!
+//! let schema = Schema::build(Query, Mutation, EmptySubscription)
+//!     .extension(ErrorPolicy::Production)
+//!     .finish();
+//!
+//! // Per request: reuse the caller's or Lambda's request ID
+//! let correlation_id = CorrelationId::from_request(&event);
+//! let request = graphql_request_from_post(event).await?.data(correlation_id);
+//! ```
+
+use std::any::TypeId;
+use std::sync::{Arc, Mutex};
+
+use async_graphql::extensions::{
+    Extension, ExtensionContext, ExtensionFactory, NextParseQuery, NextPrepareRequest, NextRequest,
+    NextValidation,
+};
+use async_graphql::parser::types::ExecutableDocument;
+use async_graphql::{
+    Request as GqlRequest, Response, ServerError, ServerResult, ValidationResult, Value, Variables,
+};
+use lambda_http::{Request, RequestExt};
+
+use crate::auth::random_alphanumeric;
+
+/// Error codes whose messages are written for clients and kept in
+/// production. Errors with any other code, or none, are masked.
+pub const CLIENT_SAFE_CODES: [&str; 14] = [
+    "BAD_REQUEST",
+    "UNAUTHORIZED",
+    "FORBIDDEN",
+    "INVALID_CREDENTIALS",
+    "NOT_FOUND",
+    "CONFLICT",
+    "TOO_MANY_ATTEMPTS",
+    "QUERY_TOO_COMPLEX",
+    "UPGRADE_REQUIRED",
+    "SERVICE_UNAVAILABLE",
+    "PERSISTED_QUERY_NOT_FOUND",
+    "OPERATION_NOT_ALLOWED",
+    PARSE_FAILED,
+    VALIDATION_FAILED,
+];
+
+/// Code given to query syntax errors in production.
+const PARSE_FAILED: &str = "GRAPHQL_PARSE_FAILED";
+
+/// Code given to validation errors (unknown fields, depth and complexity
+/// limits, ...) in production.
+const VALIDATION_FAILED: &str = "GRAPHQL_VALIDATION_FAILED";
+
+/// Message returned in place of a masked error.
+pub const MASKED_MESSAGE: &str = "Internal server error";
+
+/// Headers a client or proxy may use to supply a correlation ID.
+const CORRELATION_HEADERS: [&str; 2] = ["X-Correlation-Id", "X-Request-Id"];
+
+/// Length of generated correlation IDs.
+const CORRELATION_ID_LEN: usize = 16;
+
+/// How errors are reported to clients. Add to a schema with
+/// `.extension(policy)`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ErrorPolicy {
+    /// Return errors unchanged
+    #[default]
+    Development,
+
+    /// Mask every error without a client-safe code and attach a
+    /// correlation ID
+    Production,
+}
+
+impl ErrorPolicy {
+    /// Check whether internal error messages are masked.
+    pub fn is_production(self) -> bool {
+        self == ErrorPolicy::Production
+    }
+}
+
+impl ExtensionFactory for ErrorPolicy {
+    fn create(&self) -> Arc<dyn Extension> {
+        Arc::new(ErrorPolicyExtension {
+            policy: *self,
+            correlation_id: Mutex::new(None),
+        })
+    }
+}
+
+/// ID tying a client-visible error to the server logs.
+///
+/// Add it to the request data; requests without one get a random ID.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CorrelationId(pub String);
+
+impl CorrelationId {
+    /// Generate a random correlation ID.
+    pub fn new() -> Self {
+        Self(random_alphanumeric(CORRELATION_ID_LEN))
+    }
+
+    /// Take the ID from the `X-Correlation-Id` or `X-Request-Id` header,
+    /// falling back to the Lambda request ID and then a random ID.
+    pub fn from_request(request: &Request) -> Self {
+        CORRELATION_HEADERS
+            .iter()
+            .find_map(|name| {
+                request
+                    .headers()
+                    .get(*name)
+                    .and_then(|v| v.to_str().ok())
+                    .filter(|v| !v.is_empty())
+                    .map(String::from)
+            })
+            .or_else(|| {
+                request
+                    .lambda_context_ref()
+                    .map(|context| context.request_id.clone())
+                    .filter(|id| !id.is_empty())
+            })
+            .map(Self)
+            .unwrap_or_default()
+    }
+}
+
+impl Default for CorrelationId {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Display for CorrelationId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+struct ErrorPolicyExtension {
+    policy: ErrorPolicy,
+    /// Correlation ID found in the request data
+    correlation_id: Mutex<Option<CorrelationId>>,
+}
+
+#[async_trait::async_trait]
+impl Extension for ErrorPolicyExtension {
+    async fn request(&self, ctx: &ExtensionContext<'_>, next: NextRequest<'_>) -> Response {
+        let mut response = next.run(ctx).await;
+        if !self.policy.is_production() || !response.errors.iter().any(is_masked) {
+            return response;
+        }
+
+        let correlation_id = self
+            .correlation_id
+            .lock()
+            .ok()
+            .and_then(|id| id.clone())
+            .unwrap_or_default();
+        for err in response.errors.iter_mut().filter(|e| is_masked(e)) {
+            tracing::error!(
+                correlation_id = %correlation_id,
+                path = ?err.path,
+                error = %err.message,
+                "Internal error"
+            );
+            err.message = MASKED_MESSAGE.to_string();
+            err.extensions
+                .get_or_insert_with(Default::default)
+                .set("correlationId", correlation_id.0.clone());
+        }
+        response
+    }
+
+    async fn prepare_request(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        request: GqlRequest,
+        next: NextPrepareRequest<'_>,
+    ) -> ServerResult<GqlRequest> {
+        let correlation_id = request
+            .data
+            .get(&TypeId::of::<CorrelationId>())
+            .and_then(|id| id.downcast_ref::<CorrelationId>());
+        if let (Some(id), Ok(mut slot)) = (correlation_id, self.correlation_id.lock()) {
+            *slot = Some(id.clone());
+        }
+        next.run(ctx, request).await
+    }
+
+    async fn parse_query(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        query: &str,
+        variables: &Variables,
+        next: NextParseQuery<'_>,
+    ) -> ServerResult<ExecutableDocument> {
+        next.run(ctx, query, variables)
+            .await
+            .map_err(|err| self.with_default_code(err, PARSE_FAILED))
+    }
+
+    async fn validation(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        next: NextValidation<'_>,
+    ) -> Result<ValidationResult, Vec<ServerError>> {
+        next.run(ctx).await.map_err(|errors| {
+            errors
+                .into_iter()
+                .map(|err| self.with_default_code(err, VALIDATION_FAILED))
+                .collect()
+        })
+    }
+}
+
+impl ErrorPolicyExtension {
+    /// Tag an error raised by the GraphQL engine, which is safe to show but
+    /// carries no code, so it is not masked.
+    fn with_default_code(&self, mut err: ServerError, code: &'static str) -> ServerError {
+        if self.policy.is_production() && error_code(&err).is_none() {
+            err.extensions
+                .get_or_insert_with(Default::default)
+                .set("code", code);
+        }
+        err
+    }
+}
+
+fn error_code(err: &ServerError) -> Option<&str> {
+    match err.extensions.as_ref()?.get("code") {
+        Some(Value::String(code)) => Some(code),
+        _ => None,
+    }
+}
+
+fn is_masked(err: &ServerError) -> bool {
+    !error_code(err).is_some_and(|code| CLIENT_SAFE_CODES.contains(&code))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::{
+        gql_from_anyhow, gql_from_domain, gql_internal, gql_not_found, DomainError,
+    };
+    use async_graphql::{EmptyMutation, EmptySubscription, Object, Result, Schema};
+
+    struct Query;
+
+    #[Object]
+    impl Query {
+        async fn broken(&self) -> Result<i32> {
+            Err(gql_internal("connection to 10.0.0.5:3306 refused"))
+        }
+
+        async fn db(&self) -> Result<i32> {
+            let err: DomainError =
+                sea_orm::DbErr::Custom("syntax error near `passwords`".into()).into();
+            Err(gql_from_domain(err))
+        }
+
+        async fn raw_db(&self) -> Result<i32> {
+            Err(sea_orm::DbErr::Custom(
+                "relation `passwords` does not exist".into(),
+            ))?
+        }
+
+        async fn missing(&self) -> Result<i32> {
+            Err(gql_not_found("Post not found"))
+        }
+
+        async fn legacy(&self) -> Result<i32> {
+            Err(gql_from_anyhow(anyhow::anyhow!(
+                "query failed: Invalid column `password_hash` in users"
+            )))
+        }
+    }
+
+    fn schema(policy: ErrorPolicy) -> Schema<Query, EmptyMutation, EmptySubscription> {
+        Schema::build(Query, EmptyMutation, EmptySubscription)
+            .extension(policy)
+            .finish()
+    }
+
+    fn extension(err: &ServerError, name: &str) -> Option<Value> {
+        err.extensions.as_ref()?.get(name).cloned()
+    }
+
+    #[tokio::test]
+    async fn test_production_masks_internal_errors() {
+        let request = async_graphql::Request::new("{ broken db rawDb missing }")
+            .data(CorrelationId("req-123".into()));
+        let res = schema(ErrorPolicy::Production).execute(request).await;
+        assert_eq!(res.errors.len(), 4);
+
+        for err in res.errors.iter().filter(|e| e.path.len() == 1) {
+            match err.message.as_str() {
+                MASKED_MESSAGE => {
+                    assert_eq!(
+                        extension(err, "correlationId"),
+                        Some(Value::from("req-123"))
+                    );
+                }
+                message => {
+                    // Client errors keep their message
+                    assert_eq!(message, "Post not found");
+                    assert_eq!(extension(err, "correlationId"), None);
+                }
+            }
+        }
+        assert_eq!(
+            res.errors
+                .iter()
+                .filter(|e| e.message == MASKED_MESSAGE)
+                .count(),
+            3
+        );
+    }
+
+    #[tokio::test]
+    async fn test_production_masks_classified_legacy_errors() {
+        let res = schema(ErrorPolicy::Production).execute("{ legacy }").await;
+        let err = &res.errors[0];
+        assert!(!err.message.contains("password_hash"));
+        assert_eq!(extension(err, "code"), Some(Value::from("BAD_REQUEST")));
+    }
+
+    #[tokio::test]
+    async fn test_production_keeps_validation_errors() {
+        let res = schema(ErrorPolicy::Production).execute("{ nope }").await;
+        assert!(res.errors[0].message.contains("nope"));
+        assert_eq!(
+            extension(&res.errors[0], "code"),
+            Some(Value::from(VALIDATION_FAILED))
+        );
+
+        let res = schema(ErrorPolicy::Production).execute("{ missing").await;
+        assert_ne!(res.errors[0].message, MASKED_MESSAGE);
+        assert_eq!(
+            extension(&res.errors[0], "code"),
+            Some(Value::from(PARSE_FAILED))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_production_masks_errors_before_execution() {
+        use crate::graphql::persisted::{PersistedQueries, PersistedQueryStore};
+
+        struct BrokenStore;
+
+        #[async_trait::async_trait]
+        impl PersistedQueryStore for BrokenStore {
+            async fn get(&self, _hash: &str) -> crate::errors::DomainResult<Option<String>> {
+                Err(sea_orm::DbErr::Custom("connection to 10.0.0.5 refused".into()).into())
+            }
+
+            async fn put(&self, _hash: &str, _query: &str) -> crate::errors::DomainResult<()> {
+                Ok(())
+            }
+        }
+
+        let schema = Schema::build(Query, EmptyMutation, EmptySubscription)
+            .extension(ErrorPolicy::Production)
+            .extension(PersistedQueries::new(BrokenStore))
+            .finish();
+        let mut request = async_graphql::Request::new("").data(CorrelationId("req-9".into()));
+        request.extensions.insert(
+            "persistedQuery".to_string(),
+            Value::from_json(serde_json::json!({ "version": 1, "sha256Hash": "abc" })).unwrap(),
+        );
+        let res = schema.execute(request).await;
+        assert_eq!(res.errors[0].message, MASKED_MESSAGE);
+        assert_eq!(
+            extension(&res.errors[0], "correlationId"),
+            Some(Value::from("req-9"))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_development_keeps_messages() {
+        let res = schema(ErrorPolicy::Development).execute("{ broken }").await;
+        assert!(res.errors[0].message.contains("10.0.0.5"));
+        assert_eq!(extension(&res.errors[0], "correlationId"), None);
+    }
+
+    #[test]
+    fn test_correlation_id_from_request() {
+        let request = http::Request::builder()
+            .header("X-Request-Id", "abc")
+            .body(lambda_http::Body::Empty)
+            .unwrap();
+        assert_eq!(CorrelationId::from_request(&request).0, "abc");
+
+        let request = http::Request::builder()
+            .body(lambda_http::Body::Empty)
+            .unwrap();
+        assert_eq!(
+            CorrelationId::from_request(&request).0.len(),
+            CORRELATION_ID_LEN
+        );
+    }
+}
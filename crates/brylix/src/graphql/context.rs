@@ -1,9 +1,13 @@
 //! GraphQL context data structures.
 
-use sea_orm::DatabaseConnection;
+use sea_orm::{DatabaseConnection, EntityTrait};
 use std::sync::Arc;
 
+use super::loader::{EntityDataLoader, Loaders, RelatedDataLoader};
+
+use crate::auth::permissions::PermissionSet;
 use crate::auth::roles::AuthRole;
+use crate::auth::JwtResult;
 
 #[cfg(feature = "admin-override")]
 use crate::auth::admin_override::AdminOverride;
@@ -76,6 +80,22 @@ pub struct ContextData {
     /// Wrapped in Arc for shared ownership across resolvers
     pub tenant: Option<Arc<TenantInfo>>,
 
+    /// Permissions granted to the current request.
+    /// Empty unless set with [`ContextData::with_permissions`]
+    pub permissions: PermissionSet,
+
+    /// The real admin's user ID while impersonating `user`.
+    /// Set with [`ContextData::with_impersonator`]
+    pub impersonator: Option<String>,
+
+    /// Session ID of the current token (`sid` claim).
+    /// Set with [`ContextData::with_session_id`]
+    pub session_id: Option<String>,
+
+    /// DataLoaders created for this request.
+    /// Use [`ContextData::entity_loader`] and [`ContextData::related_loader`]
+    pub loaders: Loaders,
+
     /// Admin override for temporary elevated access.
     /// Present when a user sends an `X-Admin-Override` header with a valid token.
     #[cfg(feature = "admin-override")]
@@ -96,6 +116,10 @@ impl ContextData {
             user,
             role,
             tenant: tenant.map(Arc::new),
+            permissions: PermissionSet::new(),
+            impersonator: None,
+            session_id: None,
+            loaders: Loaders::default(),
             #[cfg(feature = "admin-override")]
             admin_override,
         }
@@ -113,6 +137,10 @@ impl ContextData {
             user,
             role,
             tenant: None,
+            permissions: PermissionSet::new(),
+            impersonator: None,
+            session_id: None,
+            loaders: Loaders::default(),
             #[cfg(feature = "admin-override")]
             admin_override,
         }
@@ -131,11 +159,130 @@ impl ContextData {
             user,
             role,
             tenant: Some(Arc::new(tenant)),
+            permissions: PermissionSet::new(),
+            impersonator: None,
+            session_id: None,
+            loaders: Loaders::default(),
             #[cfg(feature = "admin-override")]
             admin_override,
         }
     }
 
+    /// Create a ContextData from the result of the JWT middleware.
+    ///
+    /// Carries over the user, role, impersonating admin and session ID, so
+    /// [`NotImpersonating`](super::NotImpersonating) and
+    /// [`end_impersonation`](crate::auth::impersonation::end_impersonation)
+    /// see both identities. Prefer this to [`single_tenant`](Self::single_tenant)
+    /// and [`multi_tenant`](Self::multi_tenant) for authenticated requests.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let auth = jwt_middleware(&request).await?;
+    /// let data = ContextData::from_auth(db, auth, None);
+    /// ```
+    pub fn from_auth(
+        db: DatabaseConnection,
+        auth: JwtResult,
+        tenant: Option<TenantInfo>,
+        #[cfg(feature = "admin-override")] admin_override: Option<AdminOverride>,
+    ) -> Self {
+        Self::new(
+            db,
+            auth.user_id,
+            auth.role,
+            tenant,
+            #[cfg(feature = "admin-override")]
+            admin_override,
+        )
+        .with_impersonator(auth.impersonator)
+        .with_session_id(auth.session_id)
+    }
+
+    /// Attach the permissions resolved for this request.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let permissions = rbac.permissions_for(&db, role.role_name()).await?;
+    /// let data = ContextData::single_tenant(db, user, Some(role)).with_permissions(permissions);
+    /// ```
+    pub fn with_permissions(mut self, permissions: PermissionSet) -> Self {
+        self.permissions = permissions;
+        self
+    }
+
+    /// Attach the impersonating admin from [`JwtResult::impersonator`].
+    ///
+    /// [`JwtResult::impersonator`]: crate::auth::JwtResult::impersonator
+    pub fn with_impersonator(mut self, impersonator: Option<String>) -> Self {
+        self.impersonator = impersonator;
+        self
+    }
+
+    /// Attach the session ID from [`JwtResult::session_id`].
+    ///
+    /// [`JwtResult::session_id`]: crate::auth::JwtResult::session_id
+    pub fn with_session_id(mut self, session_id: Option<String>) -> Self {
+        self.session_id = session_id;
+        self
+    }
+
+    /// Get the request's batching loader for `E`, keyed by primary key.
+    ///
+    /// The loader uses this request's database connection and is created
+    /// on first use.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let user = data.entity_loader::<users::Entity>().load_one(task.assignee_id).await?;
+    /// ```
+    pub fn entity_loader<E: EntityTrait>(&self) -> Arc<EntityDataLoader<E>> {
+        self.loaders.entity::<E>(&self.db)
+    }
+
+    /// Get the request's batching loader for rows of `E` grouped by
+    /// `column`, usually a foreign key.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let tasks = data
+    ///     .related_loader::<tasks::Entity>(tasks::Column::AssigneeId)
+    ///     .load_one(user.id)
+    ///     .await?
+    ///     .unwrap_or_default();
+    /// ```
+    pub fn related_loader<E: EntityTrait>(&self, column: E::Column) -> Arc<RelatedDataLoader<E>> {
+        self.loaders.related::<E>(&self.db, column)
+    }
+
+    /// Check if an admin is impersonating the user.
+    pub fn is_impersonating(&self) -> bool {
+        self.impersonator.is_some()
+    }
+
+    /// Get the impersonating admin's user ID, if any.
+    pub fn impersonator(&self) -> Option<&str> {
+        self.impersonator.as_deref()
+    }
+
+    /// Get the person actually making the request: the impersonating admin
+    /// if present, otherwise the authenticated user.
+    pub fn real_user_id(&self) -> Option<&str> {
+        self.impersonator.as_deref().or(self.user.as_deref())
+    }
+
+    /// Check if the request has been granted a permission.
+    ///
+    /// Wildcard grants such as `"invoice.*"` or `"*"` are honoured.
+    /// Unauthenticated requests never have permissions.
+    pub fn has_permission(&self, permission: &str) -> bool {
+        self.is_authenticated() && self.permissions.allows(permission)
+    }
+
     /// Check if the request is authenticated
     pub fn is_authenticated(&self) -> bool {
         self.user.is_some()
@@ -146,6 +293,18 @@ impl ContextData {
         self.user.as_deref()
     }
 
+    /// Get the user whose resources this request may access as their
+    /// owner.
+    ///
+    /// `None` for API keys: a key acts through its scopes, not with its
+    /// owner's full rights.
+    pub fn owner_user_id(&self) -> Option<&str> {
+        match self.role {
+            Some(AuthRole::ApiKey { .. }) => None,
+            _ => self.user_id(),
+        }
+    }
+
     /// Get the authentication role if set
     pub fn auth_role(&self) -> Option<&AuthRole> {
         self.role.as_ref()
@@ -153,13 +312,14 @@ impl ContextData {
 
     /// Check if the authenticated user is an admin.
     ///
-    /// Returns `true` if the user has an admin role OR if an admin override is present.
+    /// Returns `true` if the user has an admin role OR if an unscoped admin
+    /// override issued for this user is present.
     pub fn is_admin(&self) -> bool {
         if self.role.as_ref().is_some_and(|r| r.is_admin()) {
             return true;
         }
         #[cfg(feature = "admin-override")]
-        if self.admin_override.is_some() {
+        if crate::auth::admin_override::usable_admin_override(self, None).is_some() {
             return true;
         }
         false
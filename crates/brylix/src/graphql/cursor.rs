@@ -0,0 +1,518 @@
+//! Relay-style cursor pagination.
+//!
+//! Page-number pagination ([`super::pagination`]) uses `OFFSET`, which gets
+//! slower the deeper you page and skips or repeats rows when new rows are
+//! inserted between requests. Cursor pagination instead remembers the last
+//! row seen and continues with a keyset query
+//! (`WHERE (created_at, id) > (:created_at, :id)`), which stays fast and
+//! stable on large, busy tables.
+//!
+//! Output follows the [Relay connection spec]: `edges { cursor node }` plus
+//! `pageInfo { hasNextPage hasPreviousPage startCursor endCursor }`.
+//!
+//! # Usage
+//!
+//! ```rust,ignore
+//! use brylix::graphql::cursor::{keyset_paginate, Cursor, CursorArgs, CursorConnection};
+//!
+//! #[Object]
+//! impl Query {
+//!     async fn orders(
+//!         &self,
+//!         ctx: &Context<'_>,
+//!         first: Option<i32>,
+//!         after: Option<String>,
+//!         last: Option<i32>,
+//!         before: Option<String>,
+//!     ) -> Result<CursorConnection<Order>> {
+//!         let data = ctx.data_unchecked::<ContextData>();
+//!         let window = CursorArgs::new(first, after, last, before)
+//!             .window()
+//!             .map_err(gql_from_domain)?;
+//!
+//!         let page = keyset_paginate(
+//!             &data.db,
+//!             orders::Entity::find(),
+//!             orders::Column::CreatedAt,
+//!             orders::Column::Id,
+//!             &window,
+//!             |m| Cursor::new(m.created_at, m.id),
+//!         )
+//!         .await
+//!         .map_err(gql_from_domain)?;
+//!
+//!         Ok(page.map(Order::from))
+//!     }
+//! }
+//! ```
+//!
+//! [Relay connection spec]: https://relay.dev/graphql/connections.htm
+
+use std::borrow::Cow;
+
+use async_graphql::{OutputType, SimpleObject, TypeName};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use sea_orm::{
+    ColumnTrait, Condition, ConnectionTrait, EntityTrait, Order, QueryFilter, QueryOrder,
+    QuerySelect, Select,
+};
+
+use crate::errors::{DomainError, DomainResult};
+
+/// Page size used when neither `first` nor `last` is given.
+pub const DEFAULT_PAGE_SIZE: u64 = 20;
+
+/// Largest page size a client may request; larger values are clamped.
+pub const MAX_PAGE_SIZE: u64 = 100;
+
+/// Position of a row in a `(created_at, id)` ordering.
+///
+/// Clients only ever see the opaque string from [`Cursor::encode`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cursor {
+    /// Sort timestamp of the row
+    pub created_at: DateTime<Utc>,
+
+    /// Primary key of the row, breaking ties between equal timestamps
+    pub id: i64,
+}
+
+impl Cursor {
+    /// Create a cursor pointing at a row.
+    pub fn new(created_at: DateTime<Utc>, id: i64) -> Self {
+        Self { created_at, id }
+    }
+
+    /// Encode the cursor as an opaque URL-safe string.
+    pub fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(format!(
+            "{}:{}",
+            self.created_at.timestamp_micros(),
+            self.id
+        ))
+    }
+
+    /// Decode a cursor produced by [`Cursor::encode`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `InvalidInput` if the cursor is malformed
+    pub fn decode(cursor: &str) -> DomainResult<Self> {
+        let invalid = || DomainError::InvalidInput("Invalid cursor".into());
+
+        let bytes = URL_SAFE_NO_PAD.decode(cursor).map_err(|_| invalid())?;
+        let raw = String::from_utf8(bytes).map_err(|_| invalid())?;
+        let (micros, id) = raw.split_once(':').ok_or_else(invalid)?;
+
+        let micros = micros.parse::<i64>().map_err(|_| invalid())?;
+        let created_at = DateTime::from_timestamp_micros(micros).ok_or_else(invalid)?;
+        let id = id.parse::<i64>().map_err(|_| invalid())?;
+
+        Ok(Self { created_at, id })
+    }
+}
+
+/// Raw Relay pagination arguments as received by a resolver.
+#[derive(Debug, Clone, Default)]
+pub struct CursorArgs {
+    /// Return the first `n` rows after `after`
+    pub first: Option<i32>,
+
+    /// Opaque cursor to start after
+    pub after: Option<String>,
+
+    /// Return the last `n` rows before `before`
+    pub last: Option<i32>,
+
+    /// Opaque cursor to end before
+    pub before: Option<String>,
+}
+
+impl CursorArgs {
+    /// Create arguments from the four Relay resolver arguments.
+    pub fn new(
+        first: Option<i32>,
+        after: Option<String>,
+        last: Option<i32>,
+        before: Option<String>,
+    ) -> Self {
+        Self {
+            first,
+            after,
+            last,
+            before,
+        }
+    }
+
+    /// Validate the arguments and decode the cursors.
+    ///
+    /// Page sizes are clamped to [`MAX_PAGE_SIZE`]; without `first` or
+    /// `last`, [`DEFAULT_PAGE_SIZE`] rows are returned.
+    ///
+    /// # Errors
+    ///
+    /// Returns `InvalidInput` if both `first` and `last` are set, either is
+    /// negative, or a cursor is malformed
+    pub fn window(&self) -> DomainResult<CursorWindow> {
+        if self.first.is_some() && self.last.is_some() {
+            return Err(DomainError::InvalidInput(
+                "Cannot combine `first` and `last`".into(),
+            ));
+        }
+
+        let size = match self.first.or(self.last) {
+            Some(n) if n < 0 => {
+                return Err(DomainError::InvalidInput(
+                    "`first` and `last` must not be negative".into(),
+                ))
+            }
+            Some(n) => (n as u64).min(MAX_PAGE_SIZE),
+            None => DEFAULT_PAGE_SIZE,
+        };
+
+        Ok(CursorWindow {
+            limit: size,
+            after: self.after.as_deref().map(Cursor::decode).transpose()?,
+            before: self.before.as_deref().map(Cursor::decode).transpose()?,
+            backward: self.last.is_some(),
+        })
+    }
+}
+
+/// Validated pagination window produced by [`CursorArgs::window`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CursorWindow {
+    /// Number of rows to return
+    pub limit: u64,
+
+    /// Only rows after this cursor
+    pub after: Option<Cursor>,
+
+    /// Only rows before this cursor
+    pub before: Option<Cursor>,
+
+    /// Whether the page is taken from the end (`last`) rather than the start
+    pub backward: bool,
+}
+
+/// An edge in a [`CursorConnection`].
+#[derive(Debug, Clone, SimpleObject)]
+#[graphql(name_type)]
+pub struct Edge<T: OutputType> {
+    /// A cursor for use in pagination
+    pub cursor: String,
+
+    /// The item at the end of the edge
+    pub node: T,
+}
+
+impl<T: OutputType> TypeName for Edge<T> {
+    fn type_name() -> Cow<'static, str> {
+        format!("{}Edge", T::type_name()).into()
+    }
+}
+
+/// Relay pagination metadata for a [`CursorConnection`].
+#[derive(Debug, Clone, Default, SimpleObject)]
+pub struct CursorPageInfo {
+    /// Whether more rows exist after `end_cursor`
+    pub has_next_page: bool,
+
+    /// Whether more rows exist before `start_cursor`
+    pub has_previous_page: bool,
+
+    /// Cursor of the first edge
+    pub start_cursor: Option<String>,
+
+    /// Cursor of the last edge
+    pub end_cursor: Option<String>,
+}
+
+/// A Relay-compatible connection of `T`, exposed as `<T>Connection`.
+#[derive(Debug, Clone, SimpleObject)]
+#[graphql(name_type)]
+pub struct CursorConnection<T: OutputType> {
+    /// The edges of the current page
+    pub edges: Vec<Edge<T>>,
+
+    /// Pagination metadata
+    pub page_info: CursorPageInfo,
+}
+
+impl<T: OutputType> TypeName for CursorConnection<T> {
+    fn type_name() -> Cow<'static, str> {
+        format!("{}Connection", T::type_name()).into()
+    }
+}
+
+impl<T: OutputType> CursorConnection<T> {
+    /// Build a connection from rows fetched with [`keyset`].
+    ///
+    /// `rows` must be in query order and may contain one extra row, which
+    /// is dropped and used to detect a further page.
+    pub fn from_rows<R>(
+        mut rows: Vec<R>,
+        window: &CursorWindow,
+        cursor_of: impl Fn(&R) -> Cursor,
+        node: impl Fn(R) -> T,
+    ) -> Self {
+        let has_more = rows.len() as u64 > window.limit;
+        rows.truncate(window.limit as usize);
+        if window.backward {
+            rows.reverse();
+        }
+
+        let edges: Vec<Edge<T>> = rows
+            .into_iter()
+            .map(|row| Edge {
+                cursor: cursor_of(&row).encode(),
+                node: node(row),
+            })
+            .collect();
+
+        let (has_next_page, has_previous_page) = if window.backward {
+            (window.before.is_some(), has_more)
+        } else {
+            (has_more, window.after.is_some())
+        };
+
+        Self {
+            page_info: CursorPageInfo {
+                has_next_page,
+                has_previous_page,
+                start_cursor: edges.first().map(|e| e.cursor.clone()),
+                end_cursor: edges.last().map(|e| e.cursor.clone()),
+            },
+            edges,
+        }
+    }
+
+    /// Convert every node, keeping cursors and page info.
+    pub fn map<U: OutputType>(self, f: impl Fn(T) -> U) -> CursorConnection<U> {
+        CursorConnection {
+            edges: self
+                .edges
+                .into_iter()
+                .map(|e| Edge {
+                    cursor: e.cursor,
+                    node: f(e.node),
+                })
+                .collect(),
+            page_info: self.page_info,
+        }
+    }
+}
+
+/// Restrict a query to a cursor window using a `(created_at, id)` keyset.
+///
+/// Adds the `after`/`before` conditions, orders by both columns (descending
+/// for `last`) and fetches one extra row so [`CursorConnection::from_rows`]
+/// can tell whether another page exists.
+pub fn keyset<E, C>(select: Select<E>, created_at: C, id: C, window: &CursorWindow) -> Select<E>
+where
+    E: EntityTrait,
+    C: ColumnTrait,
+{
+    let mut select = select;
+    if let Some(after) = window.after {
+        select = select.filter(
+            Condition::any().add(created_at.gt(after.created_at)).add(
+                Condition::all()
+                    .add(created_at.eq(after.created_at))
+                    .add(id.gt(after.id)),
+            ),
+        );
+    }
+    if let Some(before) = window.before {
+        select = select.filter(
+            Condition::any().add(created_at.lt(before.created_at)).add(
+                Condition::all()
+                    .add(created_at.eq(before.created_at))
+                    .add(id.lt(before.id)),
+            ),
+        );
+    }
+
+    let order = if window.backward {
+        Order::Desc
+    } else {
+        Order::Asc
+    };
+    select
+        .order_by(created_at, order.clone())
+        .order_by(id, order)
+        .limit(window.limit + 1)
+}
+
+/// Run a keyset query and return the page as a [`CursorConnection`].
+///
+/// # Errors
+///
+/// Returns `DatabaseError` if the query fails
+pub async fn keyset_paginate<E, C, T>(
+    db: &impl ConnectionTrait,
+    select: Select<E>,
+    created_at: C,
+    id: C,
+    window: &CursorWindow,
+    cursor_of: impl Fn(&E::Model) -> Cursor,
+) -> DomainResult<CursorConnection<T>>
+where
+    E: EntityTrait,
+    C: ColumnTrait,
+    T: OutputType + From<E::Model>,
+{
+    let rows = keyset(select, created_at, id, window).all(db).await?;
+    Ok(CursorConnection::from_rows(
+        rows,
+        window,
+        cursor_of,
+        T::from,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sea_orm::{DbBackend, QueryTrait};
+
+    fn at(secs: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp(secs, 0).unwrap()
+    }
+
+    #[test]
+    fn test_cursor_roundtrip() {
+        let cursor = Cursor::new(at(1_700_000_000), 42);
+        let encoded = cursor.encode();
+        assert!(!encoded.contains(':'));
+        assert_eq!(Cursor::decode(&encoded).unwrap(), cursor);
+        assert!(matches!(
+            Cursor::decode("not a cursor"),
+            Err(DomainError::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn test_window_validation() {
+        let window = CursorArgs::default().window().unwrap();
+        assert_eq!(window.limit, DEFAULT_PAGE_SIZE);
+        assert!(!window.backward);
+
+        let window = CursorArgs::new(None, None, Some(1_000), None)
+            .window()
+            .unwrap();
+        assert_eq!(window.limit, MAX_PAGE_SIZE);
+        assert!(window.backward);
+
+        assert!(CursorArgs::new(Some(1), None, Some(1), None)
+            .window()
+            .is_err());
+        assert!(CursorArgs::new(Some(-1), None, None, None)
+            .window()
+            .is_err());
+    }
+
+    #[test]
+    fn test_from_rows_forward() {
+        let cursor = Cursor::new(at(0), 1);
+        let window = CursorArgs::new(Some(2), Some(cursor.encode()), None, None)
+            .window()
+            .unwrap();
+
+        let conn = CursorConnection::from_rows(
+            vec![2i64, 3, 4],
+            &window,
+            |id| Cursor::new(at(*id), *id),
+            |id| id as i32,
+        );
+        assert_eq!(
+            conn.edges.iter().map(|e| e.node).collect::<Vec<_>>(),
+            [2, 3]
+        );
+        assert!(conn.page_info.has_next_page);
+        assert!(conn.page_info.has_previous_page);
+        assert_eq!(
+            conn.page_info.end_cursor,
+            Some(Cursor::new(at(3), 3).encode())
+        );
+    }
+
+    #[test]
+    fn test_from_rows_backward() {
+        let window = CursorArgs::new(None, None, Some(2), None).window().unwrap();
+
+        // Rows arrive newest first from the descending keyset query
+        let conn = CursorConnection::from_rows(
+            vec![9i64, 8],
+            &window,
+            |id| Cursor::new(at(*id), *id),
+            |id| id as i32,
+        );
+        assert_eq!(
+            conn.edges.iter().map(|e| e.node).collect::<Vec<_>>(),
+            [8, 9]
+        );
+        assert!(!conn.page_info.has_next_page);
+        assert!(!conn.page_info.has_previous_page);
+    }
+
+    #[test]
+    fn test_keyset_query() {
+        use crate::audit::entity;
+
+        let window = CursorWindow {
+            limit: 10,
+            after: Some(Cursor::new(at(0), 5)),
+            before: None,
+            backward: false,
+        };
+        let sql = keyset(
+            entity::Entity::find(),
+            entity::Column::OccurredAt,
+            entity::Column::Id,
+            &window,
+        )
+        .build(DbBackend::Postgres)
+        .to_string();
+
+        assert!(sql.contains(r#""occurred_at" > '1970-01-01 00:00:00.000000 +00:00'"#));
+        assert!(sql.contains(r#""audit_log"."id" > 5"#));
+        assert!(sql
+            .contains(r#"ORDER BY "audit_log"."occurred_at" ASC, "audit_log"."id" ASC LIMIT 11"#));
+    }
+
+    #[tokio::test]
+    async fn test_connection_schema() {
+        use async_graphql::{EmptyMutation, EmptySubscription, Object, Schema};
+
+        struct Query;
+
+        #[Object]
+        impl Query {
+            async fn numbers(&self) -> CursorConnection<i32> {
+                let window = CursorArgs::default().window().unwrap();
+                CursorConnection::from_rows(
+                    vec![1i64, 2],
+                    &window,
+                    |id| Cursor::new(at(*id), *id),
+                    |id| id as i32,
+                )
+            }
+        }
+
+        let schema = Schema::build(Query, EmptyMutation, EmptySubscription).finish();
+        let sdl = schema.sdl();
+        assert!(sdl.contains("type IntConnection"));
+        assert!(sdl.contains("type IntEdge"));
+
+        let res = schema
+            .execute("{ numbers { edges { cursor node } pageInfo { hasNextPage endCursor } } }")
+            .await;
+        assert!(res.errors.is_empty(), "{:?}", res.errors);
+        let json = res.data.into_json().unwrap();
+        assert_eq!(json["numbers"]["edges"][1]["node"], 2);
+        assert_eq!(json["numbers"]["pageInfo"]["hasNextPage"], false);
+    }
+}
@@ -0,0 +1,463 @@
+//! Reusable filter and sort inputs for list queries.
+//!
+//! Instead of ad-hoc arguments on every list resolver, declare a filter
+//! input per entity built from [`StringFilter`], [`IntFilter`] and
+//! [`DateTimeFilter`], accept `[SortInput!]` for ordering, and translate
+//! both to SeaORM with a [`ListColumns`] allowlist. Only columns registered
+//! on the allowlist can be filtered or sorted; anything else is rejected
+//! with `InvalidInput`, so clients can never reach arbitrary columns.
+//! `contains` and `startsWith` match their text literally (`%` and `_` are
+//! escaped), and `in` lists are capped at [`MAX_IN_VALUES`] entries.
+//!
+//! # Usage
+//!
+//! ```rust,ignore
+//! use brylix::graphql::filter::{IntFilter, ListColumns, SortInput, StringFilter};
+//!
+//! #[derive(InputObject, Default)]
+//! pub struct TaskFilter {
+//!     pub title: Option<StringFilter>,
+//!     pub priority: Option<IntFilter>,
+//! }
+//!
+//! fn task_columns() -> ListColumns<tasks::Column> {
+//!     ListColumns::new()
+//!         .filterable("title", tasks::Column::Title)
+//!         .filterable("priority", tasks::Column::Priority)
+//!         .sortable("priority", tasks::Column::Priority)
+//!         .sortable("createdAt", tasks::Column::CreatedAt)
+//! }
+//!
+//! async fn tasks(
+//!     &self,
+//!     ctx: &Context<'_>,
+//!     filter: Option<TaskFilter>,
+//!     sort: Option<Vec<SortInput>>,
+//! ) -> Result<Vec<Task>> {
+//!     let data = ctx.data_unchecked::<ContextData>();
+//!     let columns = task_columns();
+//!     let filter = filter.unwrap_or_default();
+//!
+//!     let condition = Condition::all()
+//!         .add(columns.filter("title", filter.title.as_ref()).map_err(gql_from_domain)?)
+//!         .add(columns.filter("priority", filter.priority.as_ref()).map_err(gql_from_domain)?);
+//!     let select = columns
+//!         .order(tasks::Entity::find().filter(condition), sort.as_deref().unwrap_or_default())
+//!         .map_err(gql_from_domain)?;
+//!
+//!     Ok(select.all(&data.db).await?.into_iter().map(Task::from).collect())
+//! }
+//! ```
+
+use async_graphql::{Enum, InputObject};
+use chrono::{DateTime, Utc};
+use sea_orm::sea_query::LikeExpr;
+use sea_orm::{ColumnTrait, Condition, EntityTrait, Order, QueryOrder, Select};
+
+use crate::errors::{DomainError, DomainResult};
+
+/// Maximum number of values in an `in` filter.
+pub const MAX_IN_VALUES: usize = 100;
+
+/// Escape character for `LIKE` patterns built from client text
+const LIKE_ESCAPE: char = '\\';
+
+/// Filter on a text column. All set operators must match.
+#[derive(Debug, Clone, Default, InputObject)]
+pub struct StringFilter {
+    /// Equal to
+    pub eq: Option<String>,
+
+    /// Not equal to
+    pub ne: Option<String>,
+
+    /// Contains the substring
+    pub contains: Option<String>,
+
+    /// Starts with the prefix
+    pub starts_with: Option<String>,
+
+    /// Equal to one of the values
+    #[graphql(name = "in")]
+    pub in_: Option<Vec<String>>,
+}
+
+/// Filter on an integer column. All set operators must match.
+#[derive(Debug, Clone, Default, InputObject)]
+pub struct IntFilter {
+    /// Equal to
+    pub eq: Option<i64>,
+
+    /// Not equal to
+    pub ne: Option<i64>,
+
+    /// Greater than
+    pub gt: Option<i64>,
+
+    /// Greater than or equal to
+    pub gte: Option<i64>,
+
+    /// Less than
+    pub lt: Option<i64>,
+
+    /// Less than or equal to
+    pub lte: Option<i64>,
+
+    /// Equal to one of the values
+    #[graphql(name = "in")]
+    pub in_: Option<Vec<i64>>,
+}
+
+/// Filter on a timestamp column. All set operators must match.
+#[derive(Debug, Clone, Default, InputObject)]
+pub struct DateTimeFilter {
+    /// Equal to
+    pub eq: Option<DateTime<Utc>>,
+
+    /// After
+    pub gt: Option<DateTime<Utc>>,
+
+    /// At or after
+    pub gte: Option<DateTime<Utc>>,
+
+    /// Before
+    pub lt: Option<DateTime<Utc>>,
+
+    /// At or before
+    pub lte: Option<DateTime<Utc>>,
+}
+
+/// Sort direction.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Enum)]
+pub enum SortDirection {
+    /// Smallest first
+    #[default]
+    Asc,
+
+    /// Largest first
+    Desc,
+}
+
+impl From<SortDirection> for Order {
+    fn from(direction: SortDirection) -> Self {
+        match direction {
+            SortDirection::Asc => Order::Asc,
+            SortDirection::Desc => Order::Desc,
+        }
+    }
+}
+
+/// Sort a list by one field.
+#[derive(Debug, Clone, InputObject)]
+pub struct SortInput {
+    /// Name of a sortable field
+    pub field: String,
+
+    /// Sort direction (default: ASC)
+    #[graphql(default)]
+    pub direction: SortDirection,
+}
+
+impl SortInput {
+    /// Create a sort on `field`.
+    pub fn new(field: impl Into<String>, direction: SortDirection) -> Self {
+        Self {
+            field: field.into(),
+            direction,
+        }
+    }
+}
+
+/// A filter input that can be translated to a SeaORM [`Condition`].
+pub trait ColumnFilter {
+    /// Build the condition for `column`. An empty filter matches everything.
+    ///
+    /// # Errors
+    ///
+    /// Returns `InvalidInput` if the filter is out of bounds, e.g. an `in`
+    /// list longer than [`MAX_IN_VALUES`]
+    fn condition<C: ColumnTrait>(&self, column: C) -> DomainResult<Condition>;
+}
+
+impl ColumnFilter for StringFilter {
+    fn condition<C: ColumnTrait>(&self, column: C) -> DomainResult<Condition> {
+        let mut condition = Condition::all();
+        if let Some(v) = &self.eq {
+            condition = condition.add(column.eq(v.as_str()));
+        }
+        if let Some(v) = &self.ne {
+            condition = condition.add(column.ne(v.as_str()));
+        }
+        if let Some(v) = &self.contains {
+            condition = condition.add(column.like(like_pattern("%", v, "%")));
+        }
+        if let Some(v) = &self.starts_with {
+            condition = condition.add(column.like(like_pattern("", v, "%")));
+        }
+        if let Some(v) = &self.in_ {
+            check_in_len(v)?;
+            condition = condition.add(column.is_in(v.iter().map(String::as_str)));
+        }
+        Ok(condition)
+    }
+}
+
+impl ColumnFilter for IntFilter {
+    fn condition<C: ColumnTrait>(&self, column: C) -> DomainResult<Condition> {
+        let mut condition = Condition::all();
+        if let Some(v) = self.eq {
+            condition = condition.add(column.eq(v));
+        }
+        if let Some(v) = self.ne {
+            condition = condition.add(column.ne(v));
+        }
+        if let Some(v) = self.gt {
+            condition = condition.add(column.gt(v));
+        }
+        if let Some(v) = self.gte {
+            condition = condition.add(column.gte(v));
+        }
+        if let Some(v) = self.lt {
+            condition = condition.add(column.lt(v));
+        }
+        if let Some(v) = self.lte {
+            condition = condition.add(column.lte(v));
+        }
+        if let Some(v) = &self.in_ {
+            check_in_len(v)?;
+            condition = condition.add(column.is_in(v.iter().copied()));
+        }
+        Ok(condition)
+    }
+}
+
+impl ColumnFilter for DateTimeFilter {
+    fn condition<C: ColumnTrait>(&self, column: C) -> DomainResult<Condition> {
+        let mut condition = Condition::all();
+        if let Some(v) = self.eq {
+            condition = condition.add(column.eq(v));
+        }
+        if let Some(v) = self.gt {
+            condition = condition.add(column.gt(v));
+        }
+        if let Some(v) = self.gte {
+            condition = condition.add(column.gte(v));
+        }
+        if let Some(v) = self.lt {
+            condition = condition.add(column.lt(v));
+        }
+        if let Some(v) = self.lte {
+            condition = condition.add(column.lte(v));
+        }
+        Ok(condition)
+    }
+}
+
+/// Build a `LIKE` pattern matching `text` literally between `prefix` and
+/// `suffix` wildcards.
+fn like_pattern(prefix: &str, text: &str, suffix: &str) -> LikeExpr {
+    let mut pattern = String::with_capacity(text.len() + 2);
+    pattern.push_str(prefix);
+    for c in text.chars() {
+        if matches!(c, '%' | '_' | LIKE_ESCAPE) {
+            pattern.push(LIKE_ESCAPE);
+        }
+        pattern.push(c);
+    }
+    pattern.push_str(suffix);
+    LikeExpr::new(pattern).escape(LIKE_ESCAPE)
+}
+
+fn check_in_len<T>(values: &[T]) -> DomainResult<()> {
+    if values.len() > MAX_IN_VALUES {
+        return Err(DomainError::InvalidInput(format!(
+            "`in` accepts at most {MAX_IN_VALUES} values"
+        )));
+    }
+    Ok(())
+}
+
+/// Allowlist of the columns a list query may filter and sort on.
+///
+/// Fields are addressed by their public (GraphQL) name, which does not
+/// have to match the column name.
+#[derive(Debug, Clone)]
+pub struct ListColumns<C> {
+    filterable: Vec<(&'static str, C)>,
+    sortable: Vec<(&'static str, C)>,
+}
+
+impl<C> Default for ListColumns<C> {
+    fn default() -> Self {
+        Self {
+            filterable: Vec::new(),
+            sortable: Vec::new(),
+        }
+    }
+}
+
+impl<C: ColumnTrait> ListColumns<C> {
+    /// Create an empty allowlist.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allow filtering on `column` under the name `field`.
+    pub fn filterable(mut self, field: &'static str, column: C) -> Self {
+        self.filterable.push((field, column));
+        self
+    }
+
+    /// Allow sorting on `column` under the name `field`.
+    pub fn sortable(mut self, field: &'static str, column: C) -> Self {
+        self.sortable.push((field, column));
+        self
+    }
+
+    /// Build the condition for an optional filter on `field`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `InvalidInput` if a filter is given for a field that is not
+    /// filterable, or the filter itself is invalid
+    pub fn filter<F: ColumnFilter>(
+        &self,
+        field: &str,
+        filter: Option<&F>,
+    ) -> DomainResult<Condition> {
+        let Some(filter) = filter else {
+            return Ok(Condition::all());
+        };
+        let column = lookup(&self.filterable, field)
+            .ok_or_else(|| DomainError::InvalidInput(format!("Cannot filter by `{field}`")))?;
+        filter.condition(column)
+    }
+
+    /// Apply `sort` to `select`, in order of priority.
+    ///
+    /// # Errors
+    ///
+    /// Returns `InvalidInput` if a sort field is not sortable
+    pub fn order<E: EntityTrait>(
+        &self,
+        select: Select<E>,
+        sort: &[SortInput],
+    ) -> DomainResult<Select<E>> {
+        let mut select = select;
+        for s in sort {
+            let column = lookup(&self.sortable, &s.field).ok_or_else(|| {
+                DomainError::InvalidInput(format!("Cannot sort by `{}`", s.field))
+            })?;
+            select = select.order_by(column, s.direction.into());
+        }
+        Ok(select)
+    }
+}
+
+fn lookup<C: Copy>(columns: &[(&'static str, C)], field: &str) -> Option<C> {
+    columns
+        .iter()
+        .find(|(name, _)| *name == field)
+        .map(|(_, column)| *column)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::entity;
+    use sea_orm::{DbBackend, QueryFilter, QueryTrait};
+
+    fn columns() -> ListColumns<entity::Column> {
+        ListColumns::new()
+            .filterable("action", entity::Column::Action)
+            .filterable("id", entity::Column::Id)
+            .sortable("occurredAt", entity::Column::OccurredAt)
+    }
+
+    fn sql(select: Select<entity::Entity>) -> String {
+        select.build(DbBackend::Postgres).to_string()
+    }
+
+    #[test]
+    fn test_filters_translate_to_conditions() {
+        let columns = columns();
+        let action = StringFilter {
+            starts_with: Some("auth.".into()),
+            in_: Some(vec!["auth.login".into(), "auth.logout".into()]),
+            ..Default::default()
+        };
+        let id = IntFilter {
+            gte: Some(10),
+            lt: Some(20),
+            ..Default::default()
+        };
+
+        let condition = Condition::all()
+            .add(columns.filter("action", Some(&action)).unwrap())
+            .add(columns.filter("id", Some(&id)).unwrap())
+            .add(columns.filter::<IntFilter>("id", None).unwrap());
+        let sql = sql(entity::Entity::find().filter(condition));
+
+        assert!(sql.contains(r#""audit_log"."action" LIKE 'auth.%' ESCAPE E'\\'"#));
+        assert!(sql.contains(r#""audit_log"."action" IN ('auth.login', 'auth.logout')"#));
+        assert!(sql.contains(r#""audit_log"."id" >= 10 AND "audit_log"."id" < 20"#));
+    }
+
+    #[test]
+    fn test_like_wildcards_are_escaped() {
+        let action = StringFilter {
+            contains: Some(r"100%_\".into()),
+            ..Default::default()
+        };
+        let condition = columns().filter("action", Some(&action)).unwrap();
+        let sql = sql(entity::Entity::find().filter(condition));
+        // Postgres escape-string syntax: E'\\' is a single backslash
+        assert!(sql.contains(r#""audit_log"."action" LIKE E'%100\\%\\_\\\\%' ESCAPE E'\\'"#));
+    }
+
+    #[test]
+    fn test_in_lists_are_capped() {
+        let columns = columns();
+        let id = IntFilter {
+            in_: Some((0..MAX_IN_VALUES as i64).collect()),
+            ..Default::default()
+        };
+        assert!(columns.filter("id", Some(&id)).is_ok());
+
+        let action = StringFilter {
+            in_: Some(vec!["auth.login".into(); MAX_IN_VALUES + 1]),
+            ..Default::default()
+        };
+        assert!(matches!(
+            columns.filter("action", Some(&action)),
+            Err(DomainError::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn test_sort_applies_in_order() {
+        let sort = [SortInput::new("occurredAt", SortDirection::Desc)];
+        let select = columns().order(entity::Entity::find(), &sort).unwrap();
+        assert!(sql(select).ends_with(r#"ORDER BY "audit_log"."occurred_at" DESC"#));
+    }
+
+    #[test]
+    fn test_unlisted_columns_rejected() {
+        let columns = columns();
+        let filter = StringFilter {
+            eq: Some("x".into()),
+            ..Default::default()
+        };
+        assert!(matches!(
+            columns.filter("ip", Some(&filter)),
+            Err(DomainError::InvalidInput(_))
+        ));
+
+        // Filterable is not the same as sortable
+        let sort = [SortInput::new("action", SortDirection::Asc)];
+        assert!(matches!(
+            columns.order(entity::Entity::find(), &sort),
+            Err(DomainError::InvalidInput(_))
+        ));
+    }
+}
@@ -0,0 +1,293 @@
+//! Query depth, complexity and alias limits.
+//!
+//! Without limits a single deeply nested or heavily aliased query can run
+//! thousands of resolvers against the database. [`SchemaBuilderExt`] applies
+//! the limits from [`QueryLimitsConfig`] to a schema: `limit_depth`,
+//! `limit_complexity` and an alias count check. Every rejection carries the
+//! `QUERY_TOO_COMPLEX` error code.
+//!
+//! Body and batch size limits are transport-level and are enforced by the
+//! request handler.
+//!
+//! # Usage
+//!
+//! ```rust,ignore
+//! use brylix::config::Config;
+//! use brylix::graphql::SchemaBuilderExt;
+//!
+//! let schema = Schema::build(Query, Mutation, EmptySubscription)
+//!     .with_query_limits(&Config::get().query_limits)
+//!     .finish();
+//! ```
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use async_graphql::extensions::{
+    Extension, ExtensionContext, ExtensionFactory, NextParseQuery, NextValidation,
+};
+use async_graphql::parser::types::{
+    ExecutableDocument, FragmentDefinition, Selection, SelectionSet,
+};
+use async_graphql::{
+    Name, ObjectType, Positioned, SchemaBuilder, ServerError, ServerResult, SubscriptionType,
+    ValidationResult, Variables,
+};
+
+use crate::config::QueryLimitsConfig;
+use crate::errors::DomainError;
+
+/// Messages produced by async-graphql's built-in depth/complexity checks.
+const BUILTIN_LIMIT_ERRORS: [&str; 2] = ["Query is nested too deep.", "Query is too complex."];
+
+/// Apply [`QueryLimitsConfig`] to a schema builder.
+pub trait SchemaBuilderExt {
+    /// Limit query depth, complexity and alias count.
+    fn with_query_limits(self, limits: &QueryLimitsConfig) -> Self;
+}
+
+impl<Q, M, S> SchemaBuilderExt for SchemaBuilder<Q, M, S>
+where
+    Q: ObjectType + 'static,
+    M: ObjectType + 'static,
+    S: SubscriptionType + 'static,
+{
+    fn with_query_limits(self, limits: &QueryLimitsConfig) -> Self {
+        self.limit_depth(limits.max_depth)
+            .limit_complexity(limits.max_complexity)
+            .extension(QueryLimits {
+                max_aliases: limits.max_aliases,
+            })
+    }
+}
+
+/// Extension counting aliases and tagging limit errors with
+/// `QUERY_TOO_COMPLEX`. Added by [`SchemaBuilderExt::with_query_limits`].
+pub struct QueryLimits {
+    max_aliases: usize,
+}
+
+impl ExtensionFactory for QueryLimits {
+    fn create(&self) -> Arc<dyn Extension> {
+        Arc::new(QueryLimitsExtension {
+            max_aliases: self.max_aliases,
+        })
+    }
+}
+
+struct QueryLimitsExtension {
+    max_aliases: usize,
+}
+
+#[async_trait::async_trait]
+impl Extension for QueryLimitsExtension {
+    async fn parse_query(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        query: &str,
+        variables: &Variables,
+        next: NextParseQuery<'_>,
+    ) -> ServerResult<ExecutableDocument> {
+        let document = next.run(ctx, query, variables).await?;
+        let aliases = count_aliases(&document);
+        if aliases > self.max_aliases {
+            return Err(too_complex(format!(
+                "query has {aliases} aliases, the limit is {}",
+                self.max_aliases
+            )));
+        }
+        Ok(document)
+    }
+
+    async fn validation(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        next: NextValidation<'_>,
+    ) -> Result<ValidationResult, Vec<ServerError>> {
+        next.run(ctx).await.map_err(|errors| {
+            errors
+                .into_iter()
+                .map(|mut e| {
+                    if BUILTIN_LIMIT_ERRORS.contains(&e.message.as_str()) {
+                        e.extensions
+                            .get_or_insert_with(Default::default)
+                            .set("code", "QUERY_TOO_COMPLEX");
+                    }
+                    e
+                })
+                .collect()
+        })
+    }
+}
+
+fn too_complex(reason: String) -> ServerError {
+    let err = DomainError::QueryTooComplex(reason);
+    let mut server_err = ServerError::new(err.to_string(), None);
+    server_err
+        .extensions
+        .get_or_insert_with(Default::default)
+        .set("code", err.code());
+    server_err
+}
+
+/// Count aliased fields in all operations of a document.
+///
+/// Fragment spreads count the fragment's aliases every time they appear,
+/// since each spread is executed.
+fn count_aliases(document: &ExecutableDocument) -> usize {
+    let mut counter = AliasCounter {
+        fragments: &document.fragments,
+        counted: HashMap::new(),
+        visiting: HashSet::new(),
+    };
+    document
+        .operations
+        .iter()
+        .map(|(_, op)| counter.count(&op.node.selection_set.node))
+        .fold(0, usize::saturating_add)
+}
+
+struct AliasCounter<'a> {
+    fragments: &'a HashMap<Name, Positioned<FragmentDefinition>>,
+    /// Alias count per fragment, so repeated spreads are only walked once
+    counted: HashMap<&'a str, usize>,
+    /// Fragments being counted, to stop on cyclic spreads (rejected later
+    /// by validation)
+    visiting: HashSet<&'a str>,
+}
+
+impl<'a> AliasCounter<'a> {
+    fn count(&mut self, set: &'a SelectionSet) -> usize {
+        let mut total = 0usize;
+        for selection in &set.items {
+            let aliases = match &selection.node {
+                Selection::Field(field) => usize::from(field.node.alias.is_some())
+                    .saturating_add(self.count(&field.node.selection_set.node)),
+                Selection::InlineFragment(fragment) => {
+                    self.count(&fragment.node.selection_set.node)
+                }
+                Selection::FragmentSpread(spread) => {
+                    self.fragment(spread.node.fragment_name.node.as_str())
+                }
+            };
+            total = total.saturating_add(aliases);
+        }
+        total
+    }
+
+    fn fragment(&mut self, name: &'a str) -> usize {
+        if let Some(&aliases) = self.counted.get(name) {
+            return aliases;
+        }
+        let Some(fragment) = self.fragments.get(name) else {
+            return 0;
+        };
+        if !self.visiting.insert(name) {
+            return 0;
+        }
+        let aliases = self.count(&fragment.node.selection_set.node);
+        self.visiting.remove(name);
+        self.counted.insert(name, aliases);
+        aliases
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_graphql::{EmptyMutation, EmptySubscription, Object, Schema, SimpleObject};
+
+    #[derive(SimpleObject)]
+    struct Node {
+        id: i32,
+        child: Option<Box<Node>>,
+    }
+
+    struct Query;
+
+    #[Object]
+    impl Query {
+        async fn node(&self) -> Node {
+            Node { id: 1, child: None }
+        }
+    }
+
+    fn schema(limits: QueryLimitsConfig) -> Schema<Query, EmptyMutation, EmptySubscription> {
+        Schema::build(Query, EmptyMutation, EmptySubscription)
+            .with_query_limits(&limits)
+            .finish()
+    }
+
+    async fn error_code(
+        schema: &Schema<Query, EmptyMutation, EmptySubscription>,
+        query: &str,
+    ) -> Option<String> {
+        let res = schema.execute(query).await;
+        let err = res.errors.first()?;
+        match err.extensions.as_ref()?.get("code")? {
+            async_graphql::Value::String(code) => Some(code.clone()),
+            _ => None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_limits_reject_with_code() {
+        let schema = schema(QueryLimitsConfig {
+            max_depth: 3,
+            max_complexity: 5,
+            max_aliases: 2,
+            ..Default::default()
+        });
+
+        assert_eq!(error_code(&schema, "{ node { id } }").await, None);
+        assert_eq!(
+            error_code(&schema, "{ node { child { child { id } } } }")
+                .await
+                .as_deref(),
+            Some("QUERY_TOO_COMPLEX")
+        );
+        assert_eq!(
+            error_code(&schema, "{ node { id child { id } } b: node { id } }")
+                .await
+                .as_deref(),
+            Some("QUERY_TOO_COMPLEX")
+        );
+        assert_eq!(
+            error_code(
+                &schema,
+                "{ a: node { id } b: node { id } ...F } fragment F on Query { c: node { id } }"
+            )
+            .await
+            .as_deref(),
+            Some("QUERY_TOO_COMPLEX")
+        );
+
+        // Every spread of a fragment counts its aliases again
+        assert_eq!(
+            error_code(
+                &schema,
+                "{ ...F b: node { id } ... on Query { ...F } } fragment F on Query { a: node { id } }"
+            )
+            .await
+            .as_deref(),
+            Some("QUERY_TOO_COMPLEX")
+        );
+    }
+
+    #[test]
+    fn test_count_aliases_through_fragments() {
+        let count =
+            |query: &str| count_aliases(&async_graphql::parser::parse_query(query).unwrap());
+
+        assert_eq!(count("{ a: node { id } }"), 1);
+        assert_eq!(
+            count("{ ...F x: node { ...G } } fragment F on Query { a: node { ...G } } fragment G on Node { b: id c: id }"),
+            6
+        );
+        // Cyclic spreads terminate (validation rejects them afterwards)
+        assert_eq!(
+            count("{ ...F } fragment F on Query { a: node { id } ...F }"),
+            1
+        );
+    }
+}
@@ -0,0 +1,345 @@
+//! DataLoader support for SeaORM entities.
+//!
+//! Resolving `task.assignee` for a list of tasks normally runs one query per
+//! task. The loaders here collect every key requested while a query is
+//! executing and fetch them in a single `WHERE column IN (...)` query:
+//!
+//! - [`EntityLoader<E>`] loads entities by primary key (`task.assignee`)
+//! - [`RelatedLoader<E>`] loads all entities sharing a foreign key
+//!   (`user.tasks`)
+//!
+//! Loaders are created lazily per request by [`ContextData`], using the
+//! request's (tenant's) database connection, so nothing has to be
+//! registered when building the schema and results are never shared
+//! between requests or tenants.
+//!
+//! # Usage
+//!
+//! ```rust,ignore
+//! #[ComplexObject]
+//! impl Task {
+//!     async fn assignee(&self, ctx: &Context<'_>) -> Result<Option<User>> {
+//!         let data = ctx.data_unchecked::<ContextData>();
+//!         let user = data
+//!             .entity_loader::<users::Entity>()
+//!             .load_one(self.assignee_id)
+//!             .await?;
+//!         Ok(user.map(User::from))
+//!     }
+//! }
+//!
+//! #[ComplexObject]
+//! impl User {
+//!     async fn tasks(&self, ctx: &Context<'_>) -> Result<Vec<Task>> {
+//!         let data = ctx.data_unchecked::<ContextData>();
+//!         let tasks = data
+//!             .related_loader::<tasks::Entity>(tasks::Column::AssigneeId)
+//!             .load_one(self.id)
+//!             .await?
+//!             .unwrap_or_default();
+//!         Ok(tasks.into_iter().map(Task::from).collect())
+//!     }
+//! }
+//! ```
+//!
+//! [`ContextData`]: super::ContextData
+
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::marker::PhantomData;
+use std::sync::{Arc, Mutex};
+
+use async_graphql::dataloader::{DataLoader, HashMapCache, Loader};
+use sea_orm::sea_query::ValueType;
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, DbErr, EntityTrait, IdenStatic, Iterable, ModelTrait,
+    PrimaryKeyToColumn, QueryFilter, Value,
+};
+
+/// A per-request [`DataLoader`] for [`EntityLoader<E>`].
+pub type EntityDataLoader<E> = DataLoader<EntityLoader<E>, HashMapCache>;
+
+/// A per-request [`DataLoader`] for [`RelatedLoader<E>`].
+pub type RelatedDataLoader<E> = DataLoader<RelatedLoader<E>, HashMapCache>;
+
+/// Batch-loads entities by primary key.
+///
+/// Only entities with a single-column primary key are supported.
+pub struct EntityLoader<E: EntityTrait> {
+    db: DatabaseConnection,
+    _entity: PhantomData<E>,
+}
+
+impl<E: EntityTrait> EntityLoader<E> {
+    /// Create a loader using `db`.
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self {
+            db,
+            _entity: PhantomData,
+        }
+    }
+}
+
+impl<E, K> Loader<K> for EntityLoader<E>
+where
+    E: EntityTrait,
+    E::Model: Sync,
+    K: ValueType + Into<Value> + Hash + Eq + Clone + Send + Sync + 'static,
+{
+    type Value = E::Model;
+    type Error = Arc<DbErr>;
+
+    async fn load(&self, keys: &[K]) -> Result<HashMap<K, E::Model>, Self::Error> {
+        let column = E::PrimaryKey::iter()
+            .next()
+            .map(PrimaryKeyToColumn::into_column)
+            .ok_or_else(|| Arc::new(DbErr::Custom("Entity has no primary key".into())))?;
+
+        let rows = E::find()
+            .filter(column.is_in(keys.iter().cloned()))
+            .all(&self.db)
+            .await
+            .map_err(Arc::new)?;
+
+        rows.into_iter()
+            .map(|model| Ok((row_key::<E, K>(&model, column)?, model)))
+            .collect()
+    }
+}
+
+/// Batch-loads all entities whose `column` (usually a foreign key) matches
+/// a key. Keys without rows load as `None`.
+pub struct RelatedLoader<E: EntityTrait> {
+    db: DatabaseConnection,
+    column: E::Column,
+}
+
+impl<E: EntityTrait> RelatedLoader<E> {
+    /// Create a loader grouping rows of `E` by `column`.
+    pub fn new(db: DatabaseConnection, column: E::Column) -> Self {
+        Self { db, column }
+    }
+}
+
+impl<E, K> Loader<K> for RelatedLoader<E>
+where
+    E: EntityTrait,
+    E::Model: Sync,
+    K: ValueType + Into<Value> + Hash + Eq + Clone + Send + Sync + 'static,
+{
+    type Value = Vec<E::Model>;
+    type Error = Arc<DbErr>;
+
+    async fn load(&self, keys: &[K]) -> Result<HashMap<K, Vec<E::Model>>, Self::Error> {
+        let rows = E::find()
+            .filter(self.column.is_in(keys.iter().cloned()))
+            .all(&self.db)
+            .await
+            .map_err(Arc::new)?;
+
+        let mut grouped: HashMap<K, Vec<E::Model>> = HashMap::new();
+        for model in rows {
+            let key = row_key::<E, K>(&model, self.column)?;
+            grouped.entry(key).or_default().push(model);
+        }
+        Ok(grouped)
+    }
+}
+
+/// Read a loaded row's key from `column`.
+///
+/// Fails instead of dropping the row when the loader's key type does not
+/// match the column, e.g. `i32` keys for a `BIGINT` column.
+fn row_key<E, K>(model: &E::Model, column: E::Column) -> Result<K, Arc<DbErr>>
+where
+    E: EntityTrait,
+    K: ValueType,
+{
+    K::try_from(model.get(column)).map_err(|_| {
+        Arc::new(DbErr::Type(format!(
+            "Loader key type does not match column `{}`",
+            column.as_str()
+        )))
+    })
+}
+
+/// The loaders created for one request.
+///
+/// Held by [`ContextData`](super::ContextData); use
+/// [`ContextData::entity_loader`](super::ContextData::entity_loader) and
+/// [`ContextData::related_loader`](super::ContextData::related_loader)
+/// rather than this type directly.
+#[derive(Default)]
+pub struct Loaders {
+    loaders: Mutex<HashMap<(TypeId, String), Arc<dyn Any + Send + Sync>>>,
+}
+
+impl Loaders {
+    /// Get or create the [`EntityDataLoader`] for `E`.
+    pub fn entity<E>(&self, db: &DatabaseConnection) -> Arc<EntityDataLoader<E>>
+    where
+        E: EntityTrait,
+    {
+        self.get_or_insert((TypeId::of::<EntityLoader<E>>(), String::new()), || {
+            DataLoader::with_cache(
+                EntityLoader::new(db.clone()),
+                tokio::spawn,
+                HashMapCache::default(),
+            )
+        })
+    }
+
+    /// Get or create the [`RelatedDataLoader`] for `E` grouped by `column`.
+    pub fn related<E>(
+        &self,
+        db: &DatabaseConnection,
+        column: E::Column,
+    ) -> Arc<RelatedDataLoader<E>>
+    where
+        E: EntityTrait,
+    {
+        self.get_or_insert(
+            (TypeId::of::<RelatedLoader<E>>(), column.as_str().to_owned()),
+            || {
+                DataLoader::with_cache(
+                    RelatedLoader::new(db.clone(), column),
+                    tokio::spawn,
+                    HashMapCache::default(),
+                )
+            },
+        )
+    }
+
+    fn get_or_insert<T: Send + Sync + 'static>(
+        &self,
+        key: (TypeId, String),
+        create: impl FnOnce() -> T,
+    ) -> Arc<T> {
+        let mut loaders = self.loaders.lock().unwrap_or_else(|e| e.into_inner());
+        let loader = loaders
+            .entry(key)
+            .or_insert_with(|| Arc::new(create()))
+            .clone();
+        loader
+            .downcast::<T>()
+            .unwrap_or_else(|_| unreachable!("loader registered under a different type"))
+    }
+}
+
+impl std::fmt::Debug for Loaders {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Loaders").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::entity;
+    use crate::db::tests::{recording_db, row};
+    use std::collections::BTreeMap;
+
+    fn audit_row(id: i64, actor: &str) -> BTreeMap<String, Value> {
+        row([
+            ("id", id.into()),
+            ("occurred_at", chrono::Utc::now().into()),
+            ("actor", Some(actor.to_string()).into()),
+            ("tenant", Option::<String>::None.into()),
+            ("action", "test".into()),
+            ("target_type", Option::<String>::None.into()),
+            ("target_id", Option::<String>::None.into()),
+            ("changes", Option::<serde_json::Value>::None.into()),
+            ("metadata", Option::<serde_json::Value>::None.into()),
+            ("ip", Option::<String>::None.into()),
+            ("user_agent", Option::<String>::None.into()),
+        ])
+    }
+
+    #[test]
+    fn test_loaders_are_reused_per_request() {
+        let db = DatabaseConnection::Disconnected;
+        let loaders = Loaders::default();
+
+        let a = loaders.entity::<entity::Entity>(&db);
+        let b = loaders.entity::<entity::Entity>(&db);
+        assert!(Arc::ptr_eq(&a, &b));
+
+        // Keys are typed by the caller (never polled: the connection is fake)
+        drop(a.load_one(1i64));
+
+        let by_actor = loaders.related::<entity::Entity>(&db, entity::Column::Actor);
+        let by_tenant = loaders.related::<entity::Entity>(&db, entity::Column::Tenant);
+        assert!(!Arc::ptr_eq(&by_actor, &by_tenant));
+        drop(by_actor.load_one("42".to_string()));
+        assert!(Arc::ptr_eq(
+            &by_actor,
+            &loaders.related::<entity::Entity>(&db, entity::Column::Actor)
+        ));
+
+        // A fresh request gets fresh loaders
+        assert!(!Arc::ptr_eq(
+            &a,
+            &Loaders::default().entity::<entity::Entity>(&db)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_entity_loader_batches_keys_into_one_query() {
+        let (db, statements) =
+            recording_db(vec![vec![audit_row(1, "ada"), audit_row(2, "bob")]]).await;
+        let loaders = Loaders::default();
+        let loader = loaders.entity::<entity::Entity>(&db);
+
+        let (one, two, missing) = tokio::join!(
+            loader.load_one(1i64),
+            loader.load_one(2i64),
+            loader.load_one(3i64)
+        );
+        assert_eq!(one.unwrap().unwrap().actor.as_deref(), Some("ada"));
+        assert_eq!(two.unwrap().unwrap().actor.as_deref(), Some("bob"));
+        assert!(missing.unwrap().is_none());
+
+        // Cached keys are not fetched again
+        loader.load_one(1i64).await.unwrap();
+
+        let statements = statements.lock().unwrap();
+        assert_eq!(statements.len(), 1);
+        assert!(statements[0].contains(r#"WHERE "audit_log"."id" IN ($1, $2, $3)"#));
+    }
+
+    #[tokio::test]
+    async fn test_mismatched_key_type_is_an_error() {
+        let (db, _) = recording_db(vec![vec![audit_row(1, "ada")]]).await;
+        let loader = Loaders::default().entity::<entity::Entity>(&db);
+
+        // `audit_log.id` is a BIGINT, so `i32` keys cannot be read back
+        let err = loader.load_one(1i32).await.unwrap_err();
+        assert!(err.to_string().contains("does not match column `id`"));
+    }
+
+    #[tokio::test]
+    async fn test_related_loader_groups_rows_by_column() {
+        let (db, statements) = recording_db(vec![vec![
+            audit_row(1, "ada"),
+            audit_row(2, "ada"),
+            audit_row(3, "bob"),
+        ]])
+        .await;
+        let loaders = Loaders::default();
+        let loader = loaders.related::<entity::Entity>(&db, entity::Column::Actor);
+
+        let (ada, bob, eve) = tokio::join!(
+            loader.load_one(Some("ada".to_string())),
+            loader.load_one(Some("bob".to_string())),
+            loader.load_one(Some("eve".to_string()))
+        );
+        assert_eq!(ada.unwrap().unwrap().len(), 2);
+        assert_eq!(bob.unwrap().unwrap().len(), 1);
+        assert!(eve.unwrap().is_none());
+
+        let statements = statements.lock().unwrap();
+        assert_eq!(statements.len(), 1);
+        assert!(statements[0].contains(r#"WHERE "audit_log"."actor" IN ($1, $2, $3)"#));
+    }
+}
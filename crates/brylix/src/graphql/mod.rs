@@ -23,6 +23,22 @@
 
 mod context;
 mod guards;
+mod limits;
+
+/// Relay-style cursor pagination.
+pub mod cursor;
+
+/// Filter and sort inputs for list queries.
+pub mod filter;
+
+/// DataLoaders for batching entity lookups.
+pub mod loader;
+
+/// Automatic persisted queries and operation allowlisting.
+pub mod persisted;
+
+/// GraphQL subscriptions over API Gateway WebSocket APIs.
+pub mod subscriptions;
 
 /// GraphQL helper functions (ID parsing, etc.)
 pub mod helpers;
@@ -31,7 +47,13 @@ pub mod helpers;
 pub mod pagination;
 
 pub use context::ContextData;
-pub use guards::{require_auth, require_auth_user_id};
+pub use guards::{
+    auth, require_auth, require_auth_user_id, require_not_impersonating, require_permission,
+    AnyPerm, AuthRequirement, NotImpersonating, Perm, RequireAdmin, RequireAuth, RequireOwner,
+    RequirementGuard,
+};
+
+pub use limits::{QueryLimits, SchemaBuilderExt};
 
 #[cfg(feature = "multi-tenant")]
 pub use context::TenantInfo;
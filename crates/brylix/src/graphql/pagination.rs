@@ -2,6 +2,11 @@
 //!
 //! Provides generic pagination types that work with any entity type,
 //! following the connection pattern commonly used in GraphQL APIs.
+//! [`Connection<T>`] is a GraphQL object named after its item type
+//! (`Connection<User>` is exposed as `UserPage`), and [`paginate`] builds
+//! one straight from a SeaORM query.
+//!
+//! For cursor-based pagination see [`super::cursor`].
 //!
 //! # Usage
 //!
@@ -14,7 +19,15 @@
 //! assert_eq!(info.total_pages, 10);
 //! ```
 
-use async_graphql::SimpleObject;
+use std::borrow::Cow;
+
+use async_graphql::{Object, OutputType, SimpleObject, TypeName};
+use sea_orm::{ConnectionTrait, EntityTrait, PaginatorTrait, Select};
+
+use crate::errors::DomainResult;
+
+/// Largest page size [`paginate`] will return; larger values are clamped.
+pub const MAX_PER_PAGE: u64 = 100;
 
 /// Pagination metadata for a page of results.
 #[derive(Debug, Clone, SimpleObject)]
@@ -63,7 +76,9 @@ pub fn page_info(total_count: u64, page: u64, per_page: u64) -> PageInfo {
 
 /// A paginated connection wrapper for any item type.
 ///
-/// Use this to return paginated results from GraphQL resolvers.
+/// Use this to return paginated results from GraphQL resolvers. When `T`
+/// is a GraphQL output type, `Connection<T>` is exposed as `<T>Page` with
+/// `items`, `totalCount` and `pageInfo` fields.
 ///
 /// # Example
 ///
@@ -96,6 +111,83 @@ impl<T> Connection<T> {
             page_info: page_info(total_count, page, per_page),
         }
     }
+
+    /// Convert every item, keeping the counts and page info.
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> Connection<U> {
+        Connection {
+            items: self.items.into_iter().map(f).collect(),
+            total_count: self.total_count,
+            page_info: self.page_info,
+        }
+    }
+}
+
+#[Object(name_type)]
+impl<T: OutputType> Connection<T> {
+    /// The items for the current page
+    async fn items(&self) -> &Vec<T> {
+        &self.items
+    }
+
+    /// Total count of items across all pages
+    async fn total_count(&self) -> u64 {
+        self.total_count
+    }
+
+    /// Pagination metadata
+    async fn page_info(&self) -> &PageInfo {
+        &self.page_info
+    }
+}
+
+impl<T: OutputType> TypeName for Connection<T> {
+    fn type_name() -> Cow<'static, str> {
+        format!("{}Page", T::type_name()).into()
+    }
+}
+
+/// Fetch one page of a SeaORM query as a [`Connection`].
+///
+/// `page` is 1-based; values below 1 are treated as 1. `per_page` is
+/// clamped to `1..=`[`MAX_PER_PAGE`]. Rows are converted with `T::from`.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use brylix::graphql::pagination::{paginate, Connection};
+///
+/// async fn users(&self, ctx: &Context<'_>, page: u64, per_page: u64) -> Result<Connection<UserDto>> {
+///     let data = ctx.data_unchecked::<ContextData>();
+///     let select = users::Entity::find().order_by_asc(users::Column::Id);
+///     paginate(&data.db, select, page, per_page).await.map_err(gql_from_domain)
+/// }
+/// ```
+///
+/// # Errors
+///
+/// Returns `DatabaseError` if the count or page query fails
+pub async fn paginate<E, T>(
+    db: &impl ConnectionTrait,
+    select: Select<E>,
+    page: u64,
+    per_page: u64,
+) -> DomainResult<Connection<T>>
+where
+    E: EntityTrait,
+    E::Model: Sync,
+    T: From<E::Model>,
+{
+    let (page, per_page) = page_bounds(page, per_page);
+    let paginator = select.paginate(db, per_page);
+    let total_count = paginator.num_items().await?;
+    let items = paginator.fetch_page(page - 1).await?;
+
+    Ok(Connection::new(items, total_count, page, per_page).map(T::from))
+}
+
+/// Normalize a requested page to a 1-based page and a clamped page size.
+fn page_bounds(page: u64, per_page: u64) -> (u64, u64) {
+    (page.max(1), per_page.clamp(1, MAX_PER_PAGE))
 }
 
 /// Trait for converting paginated query results into a Connection.
@@ -203,4 +295,37 @@ mod tests {
         assert_eq!(conn.total_count, 30);
         assert!(conn.page_info.has_next_page);
     }
+
+    #[test]
+    fn test_page_bounds_clamped() {
+        assert_eq!(page_bounds(0, 0), (1, 1));
+        assert_eq!(page_bounds(3, 25), (3, 25));
+        assert_eq!(page_bounds(2, 10_000), (2, MAX_PER_PAGE));
+    }
+
+    #[tokio::test]
+    async fn test_connection_schema() {
+        use async_graphql::{EmptyMutation, EmptySubscription, Schema};
+
+        struct Query;
+
+        #[Object]
+        impl Query {
+            async fn numbers(&self) -> Connection<i32> {
+                Connection::new(vec![1u8, 2, 3], 30, 1, 3).map(i32::from)
+            }
+        }
+
+        let schema = Schema::build(Query, EmptyMutation, EmptySubscription).finish();
+        assert!(schema.sdl().contains("type IntPage"));
+
+        let res = schema
+            .execute("{ numbers { items totalCount pageInfo { hasNextPage totalPages } } }")
+            .await;
+        assert!(res.errors.is_empty(), "{:?}", res.errors);
+        let json = res.data.into_json().unwrap();
+        assert_eq!(json["numbers"]["items"], serde_json::json!([1, 2, 3]));
+        assert_eq!(json["numbers"]["totalCount"], 30);
+        assert_eq!(json["numbers"]["pageInfo"]["totalPages"], 10);
+    }
 }
@@ -0,0 +1,671 @@
+//! Automatic persisted queries (APQ) and operation allowlisting.
+//!
+//! With APQ, clients send the SHA-256 hash of a query in
+//! `extensions.persistedQuery` instead of the full query text. Unknown
+//! hashes are answered with `PERSISTED_QUERY_NOT_FOUND`, the client retries
+//! once with the full query, and the server remembers it in a
+//! [`PersistedQueryStore`]. This is the protocol implemented by Apollo
+//! Client, Relay and most mobile GraphQL clients.
+//!
+//! In strict (allowlist) mode the server only executes operations listed
+//! in an [`OperationManifest`] generated at build time; any other query is
+//! rejected with `OPERATION_NOT_ALLOWED`.
+//!
+//! APQ lets any client register queries: only queries that pass validation
+//! are stored, and the stores are capped, but production APIs with known
+//! clients should use allowlist mode.
+//!
+//! # Usage
+//!
+//! ```rust,ignore
+//! use brylix::graphql::persisted::{OperationManifest, PersistedQueries, SeaOrmPersistedQueryStore};
+//!
+//! // APQ: remember queries sent by clients
+//! let schema = Schema::build(Query, Mutation, EmptySubscription)
+//!     .extension(PersistedQueries::new(SeaOrmPersistedQueryStore::new(db.clone())))
+//!     .finish();
+//!
+//! // Strict mode: only operations from the build-time manifest
+//! let manifest = OperationManifest::from_json(include_str!("../persisted-query-manifest.json"))?;
+//! let schema = Schema::build(Query, Mutation, EmptySubscription)
+//!     .extension(PersistedQueries::allowlist(manifest))
+//!     .finish();
+//! ```
+
+use std::any::TypeId;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+use async_graphql::extensions::{
+    Extension, ExtensionContext, ExtensionFactory, NextPrepareRequest, NextValidation,
+};
+use async_graphql::{Request, ServerError, ServerResult, ValidationResult};
+use chrono::Utc;
+use sea_orm::{DatabaseConnection, EntityTrait, PaginatorTrait, Set, SqlErr};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use crate::errors::{DomainError, DomainResult};
+use crate::handler::{is_mutation, GetRequest};
+
+/// Error code for a hash the server does not know; clients retry with the query.
+pub const PERSISTED_QUERY_NOT_FOUND: &str = "PERSISTED_QUERY_NOT_FOUND";
+
+/// Error code for a query that is not in the allowlist.
+pub const OPERATION_NOT_ALLOWED: &str = "OPERATION_NOT_ALLOWED";
+
+/// Hex-encoded SHA-256 hash of a query, as used in `persistedQuery.sha256Hash`.
+pub fn query_hash(query: &str) -> String {
+    Sha256::digest(query.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Storage for queries registered through APQ.
+#[async_trait::async_trait]
+pub trait PersistedQueryStore: Send + Sync {
+    /// Look up a query by its hash.
+    async fn get(&self, hash: &str) -> DomainResult<Option<String>>;
+
+    /// Remember a query under its hash. Storing a known hash again is not an error.
+    async fn put(&self, hash: &str, query: &str) -> DomainResult<()>;
+}
+
+/// In-process query store.
+///
+/// Holds at most `capacity` queries (default 1000); once full, new queries
+/// are still executed but not remembered. Queries are lost on cold start
+/// and not shared between Lambda instances; use
+/// [`SeaOrmPersistedQueryStore`] to share them.
+#[derive(Debug)]
+pub struct MemoryPersistedQueryStore {
+    queries: Mutex<HashMap<String, String>>,
+    capacity: usize,
+}
+
+impl Default for MemoryPersistedQueryStore {
+    fn default() -> Self {
+        Self::with_capacity(1000)
+    }
+}
+
+impl MemoryPersistedQueryStore {
+    /// Create an empty store holding up to 1000 queries.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create an empty store holding up to `capacity` queries.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            queries: Mutex::new(HashMap::new()),
+            capacity,
+        }
+    }
+
+    fn lock(&self) -> DomainResult<std::sync::MutexGuard<'_, HashMap<String, String>>> {
+        self.queries
+            .lock()
+            .map_err(|_| DomainError::Internal("Persisted query store lock poisoned".into()))
+    }
+}
+
+#[async_trait::async_trait]
+impl PersistedQueryStore for MemoryPersistedQueryStore {
+    async fn get(&self, hash: &str) -> DomainResult<Option<String>> {
+        Ok(self.lock()?.get(hash).cloned())
+    }
+
+    async fn put(&self, hash: &str, query: &str) -> DomainResult<()> {
+        let mut queries = self.lock()?;
+        if queries.len() < self.capacity {
+            queries.insert(hash.to_string(), query.to_string());
+        }
+        Ok(())
+    }
+}
+
+/// SeaORM-backed query store using the `persisted_queries` table.
+///
+/// Holds at most `capacity` queries (default 10000); once full, new queries
+/// are still executed but not remembered. Add [`migration::Migration`] to
+/// your migrator to create the table.
+#[derive(Debug, Clone)]
+pub struct SeaOrmPersistedQueryStore {
+    db: DatabaseConnection,
+    capacity: u64,
+}
+
+impl SeaOrmPersistedQueryStore {
+    /// Create a store using the given connection, holding up to 10000 queries.
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self::with_capacity(db, 10_000)
+    }
+
+    /// Create a store using the given connection, holding up to `capacity`
+    /// queries.
+    pub fn with_capacity(db: DatabaseConnection, capacity: u64) -> Self {
+        Self { db, capacity }
+    }
+}
+
+#[async_trait::async_trait]
+impl PersistedQueryStore for SeaOrmPersistedQueryStore {
+    async fn get(&self, hash: &str) -> DomainResult<Option<String>> {
+        Ok(entity::Entity::find_by_id(hash.to_string())
+            .one(&self.db)
+            .await?
+            .map(|m| m.query))
+    }
+
+    async fn put(&self, hash: &str, query: &str) -> DomainResult<()> {
+        if entity::Entity::find().count(&self.db).await? >= self.capacity {
+            tracing::debug!("Persisted query store is full");
+            return Ok(());
+        }
+        let model = entity::ActiveModel {
+            hash: Set(hash.to_string()),
+            query: Set(query.to_string()),
+            created_at: Set(Utc::now()),
+        };
+        match entity::Entity::insert(model)
+            .exec_without_returning(&self.db)
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(e) if matches!(e.sql_err(), Some(SqlErr::UniqueConstraintViolation(_))) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+/// The operations a client is allowed to run in strict mode.
+#[derive(Debug, Clone, Default)]
+pub struct OperationManifest {
+    /// Query text by operation ID
+    operations: HashMap<String, String>,
+    /// SHA-256 hashes of every allowed query text
+    hashes: HashSet<String>,
+}
+
+#[derive(Deserialize)]
+struct ApolloManifest {
+    operations: Vec<ApolloOperation>,
+}
+
+#[derive(Deserialize)]
+struct ApolloOperation {
+    id: String,
+    body: String,
+}
+
+impl OperationManifest {
+    /// Build a manifest from query texts, identified by their hashes.
+    pub fn from_queries<I, Q>(queries: I) -> Self
+    where
+        I: IntoIterator<Item = Q>,
+        Q: Into<String>,
+    {
+        let mut manifest = Self::default();
+        for query in queries {
+            let query = query.into();
+            manifest.insert(query_hash(&query), query);
+        }
+        manifest
+    }
+
+    /// Parse a persisted query manifest.
+    ///
+    /// Accepts the Apollo format
+    /// (`{"operations": [{"id": "...", "body": "..."}]}`, as written by
+    /// `generate-persisted-query-manifest`) or a plain `{"<id>": "<query>"}`
+    /// object.
+    ///
+    /// # Errors
+    ///
+    /// Returns `InvalidInput` if the JSON matches neither format
+    pub fn from_json(json: &str) -> DomainResult<Self> {
+        let invalid = |e: serde_json::Error| {
+            DomainError::InvalidInput(format!("Invalid persisted query manifest: {e}"))
+        };
+
+        let value: serde_json::Value = serde_json::from_str(json).map_err(invalid)?;
+        let mut manifest = Self::default();
+        if value.get("operations").is_some() {
+            let apollo: ApolloManifest = serde_json::from_value(value).map_err(invalid)?;
+            for op in apollo.operations {
+                manifest.insert(op.id, op.body);
+            }
+        } else {
+            let map: HashMap<String, String> = serde_json::from_value(value).map_err(invalid)?;
+            for (id, body) in map {
+                manifest.insert(id, body);
+            }
+        }
+        Ok(manifest)
+    }
+
+    fn insert(&mut self, id: String, query: String) {
+        self.hashes.insert(query_hash(&query));
+        self.operations.insert(id, query);
+    }
+
+    /// Look up an operation by ID.
+    pub fn get(&self, id: &str) -> Option<&str> {
+        self.operations.get(id).map(String::as_str)
+    }
+
+    /// Check whether a query text is allowed.
+    pub fn allows(&self, query: &str) -> bool {
+        self.hashes.contains(&query_hash(query))
+    }
+
+    /// Number of operations in the manifest.
+    pub fn len(&self) -> usize {
+        self.operations.len()
+    }
+
+    /// Whether the manifest has no operations.
+    pub fn is_empty(&self) -> bool {
+        self.operations.is_empty()
+    }
+}
+
+/// Schema extension implementing APQ or strict allowlisting.
+#[derive(Clone)]
+pub struct PersistedQueries {
+    mode: Mode,
+}
+
+#[derive(Clone)]
+enum Mode {
+    Automatic(Arc<dyn PersistedQueryStore>),
+    Allowlist(Arc<OperationManifest>),
+}
+
+impl PersistedQueries {
+    /// Automatic persisted queries: accept any query and remember it in `store`.
+    pub fn new(store: impl PersistedQueryStore + 'static) -> Self {
+        Self {
+            mode: Mode::Automatic(Arc::new(store)),
+        }
+    }
+
+    /// Strict mode: only run operations from `manifest`.
+    pub fn allowlist(manifest: OperationManifest) -> Self {
+        Self {
+            mode: Mode::Allowlist(Arc::new(manifest)),
+        }
+    }
+}
+
+impl ExtensionFactory for PersistedQueries {
+    fn create(&self) -> Arc<dyn Extension> {
+        Arc::new(PersistedQueriesExtension {
+            mode: self.mode.clone(),
+            register: Mutex::new(None),
+        })
+    }
+}
+
+struct PersistedQueriesExtension {
+    mode: Mode,
+    /// Hash and query to store once the query passes validation
+    register: Mutex<Option<(String, String)>>,
+}
+
+#[derive(Deserialize)]
+struct PersistedQuery {
+    version: i32,
+    #[serde(rename = "sha256Hash")]
+    sha256_hash: String,
+}
+
+#[async_trait::async_trait]
+impl Extension for PersistedQueriesExtension {
+    async fn prepare_request(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        mut request: Request,
+        next: NextPrepareRequest<'_>,
+    ) -> ServerResult<Request> {
+        let persisted = match request.extensions.remove("persistedQuery") {
+            Some(value) => {
+                let persisted: PersistedQuery = async_graphql::from_value(value).map_err(|_| {
+                    request_error("BAD_REQUEST", "Invalid persistedQuery extension")
+                })?;
+                if persisted.version != 1 {
+                    return Err(request_error(
+                        "BAD_REQUEST",
+                        "Unsupported persistedQuery version",
+                    ));
+                }
+                Some(persisted.sha256_hash)
+            }
+            None => None,
+        };
+
+        if let Some(hash) = &persisted {
+            if !request.query.is_empty() && query_hash(&request.query) != *hash {
+                return Err(request_error(
+                    "BAD_REQUEST",
+                    "provided sha does not match query",
+                ));
+            }
+        }
+
+        match (&self.mode, persisted) {
+            (Mode::Automatic(store), Some(hash)) if request.query.is_empty() => {
+                match store.get(&hash).await {
+                    Ok(Some(query)) => request.query = query,
+                    Ok(None) => {
+                        return Err(request_error(
+                            PERSISTED_QUERY_NOT_FOUND,
+                            "PersistedQueryNotFound",
+                        ))
+                    }
+                    Err(e) => return Err(request_error(e.code(), e.to_string())),
+                }
+            }
+            (Mode::Automatic(_), Some(hash)) => {
+                if let Ok(mut register) = self.register.lock() {
+                    *register = Some((hash, request.query.clone()));
+                }
+            }
+            (Mode::Automatic(_), None) => {}
+            (Mode::Allowlist(manifest), Some(hash)) if request.query.is_empty() => {
+                match manifest.get(&hash) {
+                    Some(query) => request.query = query.to_string(),
+                    None => return Err(not_allowed()),
+                }
+            }
+            (Mode::Allowlist(manifest), _) => {
+                if !manifest.allows(&request.query) {
+                    return Err(not_allowed());
+                }
+            }
+        }
+
+        if request.data.contains_key(&TypeId::of::<GetRequest>())
+            && is_mutation(&request.query, request.operation_name.as_deref())
+        {
+            return Err(request_error(
+                "BAD_REQUEST",
+                "Mutations are not allowed over GET",
+            ));
+        }
+
+        next.run(ctx, request).await
+    }
+
+    async fn validation(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        next: NextValidation<'_>,
+    ) -> Result<ValidationResult, Vec<ServerError>> {
+        let result = next.run(ctx).await?;
+        let register = self.register.lock().ok().and_then(|mut r| r.take());
+        if let (Mode::Automatic(store), Some((hash, query))) = (&self.mode, register) {
+            if let Err(e) = store.put(&hash, &query).await {
+                tracing::warn!(error = %e, "Failed to store persisted query");
+            }
+        }
+        Ok(result)
+    }
+}
+
+fn not_allowed() -> ServerError {
+    request_error(OPERATION_NOT_ALLOWED, "Operation is not in the allowlist")
+}
+
+fn request_error(code: &'static str, message: impl Into<String>) -> ServerError {
+    let mut err = ServerError::new(message, None);
+    err.extensions
+        .get_or_insert_with(Default::default)
+        .set("code", code);
+    err
+}
+
+/// SeaORM entity for the `persisted_queries` table.
+pub mod entity {
+    use sea_orm::entity::prelude::*;
+
+    /// A query registered through APQ.
+    #[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+    #[sea_orm(table_name = "persisted_queries")]
+    pub struct Model {
+        /// SHA-256 hash of the query (hex)
+        #[sea_orm(primary_key, auto_increment = false)]
+        pub hash: String,
+        /// Query text
+        #[sea_orm(column_type = "Text")]
+        pub query: String,
+        /// When the query was first registered
+        pub created_at: DateTimeUtc,
+    }
+
+    /// No relations.
+    #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+    pub enum Relation {}
+
+    impl ActiveModelBehavior for ActiveModel {}
+}
+
+/// Migration creating the `persisted_queries` table.
+pub mod migration {
+    use sea_orm_migration::prelude::*;
+
+    /// Creates the `persisted_queries` table.
+    pub struct Migration;
+
+    impl MigrationName for Migration {
+        fn name(&self) -> &str {
+            "m20261018_000008_create_persisted_queries_table"
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl MigrationTrait for Migration {
+        async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+            manager
+                .create_table(
+                    Table::create()
+                        .table(PersistedQueries::Table)
+                        .if_not_exists()
+                        .col(
+                            ColumnDef::new(PersistedQueries::Hash)
+                                .string_len(64)
+                                .not_null()
+                                .primary_key(),
+                        )
+                        .col(ColumnDef::new(PersistedQueries::Query).text().not_null())
+                        .col(
+                            ColumnDef::new(PersistedQueries::CreatedAt)
+                                .timestamp()
+                                .not_null(),
+                        )
+                        .to_owned(),
+                )
+                .await
+        }
+
+        async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+            manager
+                .drop_table(Table::drop().table(PersistedQueries::Table).to_owned())
+                .await
+        }
+    }
+
+    #[derive(Iden)]
+    enum PersistedQueries {
+        Table,
+        Hash,
+        Query,
+        CreatedAt,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_graphql::{EmptyMutation, EmptySubscription, Object, Schema};
+    use serde_json::json;
+
+    const HELLO: &str = "query Hello { hello }";
+    const HELLO_HASH: &str = "3f710a83decac3d21ddeae7bd265d8c5a48749226d23327b5dfd7031f406a987";
+
+    struct Query;
+
+    #[Object]
+    impl Query {
+        async fn hello(&self) -> &str {
+            "world"
+        }
+    }
+
+    fn schema(extension: PersistedQueries) -> Schema<Query, EmptyMutation, EmptySubscription> {
+        Schema::build(Query, EmptyMutation, EmptySubscription)
+            .extension(extension)
+            .finish()
+    }
+
+    fn request(query: &str, hash: Option<&str>) -> Request {
+        let mut request = Request::new(query);
+        if let Some(hash) = hash {
+            request.extensions.insert(
+                "persistedQuery".into(),
+                async_graphql::Value::from_json(json!({ "version": 1, "sha256Hash": hash }))
+                    .unwrap(),
+            );
+        }
+        request
+    }
+
+    async fn run(
+        schema: &Schema<Query, EmptyMutation, EmptySubscription>,
+        request: Request,
+    ) -> Result<serde_json::Value, String> {
+        let res = schema.execute(request).await;
+        match res.errors.first() {
+            Some(err) => Err(err
+                .extensions
+                .as_ref()
+                .and_then(|e| e.get("code"))
+                .map(|c| c.to_string().trim_matches('"').to_string())
+                .unwrap_or_default()),
+            None => Ok(res.data.into_json().unwrap()),
+        }
+    }
+
+    #[test]
+    fn test_query_hash() {
+        assert_eq!(query_hash(HELLO), HELLO_HASH);
+    }
+
+    #[tokio::test]
+    async fn test_automatic_persisted_queries() {
+        let schema = schema(PersistedQueries::new(MemoryPersistedQueryStore::new()));
+
+        assert_eq!(
+            run(&schema, request("", Some(HELLO_HASH))).await,
+            Err(PERSISTED_QUERY_NOT_FOUND.to_string())
+        );
+        assert_eq!(
+            run(&schema, request("{ hello }", Some(HELLO_HASH))).await,
+            Err("BAD_REQUEST".to_string())
+        );
+        assert_eq!(
+            run(&schema, request(HELLO, Some(HELLO_HASH))).await,
+            Ok(json!({ "hello": "world" }))
+        );
+        assert_eq!(
+            run(&schema, request("", Some(HELLO_HASH))).await,
+            Ok(json!({ "hello": "world" }))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_invalid_queries_are_not_stored() {
+        let schema = schema(PersistedQueries::new(MemoryPersistedQueryStore::new()));
+        let invalid = "{ nope }";
+        let hash = query_hash(invalid);
+
+        assert!(run(&schema, request(invalid, Some(&hash))).await.is_err());
+        assert_eq!(
+            run(&schema, request("", Some(&hash))).await,
+            Err(PERSISTED_QUERY_NOT_FOUND.to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sea_orm_store_stops_at_capacity() {
+        use crate::db::tests::{recording_db, row};
+
+        let (db, statements) = recording_db(vec![vec![row([("num_items", 2i64.into())])]]).await;
+        let store = SeaOrmPersistedQueryStore::with_capacity(db, 2);
+
+        store.put(HELLO_HASH, HELLO).await.unwrap();
+        let statements = statements.lock().unwrap();
+        assert_eq!(statements.len(), 1);
+        assert!(statements[0].contains("COUNT(*)"));
+    }
+
+    #[tokio::test]
+    async fn test_allowlist_rejects_unknown_operations() {
+        let manifest = OperationManifest::from_json(
+            &json!({
+                "format": "apollo-persisted-query-manifest",
+                "version": 1,
+                "operations": [{ "id": HELLO_HASH, "name": "Hello", "type": "query", "body": HELLO }]
+            })
+            .to_string(),
+        )
+        .unwrap();
+        assert_eq!(manifest.len(), 1);
+        let schema = schema(PersistedQueries::allowlist(manifest));
+
+        assert_eq!(
+            run(&schema, request("", Some(HELLO_HASH))).await,
+            Ok(json!({ "hello": "world" }))
+        );
+        assert_eq!(
+            run(&schema, request(HELLO, None)).await,
+            Ok(json!({ "hello": "world" }))
+        );
+        assert_eq!(
+            run(&schema, request("{ hello }", None)).await,
+            Err(OPERATION_NOT_ALLOWED.to_string())
+        );
+        assert_eq!(
+            run(&schema, request("", Some(&query_hash("{ hello }")))).await,
+            Err(OPERATION_NOT_ALLOWED.to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_persisted_mutation_rejected_over_get() {
+        let store = MemoryPersistedQueryStore::new();
+        let mutation = "mutation Wipe { wipe }";
+        let hash = query_hash(mutation);
+        store.put(&hash, mutation).await.unwrap();
+        let schema = schema(PersistedQueries::new(store));
+
+        assert_eq!(
+            run(&schema, request("", Some(&hash)).data(GetRequest)).await,
+            Err("BAD_REQUEST".to_string())
+        );
+    }
+
+    #[test]
+    fn test_manifest_formats() {
+        let manifest =
+            OperationManifest::from_json(&json!({ "hello-v1": HELLO }).to_string()).unwrap();
+        assert_eq!(manifest.get("hello-v1"), Some(HELLO));
+        assert!(manifest.allows(HELLO));
+        assert!(OperationManifest::from_json("[1, 2]").is_err());
+
+        let manifest = OperationManifest::from_queries([HELLO]);
+        assert_eq!(manifest.get(HELLO_HASH), Some(HELLO));
+    }
+}
@@ -0,0 +1,1548 @@
+//! GraphQL subscriptions over API Gateway WebSocket APIs.
+//!
+//! A Lambda function cannot hold a subscription stream open, so
+//! subscriptions are stored instead of executed: [`WebSocketHandler`]
+//! handles the `$connect`, `$disconnect` and `$default` routes of a
+//! WebSocket API, speaks the `graphql-transport-ws` protocol, and records
+//! connections and subscription documents in a [`SubscriptionStore`].
+//! When something happens, [`WebSocketHandler::publish`] evaluates every
+//! subscription on the topic against the event and posts the results back
+//! through a [`ConnectionManager`] (the API Gateway management API in
+//! production, [`MemoryConnectionManager`] in tests).
+//!
+//! The topic of a subscription is the name of its root field. Subscription
+//! resolvers read the published event with [`subscription_event`] and may
+//! filter it using their arguments:
+//!
+//! ```rust,ignore
+//! #[Subscription]
+//! impl Subscription {
+//!     async fn task_updated(&self, ctx: &Context<'_>, project_id: i64) -> impl Stream<Item = Task> {
+//!         subscription_event::<Task>(ctx).filter(move |t| ready(t.project_id == project_id))
+//!     }
+//! }
+//! ```
+//!
+//! Resolvers must only yield published events: subscriptions are also
+//! dry-run without an event when they are registered, to report invalid
+//! documents and authorization errors early.
+//!
+//! Every operation runs with a [`ContextData`] built for the connection's
+//! identity: the `connection_init` handler authenticates the client and
+//! returns a [`JwtResult`], of which only the user ID, tenant and role
+//! name are stored, and the context factory turns that identity back into
+//! a `ContextData` for the dry run, for each published event and for
+//! queries and mutations sent over the socket. Tokens themselves are never
+//! stored.
+//!
+//! # Usage
+//!
+//! ```rust,ignore
+//! use brylix::graphql::subscriptions::{SeaOrmSubscriptionStore, WebSocketHandler};
+//!
+//! // WebSocket Lambda
+//! let handler = WebSocketHandler::new(
+//!     schema,
+//!     Arc::new(SeaOrmSubscriptionStore::new(db.clone())),
+//!     Arc::new(ApiGatewayConnections::new(&endpoint)), // your ConnectionManager
+//!     move |identity: JwtResult| {
+//!         let db = db.clone();
+//!         async move { Ok(ContextData::single_tenant(db, identity.user_id, identity.role)) }
+//!     },
+//! )
+//! .with_connection_init(|payload| async move {
+//!     let token = payload
+//!         .as_ref()
+//!         .and_then(|p| p.get("token"))
+//!         .and_then(|t| t.as_str())
+//!         .ok_or(DomainError::Unauthorized)?;
+//!     authenticate_token(token).await.map_err(|_| DomainError::Unauthorized)
+//! });
+//! lambda_runtime::run(service_fn(|event: LambdaEvent<ApiGatewayWebsocketProxyRequest>| async {
+//!     Ok::<_, Error>(handler.handle(event.payload).await)
+//! }))
+//! .await?;
+//!
+//! // Anywhere an event happens (mutation, queue consumer, ...)
+//! handler.publish("taskUpdated", &task).await?;
+//! ```
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+
+use async_graphql::parser::parse_query;
+use async_graphql::parser::types::{
+    DocumentOperations, ExecutableDocument, OperationType, Selection, SelectionSet,
+};
+use async_graphql::{Context, Executor, Request, Response, ServerError, Variables};
+use chrono::{DateTime, Utc};
+use futures::future::BoxFuture;
+use futures::{Future, Stream, StreamExt};
+use lambda_http::aws_lambda_events::apigw::{
+    ApiGatewayProxyResponse, ApiGatewayWebsocketProxyRequest,
+};
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set, SqlErr};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::auth::roles::AuthRole;
+use crate::auth::JwtResult;
+use crate::errors::{gql_from_domain, DomainError, DomainResult};
+use crate::graphql::ContextData;
+
+/// WebSocket subprotocol spoken by [`WebSocketHandler`].
+pub const PROTOCOL: &str = "graphql-transport-ws";
+
+/// An open WebSocket connection.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WsConnection {
+    /// API Gateway connection ID
+    pub connection_id: String,
+    /// When the client connected
+    pub connected_at: DateTime<Utc>,
+    /// Whether `connection_init` has been accepted
+    pub acknowledged: bool,
+    /// Authenticated user ID
+    pub user_id: Option<String>,
+    /// Tenant of the authenticated user
+    pub tenant: Option<String>,
+    /// Role name of the authenticated user
+    pub role: Option<String>,
+}
+
+impl WsConnection {
+    /// Rebuild the identity accepted by `connection_init`.
+    ///
+    /// Only the user ID, tenant and role are kept: API key roles come back
+    /// as [`AuthRole::Custom`] and scopes are empty.
+    pub fn identity(&self) -> JwtResult {
+        let role = self.role.as_deref().and_then(|name| {
+            let id = self.user_id.as_deref()?.parse().ok()?;
+            Some(AuthRole::from_name(name, id))
+        });
+        JwtResult {
+            user_id: self.user_id.clone(),
+            tenant: self.tenant.clone(),
+            role,
+            ..JwtResult::empty()
+        }
+    }
+}
+
+/// A registered subscription.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WsSubscription {
+    /// Connection the subscription belongs to
+    pub connection_id: String,
+    /// Operation ID chosen by the client, unique per connection
+    pub id: String,
+    /// Root field name of the subscription
+    pub topic: String,
+    /// Subscription document
+    pub query: String,
+    /// Variables (JSON object)
+    pub variables: Value,
+    /// Operation name, if the document has several operations
+    pub operation_name: Option<String>,
+    /// When the subscription was registered
+    pub created_at: DateTime<Utc>,
+}
+
+/// A published event, available to subscription resolvers as request
+/// data. Read it with [`subscription_event`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubscriptionEvent {
+    /// Topic (root field name) the event was published to
+    pub topic: String,
+    /// Event payload
+    pub payload: Value,
+}
+
+/// Stream the published event, deserialized as `T`.
+///
+/// Yields nothing when no event is available (while a subscription is
+/// being registered) or the payload is not a `T`.
+pub fn subscription_event<T>(ctx: &Context<'_>) -> impl Stream<Item = T> + Send + 'static
+where
+    T: DeserializeOwned + Send + 'static,
+{
+    let item = ctx.data_opt::<SubscriptionEvent>().and_then(|event| {
+        match serde_json::from_value(event.payload.clone()) {
+            Ok(item) => Some(item),
+            Err(e) => {
+                tracing::warn!(topic = %event.topic, error = %e, "Invalid subscription event payload");
+                None
+            }
+        }
+    });
+    futures::stream::iter(item)
+}
+
+/// Storage for connections and subscriptions.
+#[async_trait::async_trait]
+pub trait SubscriptionStore: Send + Sync {
+    /// Record a new connection.
+    async fn add_connection(&self, connection: WsConnection) -> DomainResult<()>;
+
+    /// Find a connection by ID.
+    async fn find_connection(&self, connection_id: &str) -> DomainResult<Option<WsConnection>>;
+
+    /// Mark a connection as initialized, storing the user ID, tenant and
+    /// role of `identity`.
+    async fn acknowledge(&self, connection_id: &str, identity: &JwtResult) -> DomainResult<()>;
+
+    /// Remove a connection and all of its subscriptions.
+    async fn remove_connection(&self, connection_id: &str) -> DomainResult<()>;
+
+    /// Register a subscription. Returns `false` if the connection already
+    /// has a subscription with the same ID.
+    async fn add_subscription(&self, subscription: WsSubscription) -> DomainResult<bool>;
+
+    /// Remove a subscription.
+    async fn remove_subscription(&self, connection_id: &str, id: &str) -> DomainResult<()>;
+
+    /// List the subscriptions on a topic.
+    async fn subscriptions_for_topic(&self, topic: &str) -> DomainResult<Vec<WsSubscription>>;
+}
+
+/// Sends messages to connected clients.
+///
+/// In production, implement this with the API Gateway management API
+/// (`PostToConnection` / `DeleteConnection`).
+#[async_trait::async_trait]
+pub trait ConnectionManager: Send + Sync {
+    /// Send a text message. Returns `false` if the connection no longer
+    /// exists (HTTP 410 Gone).
+    async fn post(&self, connection_id: &str, message: &str) -> DomainResult<bool>;
+
+    /// Close a connection.
+    async fn close(&self, connection_id: &str) -> DomainResult<()>;
+}
+
+/// Authenticates a connection from its `connection_init` payload.
+pub type ConnectionInitHandler =
+    Arc<dyn Fn(Option<Value>) -> BoxFuture<'static, DomainResult<JwtResult>> + Send + Sync>;
+
+/// Builds the resolver context for a connection's identity.
+pub type ContextFactory =
+    Arc<dyn Fn(JwtResult) -> BoxFuture<'static, DomainResult<ContextData>> + Send + Sync>;
+
+/// Messages sent by the client.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ClientMessage {
+    ConnectionInit {
+        #[serde(default)]
+        payload: Option<Value>,
+    },
+    Ping {},
+    Pong {},
+    Subscribe {
+        id: String,
+        payload: Box<Request>,
+    },
+    Complete {
+        id: String,
+    },
+}
+
+/// Messages sent to the client.
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ServerMessage<'a> {
+    ConnectionAck,
+    Pong,
+    Next {
+        id: &'a str,
+        payload: &'a Response,
+    },
+    Error {
+        id: &'a str,
+        payload: &'a [ServerError],
+    },
+    Complete {
+        id: &'a str,
+    },
+}
+
+/// Lambda handler for an API Gateway WebSocket API serving GraphQL
+/// subscriptions.
+pub struct WebSocketHandler<E> {
+    executor: E,
+    store: Arc<dyn SubscriptionStore>,
+    connections: Arc<dyn ConnectionManager>,
+    context: ContextFactory,
+    on_init: Option<ConnectionInitHandler>,
+}
+
+impl<E: Executor> WebSocketHandler<E> {
+    /// Create a handler executing operations with `executor` (usually the
+    /// schema), in the context `context` builds for each connection's
+    /// identity.
+    ///
+    /// Without [`with_connection_init`](Self::with_connection_init) every
+    /// connection is anonymous.
+    pub fn new<F, Fut>(
+        executor: E,
+        store: Arc<dyn SubscriptionStore>,
+        connections: Arc<dyn ConnectionManager>,
+        context: F,
+    ) -> Self
+    where
+        F: Fn(JwtResult) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = DomainResult<ContextData>> + Send + 'static,
+    {
+        Self {
+            executor,
+            store,
+            connections,
+            context: Arc::new(move |identity| Box::pin(context(identity))),
+            on_init: None,
+        }
+    }
+
+    /// Authenticate connections from their `connection_init` payload.
+    /// Connections whose payload is rejected are closed.
+    pub fn with_connection_init<F, Fut>(mut self, handler: F) -> Self
+    where
+        F: Fn(Option<Value>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = DomainResult<JwtResult>> + Send + 'static,
+    {
+        self.on_init = Some(Arc::new(move |payload| Box::pin(handler(payload))));
+        self
+    }
+
+    /// Handle a `$connect`, `$disconnect` or `$default` route event.
+    pub async fn handle(&self, event: ApiGatewayWebsocketProxyRequest) -> ApiGatewayProxyResponse {
+        let context = &event.request_context;
+        let Some(connection_id) = context.connection_id.as_deref() else {
+            return ws_response(400);
+        };
+
+        let result = match context.event_type.as_deref() {
+            Some("CONNECT") => {
+                let connection = WsConnection {
+                    connection_id: connection_id.to_string(),
+                    connected_at: DateTime::from_timestamp_millis(context.connected_at)
+                        .unwrap_or_else(Utc::now),
+                    acknowledged: false,
+                    user_id: None,
+                    tenant: None,
+                    role: None,
+                };
+                self.store.add_connection(connection).await
+            }
+            Some("DISCONNECT") => self.store.remove_connection(connection_id).await,
+            _ => {
+                self.on_message(connection_id, event.body.as_deref().unwrap_or_default())
+                    .await
+            }
+        };
+
+        match result {
+            Ok(()) if context.event_type.as_deref() == Some("CONNECT") => {
+                let mut response = ws_response(200);
+                if requests_protocol(&event) {
+                    response.headers.insert(
+                        "Sec-WebSocket-Protocol",
+                        http::HeaderValue::from_static(PROTOCOL),
+                    );
+                }
+                response
+            }
+            Ok(()) => ws_response(200),
+            Err(e) => {
+                tracing::warn!(connection_id, error = %e, "WebSocket event failed");
+                ws_response(500)
+            }
+        }
+    }
+
+    /// Publish an event to every subscription on `topic`. Returns the
+    /// number of messages delivered.
+    ///
+    /// Each subscription is evaluated in the context of its own
+    /// connection's identity. Connections that have gone away are removed
+    /// from the store.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the payload cannot be serialized or storage or
+    /// delivery fails
+    pub async fn publish<T: Serialize>(&self, topic: &str, payload: &T) -> DomainResult<usize> {
+        let event = SubscriptionEvent {
+            topic: topic.to_string(),
+            payload: serde_json::to_value(payload)
+                .map_err(|e| DomainError::Internal(format!("Invalid event payload: {}", e)))?,
+        };
+
+        let mut connections: HashMap<String, Option<WsConnection>> = HashMap::new();
+        let mut delivered = 0;
+        for subscription in self.store.subscriptions_for_topic(topic).await? {
+            let connection_id = subscription.connection_id.clone();
+            if !connections.contains_key(&connection_id) {
+                let connection = self.store.find_connection(&connection_id).await?;
+                connections.insert(connection_id.clone(), connection);
+            }
+            let Some(connection) = connections.get(&connection_id).cloned().flatten() else {
+                continue;
+            };
+
+            let data = match (self.context)(connection.identity()).await {
+                Ok(data) => data,
+                Err(e) => {
+                    tracing::warn!(connection_id, error = %e, "Failed to build subscription context");
+                    continue;
+                }
+            };
+            let request = subscription_request(&subscription, connection, data).data(event.clone());
+            let mut stream = self.executor.execute_stream(request, None);
+            while let Some(response) = stream.next().await {
+                let message = ServerMessage::Next {
+                    id: &subscription.id,
+                    payload: &response,
+                };
+                if self.send(&connection_id, &message).await? {
+                    delivered += 1;
+                } else {
+                    self.store.remove_connection(&connection_id).await?;
+                    connections.insert(connection_id.clone(), None);
+                    break;
+                }
+            }
+        }
+        Ok(delivered)
+    }
+
+    async fn on_message(&self, connection_id: &str, body: &str) -> DomainResult<()> {
+        let message = match serde_json::from_str::<ClientMessage>(body) {
+            Ok(message) => message,
+            Err(e) => {
+                tracing::debug!(connection_id, error = %e, "Invalid WebSocket message");
+                return self.connections.close(connection_id).await;
+            }
+        };
+
+        match message {
+            ClientMessage::ConnectionInit { payload } => {
+                let identity = match &self.on_init {
+                    Some(on_init) => match on_init(payload).await {
+                        Ok(identity) => identity,
+                        Err(e) => {
+                            tracing::debug!(connection_id, error = %e, "Connection rejected");
+                            return self.connections.close(connection_id).await;
+                        }
+                    },
+                    None => JwtResult::empty(),
+                };
+                self.store.acknowledge(connection_id, &identity).await?;
+                self.send(connection_id, &ServerMessage::ConnectionAck)
+                    .await?;
+            }
+            ClientMessage::Ping {} => {
+                self.send(connection_id, &ServerMessage::Pong).await?;
+            }
+            ClientMessage::Pong {} => {}
+            ClientMessage::Subscribe { id, payload } => {
+                self.on_subscribe(connection_id, &id, *payload).await?;
+            }
+            ClientMessage::Complete { id } => {
+                self.store.remove_subscription(connection_id, &id).await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn on_subscribe(
+        &self,
+        connection_id: &str,
+        id: &str,
+        request: Request,
+    ) -> DomainResult<()> {
+        let Some(connection) = self
+            .store
+            .find_connection(connection_id)
+            .await?
+            .filter(|c| c.acknowledged)
+        else {
+            // Subscribing before connection_init is a protocol violation
+            return self.connections.close(connection_id).await;
+        };
+
+        let document = match parse_query(&request.query) {
+            Ok(document) => document,
+            Err(e) => {
+                let errors = [ServerError::from(e)];
+                let message = ServerMessage::Error {
+                    id,
+                    payload: &errors,
+                };
+                return self.send(connection_id, &message).await.map(drop);
+            }
+        };
+        let Some((operation_type, topic)) =
+            root_field(&document, request.operation_name.as_deref())
+        else {
+            let errors = [ServerError::new("Unknown operation", None)];
+            let message = ServerMessage::Error {
+                id,
+                payload: &errors,
+            };
+            return self.send(connection_id, &message).await.map(drop);
+        };
+
+        let data = match (self.context)(connection.identity()).await {
+            Ok(data) => data,
+            Err(e) => {
+                let errors = [gql_from_domain(e).into_server_error(Default::default())];
+                let message = ServerMessage::Error {
+                    id,
+                    payload: &errors,
+                };
+                return self.send(connection_id, &message).await.map(drop);
+            }
+        };
+
+        // Queries and mutations are answered once
+        if operation_type != OperationType::Subscription {
+            let response = self
+                .executor
+                .execute(request.data(connection).data(data))
+                .await;
+            if response.data == async_graphql::Value::Null && response.is_err() {
+                let message = ServerMessage::Error {
+                    id,
+                    payload: &response.errors,
+                };
+                return self.send(connection_id, &message).await.map(drop);
+            }
+            let next = ServerMessage::Next {
+                id,
+                payload: &response,
+            };
+            if self.send(connection_id, &next).await? {
+                self.send(connection_id, &ServerMessage::Complete { id })
+                    .await?;
+            }
+            return Ok(());
+        }
+
+        let subscription = WsSubscription {
+            connection_id: connection_id.to_string(),
+            id: id.to_string(),
+            topic,
+            query: request.query,
+            variables: serde_json::to_value(&request.variables)
+                .map_err(|e| DomainError::Internal(format!("Invalid variables: {}", e)))?,
+            operation_name: request.operation_name,
+            created_at: Utc::now(),
+        };
+
+        // Dry run without an event to surface validation and resolver errors
+        let mut stream = self
+            .executor
+            .execute_stream(subscription_request(&subscription, connection, data), None);
+        if let Some(response) = stream.next().await {
+            if response.is_err() {
+                let message = ServerMessage::Error {
+                    id,
+                    payload: &response.errors,
+                };
+                return self.send(connection_id, &message).await.map(drop);
+            }
+        }
+
+        if !self.store.add_subscription(subscription).await? {
+            // Duplicate operation ID
+            return self.connections.close(connection_id).await;
+        }
+        Ok(())
+    }
+
+    async fn send(&self, connection_id: &str, message: &ServerMessage<'_>) -> DomainResult<bool> {
+        let text = serde_json::to_string(message)
+            .map_err(|e| DomainError::Internal(format!("Failed to encode message: {}", e)))?;
+        self.connections.post(connection_id, &text).await
+    }
+}
+
+fn subscription_request(
+    subscription: &WsSubscription,
+    connection: WsConnection,
+    data: ContextData,
+) -> Request {
+    let mut request = Request::new(subscription.query.clone())
+        .variables(Variables::from_json(subscription.variables.clone()))
+        .data(connection)
+        .data(data);
+    if let Some(operation_name) = &subscription.operation_name {
+        request = request.operation_name(operation_name.clone());
+    }
+    request
+}
+
+fn ws_response(status_code: i64) -> ApiGatewayProxyResponse {
+    let mut response = ApiGatewayProxyResponse::default();
+    response.status_code = status_code;
+    response
+}
+
+/// Check whether the client offered the `graphql-transport-ws` subprotocol.
+fn requests_protocol(event: &ApiGatewayWebsocketProxyRequest) -> bool {
+    event
+        .headers
+        .get("Sec-WebSocket-Protocol")
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.split(',').any(|p| p.trim() == PROTOCOL))
+}
+
+/// Find the selected operation's type and the name of its first root field.
+fn root_field(
+    document: &ExecutableDocument,
+    operation_name: Option<&str>,
+) -> Option<(OperationType, String)> {
+    // The document is not validated yet, so `visiting` stops on cyclic
+    // fragment spreads
+    fn first_field<'a>(
+        document: &'a ExecutableDocument,
+        set: &'a SelectionSet,
+        visiting: &mut HashSet<&'a str>,
+    ) -> Option<String> {
+        set.items
+            .iter()
+            .find_map(|selection| match &selection.node {
+                Selection::Field(field) => Some(field.node.name.node.to_string()),
+                Selection::InlineFragment(fragment) => {
+                    first_field(document, &fragment.node.selection_set.node, visiting)
+                }
+                Selection::FragmentSpread(spread) => {
+                    let name = spread.node.fragment_name.node.as_str();
+                    let fragment = document.fragments.get(name)?;
+                    if !visiting.insert(name) {
+                        return None;
+                    }
+                    let field = first_field(document, &fragment.node.selection_set.node, visiting);
+                    visiting.remove(name);
+                    field
+                }
+            })
+    }
+
+    let operation = match (&document.operations, operation_name) {
+        (DocumentOperations::Single(operation), _) => operation,
+        (DocumentOperations::Multiple(operations), Some(name)) => operations
+            .iter()
+            .find(|(n, _)| n.as_str() == name)
+            .map(|(_, operation)| operation)?,
+        (DocumentOperations::Multiple(_), None) => return None,
+    };
+    let field = first_field(
+        document,
+        &operation.node.selection_set.node,
+        &mut HashSet::new(),
+    )?;
+    Some((operation.node.ty, field))
+}
+
+/// In-process connection manager recording sent messages, for tests and
+/// local development.
+#[derive(Debug, Default)]
+pub struct MemoryConnectionManager {
+    messages: Mutex<HashMap<String, Vec<String>>>,
+    closed: Mutex<HashSet<String>>,
+}
+
+impl MemoryConnectionManager {
+    /// Create a manager with no connections.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the messages sent to a connection.
+    pub fn messages(&self, connection_id: &str) -> Vec<String> {
+        self.messages
+            .lock()
+            .map(|m| m.get(connection_id).cloned().unwrap_or_default())
+            .unwrap_or_default()
+    }
+
+    /// Check whether a connection has been closed.
+    pub fn is_closed(&self, connection_id: &str) -> bool {
+        self.closed
+            .lock()
+            .map(|c| c.contains(connection_id))
+            .unwrap_or_default()
+    }
+
+    fn lock_closed(&self) -> DomainResult<std::sync::MutexGuard<'_, HashSet<String>>> {
+        self.closed
+            .lock()
+            .map_err(|_| DomainError::Internal("Connection manager lock poisoned".into()))
+    }
+}
+
+#[async_trait::async_trait]
+impl ConnectionManager for MemoryConnectionManager {
+    async fn post(&self, connection_id: &str, message: &str) -> DomainResult<bool> {
+        if self.lock_closed()?.contains(connection_id) {
+            return Ok(false);
+        }
+        self.messages
+            .lock()
+            .map_err(|_| DomainError::Internal("Connection manager lock poisoned".into()))?
+            .entry(connection_id.to_string())
+            .or_default()
+            .push(message.to_string());
+        Ok(true)
+    }
+
+    async fn close(&self, connection_id: &str) -> DomainResult<()> {
+        self.lock_closed()?.insert(connection_id.to_string());
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default)]
+struct MemoryState {
+    connections: HashMap<String, WsConnection>,
+    subscriptions: HashMap<(String, String), WsSubscription>,
+}
+
+/// In-process subscription store.
+///
+/// Subscriptions are lost on cold start and not shared between Lambda
+/// instances; use [`SeaOrmSubscriptionStore`] in production.
+#[derive(Debug, Default)]
+pub struct MemorySubscriptionStore {
+    state: Mutex<MemoryState>,
+}
+
+impl MemorySubscriptionStore {
+    /// Create an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn lock(&self) -> DomainResult<std::sync::MutexGuard<'_, MemoryState>> {
+        self.state
+            .lock()
+            .map_err(|_| DomainError::Internal("Subscription store lock poisoned".into()))
+    }
+}
+
+#[async_trait::async_trait]
+impl SubscriptionStore for MemorySubscriptionStore {
+    async fn add_connection(&self, connection: WsConnection) -> DomainResult<()> {
+        self.lock()?
+            .connections
+            .insert(connection.connection_id.clone(), connection);
+        Ok(())
+    }
+
+    async fn find_connection(&self, connection_id: &str) -> DomainResult<Option<WsConnection>> {
+        Ok(self.lock()?.connections.get(connection_id).cloned())
+    }
+
+    async fn acknowledge(&self, connection_id: &str, identity: &JwtResult) -> DomainResult<()> {
+        if let Some(connection) = self.lock()?.connections.get_mut(connection_id) {
+            connection.acknowledged = true;
+            connection.user_id = identity.user_id.clone();
+            connection.tenant = identity.tenant.clone();
+            connection.role = identity.role.as_ref().map(|r| r.role_name().to_string());
+        }
+        Ok(())
+    }
+
+    async fn remove_connection(&self, connection_id: &str) -> DomainResult<()> {
+        let mut state = self.lock()?;
+        state.connections.remove(connection_id);
+        state
+            .subscriptions
+            .retain(|(connection, _), _| connection != connection_id);
+        Ok(())
+    }
+
+    async fn add_subscription(&self, subscription: WsSubscription) -> DomainResult<bool> {
+        let key = (subscription.connection_id.clone(), subscription.id.clone());
+        let mut state = self.lock()?;
+        if state.subscriptions.contains_key(&key) {
+            return Ok(false);
+        }
+        state.subscriptions.insert(key, subscription);
+        Ok(true)
+    }
+
+    async fn remove_subscription(&self, connection_id: &str, id: &str) -> DomainResult<()> {
+        self.lock()?
+            .subscriptions
+            .remove(&(connection_id.to_string(), id.to_string()));
+        Ok(())
+    }
+
+    async fn subscriptions_for_topic(&self, topic: &str) -> DomainResult<Vec<WsSubscription>> {
+        Ok(self
+            .lock()?
+            .subscriptions
+            .values()
+            .filter(|s| s.topic == topic)
+            .cloned()
+            .collect())
+    }
+}
+
+/// SeaORM-backed subscription store using the `ws_connections` and
+/// `ws_subscriptions` tables.
+///
+/// Add [`migration::Migration`] to your migrator to create the tables.
+#[derive(Debug, Clone)]
+pub struct SeaOrmSubscriptionStore {
+    db: DatabaseConnection,
+}
+
+impl SeaOrmSubscriptionStore {
+    /// Create a store using the given connection.
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self { db }
+    }
+}
+
+impl From<entity::connection::Model> for WsConnection {
+    fn from(m: entity::connection::Model) -> Self {
+        Self {
+            connection_id: m.connection_id,
+            connected_at: m.connected_at,
+            acknowledged: m.acknowledged,
+            user_id: m.user_id,
+            tenant: m.tenant,
+            role: m.role,
+        }
+    }
+}
+
+impl From<entity::subscription::Model> for WsSubscription {
+    fn from(m: entity::subscription::Model) -> Self {
+        Self {
+            connection_id: m.connection_id,
+            id: m.id,
+            topic: m.topic,
+            query: m.query,
+            variables: m.variables,
+            operation_name: m.operation_name,
+            created_at: m.created_at,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl SubscriptionStore for SeaOrmSubscriptionStore {
+    async fn add_connection(&self, connection: WsConnection) -> DomainResult<()> {
+        let model = entity::connection::ActiveModel {
+            connection_id: Set(connection.connection_id),
+            connected_at: Set(connection.connected_at),
+            acknowledged: Set(connection.acknowledged),
+            user_id: Set(connection.user_id),
+            tenant: Set(connection.tenant),
+            role: Set(connection.role),
+        };
+        entity::connection::Entity::insert(model)
+            .exec_without_returning(&self.db)
+            .await?;
+        Ok(())
+    }
+
+    async fn find_connection(&self, connection_id: &str) -> DomainResult<Option<WsConnection>> {
+        Ok(
+            entity::connection::Entity::find_by_id(connection_id.to_string())
+                .one(&self.db)
+                .await?
+                .map(WsConnection::from),
+        )
+    }
+
+    async fn acknowledge(&self, connection_id: &str, identity: &JwtResult) -> DomainResult<()> {
+        let role = identity.role.as_ref().map(|r| r.role_name().to_string());
+        entity::connection::Entity::update_many()
+            .col_expr(entity::connection::Column::Acknowledged, true.into())
+            .col_expr(
+                entity::connection::Column::UserId,
+                identity.user_id.clone().into(),
+            )
+            .col_expr(
+                entity::connection::Column::Tenant,
+                identity.tenant.clone().into(),
+            )
+            .col_expr(entity::connection::Column::Role, role.into())
+            .filter(entity::connection::Column::ConnectionId.eq(connection_id))
+            .exec(&self.db)
+            .await?;
+        Ok(())
+    }
+
+    async fn remove_connection(&self, connection_id: &str) -> DomainResult<()> {
+        entity::subscription::Entity::delete_many()
+            .filter(entity::subscription::Column::ConnectionId.eq(connection_id))
+            .exec(&self.db)
+            .await?;
+        entity::connection::Entity::delete_by_id(connection_id.to_string())
+            .exec(&self.db)
+            .await?;
+        Ok(())
+    }
+
+    async fn add_subscription(&self, subscription: WsSubscription) -> DomainResult<bool> {
+        let model = entity::subscription::ActiveModel {
+            connection_id: Set(subscription.connection_id),
+            id: Set(subscription.id),
+            topic: Set(subscription.topic),
+            query: Set(subscription.query),
+            variables: Set(subscription.variables),
+            operation_name: Set(subscription.operation_name),
+            created_at: Set(subscription.created_at),
+        };
+        match entity::subscription::Entity::insert(model)
+            .exec_without_returning(&self.db)
+            .await
+        {
+            Ok(_) => Ok(true),
+            Err(e) if matches!(e.sql_err(), Some(SqlErr::UniqueConstraintViolation(_))) => {
+                Ok(false)
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn remove_subscription(&self, connection_id: &str, id: &str) -> DomainResult<()> {
+        entity::subscription::Entity::delete_many()
+            .filter(entity::subscription::Column::ConnectionId.eq(connection_id))
+            .filter(entity::subscription::Column::Id.eq(id))
+            .exec(&self.db)
+            .await?;
+        Ok(())
+    }
+
+    async fn subscriptions_for_topic(&self, topic: &str) -> DomainResult<Vec<WsSubscription>> {
+        Ok(entity::subscription::Entity::find()
+            .filter(entity::subscription::Column::Topic.eq(topic))
+            .all(&self.db)
+            .await?
+            .into_iter()
+            .map(WsSubscription::from)
+            .collect())
+    }
+}
+
+/// SeaORM entities for the `ws_connections` and `ws_subscriptions` tables.
+pub mod entity {
+    /// The `ws_connections` table.
+    pub mod connection {
+        use sea_orm::entity::prelude::*;
+
+        /// An open WebSocket connection.
+        #[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+        #[sea_orm(table_name = "ws_connections")]
+        pub struct Model {
+            /// API Gateway connection ID
+            #[sea_orm(primary_key, auto_increment = false)]
+            pub connection_id: String,
+            /// When the client connected
+            pub connected_at: DateTimeUtc,
+            /// Whether `connection_init` has been accepted
+            pub acknowledged: bool,
+            /// Authenticated user ID
+            pub user_id: Option<String>,
+            /// Tenant of the authenticated user
+            pub tenant: Option<String>,
+            /// Role name of the authenticated user
+            pub role: Option<String>,
+        }
+
+        /// No relations.
+        #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+        pub enum Relation {}
+
+        impl ActiveModelBehavior for ActiveModel {}
+    }
+
+    /// The `ws_subscriptions` table.
+    pub mod subscription {
+        use sea_orm::entity::prelude::*;
+
+        /// A registered subscription.
+        #[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+        #[sea_orm(table_name = "ws_subscriptions")]
+        pub struct Model {
+            /// Connection the subscription belongs to
+            #[sea_orm(primary_key, auto_increment = false)]
+            pub connection_id: String,
+            /// Operation ID chosen by the client
+            #[sea_orm(primary_key, auto_increment = false)]
+            pub id: String,
+            /// Root field name
+            pub topic: String,
+            /// Subscription document
+            #[sea_orm(column_type = "Text")]
+            pub query: String,
+            /// Variables
+            pub variables: Json,
+            /// Operation name
+            pub operation_name: Option<String>,
+            /// When the subscription was registered
+            pub created_at: DateTimeUtc,
+        }
+
+        /// No relations.
+        #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+        pub enum Relation {}
+
+        impl ActiveModelBehavior for ActiveModel {}
+    }
+}
+
+/// Migration creating the `ws_connections` and `ws_subscriptions` tables.
+pub mod migration {
+    use sea_orm_migration::prelude::*;
+
+    /// Creates the `ws_connections` and `ws_subscriptions` tables.
+    pub struct Migration;
+
+    impl MigrationName for Migration {
+        fn name(&self) -> &str {
+            "m20261018_000009_create_ws_subscriptions_tables"
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl MigrationTrait for Migration {
+        async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+            manager
+                .create_table(
+                    Table::create()
+                        .table(WsConnections::Table)
+                        .if_not_exists()
+                        .col(
+                            ColumnDef::new(WsConnections::ConnectionId)
+                                .string_len(128)
+                                .not_null()
+                                .primary_key(),
+                        )
+                        .col(
+                            ColumnDef::new(WsConnections::ConnectedAt)
+                                .timestamp()
+                                .not_null(),
+                        )
+                        .col(
+                            ColumnDef::new(WsConnections::Acknowledged)
+                                .boolean()
+                                .not_null(),
+                        )
+                        .col(ColumnDef::new(WsConnections::UserId).string_len(255).null())
+                        .col(ColumnDef::new(WsConnections::Tenant).string_len(255).null())
+                        .col(ColumnDef::new(WsConnections::Role).string_len(64).null())
+                        .to_owned(),
+                )
+                .await?;
+
+            manager
+                .create_table(
+                    Table::create()
+                        .table(WsSubscriptions::Table)
+                        .if_not_exists()
+                        .col(
+                            ColumnDef::new(WsSubscriptions::ConnectionId)
+                                .string_len(128)
+                                .not_null(),
+                        )
+                        .col(
+                            ColumnDef::new(WsSubscriptions::Id)
+                                .string_len(255)
+                                .not_null(),
+                        )
+                        .col(
+                            ColumnDef::new(WsSubscriptions::Topic)
+                                .string_len(255)
+                                .not_null(),
+                        )
+                        .col(ColumnDef::new(WsSubscriptions::Query).text().not_null())
+                        .col(ColumnDef::new(WsSubscriptions::Variables).json().not_null())
+                        .col(
+                            ColumnDef::new(WsSubscriptions::OperationName)
+                                .string_len(255)
+                                .null(),
+                        )
+                        .col(
+                            ColumnDef::new(WsSubscriptions::CreatedAt)
+                                .timestamp()
+                                .not_null(),
+                        )
+                        .primary_key(
+                            Index::create()
+                                .col(WsSubscriptions::ConnectionId)
+                                .col(WsSubscriptions::Id),
+                        )
+                        .to_owned(),
+                )
+                .await?;
+
+            manager
+                .create_index(
+                    Index::create()
+                        .name("idx_ws_subscriptions_topic")
+                        .table(WsSubscriptions::Table)
+                        .col(WsSubscriptions::Topic)
+                        .to_owned(),
+                )
+                .await
+        }
+
+        async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+            manager
+                .drop_table(Table::drop().table(WsSubscriptions::Table).to_owned())
+                .await?;
+            manager
+                .drop_table(Table::drop().table(WsConnections::Table).to_owned())
+                .await
+        }
+    }
+
+    #[derive(Iden)]
+    enum WsConnections {
+        Table,
+        ConnectionId,
+        ConnectedAt,
+        Acknowledged,
+        UserId,
+        Tenant,
+        Role,
+    }
+
+    #[derive(Iden)]
+    enum WsSubscriptions {
+        Table,
+        ConnectionId,
+        Id,
+        Topic,
+        Query,
+        Variables,
+        OperationName,
+        CreatedAt,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graphql::guards::RequireAuth;
+    use crate::graphql::context::TenantInfo;
+    use async_graphql::{EmptyMutation, Object, Schema, SimpleObject, Subscription};
+    use futures::future::ready;
+    use sea_orm::DatabaseConnection;
+    use serde_json::json;
+
+    #[derive(Debug, Clone, SimpleObject, Serialize, Deserialize)]
+    struct Task {
+        id: i64,
+        project_id: i64,
+    }
+
+    #[derive(Debug, Clone, SimpleObject, Serialize, Deserialize)]
+    struct Note {
+        id: i64,
+        owner: String,
+    }
+
+    struct Query;
+
+    #[Object]
+    impl Query {
+        async fn hello(&self) -> &str {
+            "world"
+        }
+
+        async fn me(&self, ctx: &Context<'_>) -> Option<String> {
+            ctx.data_unchecked::<ContextData>().user.clone()
+        }
+    }
+
+    struct Subscription;
+
+    #[Subscription]
+    impl Subscription {
+        async fn task_updated(
+            &self,
+            ctx: &Context<'_>,
+            project_id: i64,
+        ) -> impl Stream<Item = Task> {
+            subscription_event::<Task>(ctx).filter(move |t| ready(t.project_id == project_id))
+        }
+
+        #[graphql(guard = "RequireAuth")]
+        async fn my_notes(&self, ctx: &Context<'_>) -> impl Stream<Item = Note> {
+            let user = ctx.data_unchecked::<ContextData>().user.clone();
+            subscription_event::<Note>(ctx).filter(move |n| ready(Some(&n.owner) == user.as_ref()))
+        }
+    }
+
+    type TestSchema = Schema<Query, EmptyMutation, Subscription>;
+
+    struct Harness {
+        handler: WebSocketHandler<TestSchema>,
+        store: Arc<MemorySubscriptionStore>,
+        connections: Arc<MemoryConnectionManager>,
+    }
+
+    fn context(identity: JwtResult) -> DomainResult<ContextData> {
+        Ok(ContextData::new(
+            DatabaseConnection::Disconnected,
+            identity.user_id,
+            identity.role,
+            identity.tenant.map(|t| TenantInfo::new(t, None, 1)),
+            #[cfg(feature = "admin-override")]
+            None,
+        ))
+    }
+
+    /// The token is the user ID in these tests
+    async fn authenticate(payload: Option<Value>) -> DomainResult<JwtResult> {
+        let token = payload
+            .as_ref()
+            .and_then(|p| p.get("token"))
+            .and_then(|t| t.as_str())
+            .ok_or(DomainError::Unauthorized)?;
+        Ok(JwtResult {
+            user_id: Some(token.to_string()),
+            tenant: Some("acme".to_string()),
+            role: Some(AuthRole::User(token.parse().unwrap_or_default())),
+            ..JwtResult::empty()
+        })
+    }
+
+    fn handler(
+        store: Arc<MemorySubscriptionStore>,
+        connections: Arc<MemoryConnectionManager>,
+    ) -> WebSocketHandler<TestSchema> {
+        let schema = Schema::new(Query, EmptyMutation, Subscription);
+        WebSocketHandler::new(schema, store, connections, |identity| {
+            ready(context(identity))
+        })
+    }
+
+    fn harness() -> Harness {
+        let store = Arc::new(MemorySubscriptionStore::new());
+        let connections = Arc::new(MemoryConnectionManager::new());
+        let handler =
+            handler(store.clone(), connections.clone()).with_connection_init(authenticate);
+        Harness {
+            handler,
+            store,
+            connections,
+        }
+    }
+
+    fn event(
+        connection_id: &str,
+        event_type: &str,
+        body: Option<Value>,
+    ) -> ApiGatewayWebsocketProxyRequest {
+        serde_json::from_value(json!({
+            "headers": { "Sec-WebSocket-Protocol": PROTOCOL },
+            "requestContext": {
+                "connectionId": connection_id,
+                "eventType": event_type,
+                "connectedAt": 1_700_000_000_000i64,
+                "requestTimeEpoch": 1_700_000_000_000i64,
+            },
+            "body": body.map(|b| b.to_string()),
+        }))
+        .unwrap()
+    }
+
+    impl Harness {
+        async fn send(&self, connection_id: &str, body: Value) -> i64 {
+            self.handler
+                .handle(event(connection_id, "MESSAGE", Some(body)))
+                .await
+                .status_code
+        }
+
+        async fn connect(&self, connection_id: &str) {
+            self.connect_as(connection_id, "1").await;
+        }
+
+        async fn connect_as(&self, connection_id: &str, user: &str) {
+            let response = self
+                .handler
+                .handle(event(connection_id, "CONNECT", None))
+                .await;
+            assert_eq!(response.status_code, 200);
+            assert_eq!(response.headers["Sec-WebSocket-Protocol"], PROTOCOL);
+            self.send(
+                connection_id,
+                json!({ "type": "connection_init", "payload": { "token": user } }),
+            )
+            .await;
+        }
+
+        fn messages(&self, connection_id: &str) -> Vec<Value> {
+            self.connections
+                .messages(connection_id)
+                .iter()
+                .map(|m| serde_json::from_str(m).unwrap())
+                .collect()
+        }
+    }
+
+    const TASK_UPDATED: &str = "subscription ($p: Int!) { taskUpdated(projectId: $p) { id } }";
+
+    #[tokio::test]
+    async fn test_subscribe_publish_complete() {
+        let h = harness();
+        h.connect("c1").await;
+        assert_eq!(h.messages("c1"), vec![json!({ "type": "connection_ack" })]);
+
+        h.send(
+            "c1",
+            json!({ "type": "subscribe", "id": "s1", "payload": { "query": TASK_UPDATED, "variables": { "p": 7 } } }),
+        )
+        .await;
+        let stored = h
+            .store
+            .subscriptions_for_topic("taskUpdated")
+            .await
+            .unwrap();
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].variables, json!({ "p": 7 }));
+
+        // Only events matching the subscription's arguments are delivered
+        let delivered = h
+            .handler
+            .publish(
+                "taskUpdated",
+                &Task {
+                    id: 1,
+                    project_id: 7,
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(delivered, 1);
+        h.handler
+            .publish(
+                "taskUpdated",
+                &Task {
+                    id: 2,
+                    project_id: 8,
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            h.messages("c1").last().unwrap(),
+            &json!({ "type": "next", "id": "s1", "payload": { "data": { "taskUpdated": { "id": 1 } } } })
+        );
+        assert_eq!(h.messages("c1").len(), 2);
+
+        h.send("c1", json!({ "type": "ping" })).await;
+        assert_eq!(h.messages("c1").last().unwrap(), &json!({ "type": "pong" }));
+
+        h.send("c1", json!({ "type": "complete", "id": "s1" }))
+            .await;
+        assert_eq!(
+            h.handler
+                .publish(
+                    "taskUpdated",
+                    &Task {
+                        id: 3,
+                        project_id: 7
+                    }
+                )
+                .await
+                .unwrap(),
+            0
+        );
+
+        h.handler.handle(event("c1", "DISCONNECT", None)).await;
+        assert!(h.store.find_connection("c1").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_protocol_errors() {
+        let h = harness();
+
+        // connection_init without a token is rejected
+        h.handler.handle(event("c1", "CONNECT", None)).await;
+        h.send("c1", json!({ "type": "connection_init" })).await;
+        assert!(h.connections.is_closed("c1"));
+
+        // Subscribing before connection_init closes the connection
+        h.handler.handle(event("c2", "CONNECT", None)).await;
+        h.send(
+            "c2",
+            json!({ "type": "subscribe", "id": "s1", "payload": { "query": TASK_UPDATED } }),
+        )
+        .await;
+        assert!(h.connections.is_closed("c2"));
+
+        // Invalid documents are reported and not stored
+        h.connect("c3").await;
+        h.send(
+            "c3",
+            json!({ "type": "subscribe", "id": "s1", "payload": { "query": "subscription { nope }" } }),
+        )
+        .await;
+        let error = h.messages("c3").pop().unwrap();
+        assert_eq!(error["type"], "error");
+        assert_eq!(error["id"], "s1");
+        assert!(h
+            .store
+            .subscriptions_for_topic("nope")
+            .await
+            .unwrap()
+            .is_empty());
+
+        // Cyclic fragments are reported instead of overflowing the stack
+        h.send(
+            "c3",
+            json!({ "type": "subscribe", "id": "s2", "payload": {
+                "query": "subscription { ...F } fragment F on Subscription { ...F }"
+            } }),
+        )
+        .await;
+        let error = h.messages("c3").pop().unwrap();
+        assert_eq!(error["type"], "error");
+        assert_eq!(error["id"], "s2");
+
+        // Queries are answered once
+        h.send(
+            "c3",
+            json!({ "type": "subscribe", "id": "q1", "payload": { "query": "{ hello }" } }),
+        )
+        .await;
+        let messages = h.messages("c3");
+        assert_eq!(
+            messages[messages.len() - 2..],
+            [
+                json!({ "type": "next", "id": "q1", "payload": { "data": { "hello": "world" } } }),
+                json!({ "type": "complete", "id": "q1" }),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_operations_run_as_the_connection_identity() {
+        let h = harness();
+        h.connect_as("c1", "1").await;
+        h.connect_as("c2", "2").await;
+
+        // Only the identity is stored, never the connection_init payload
+        let connection = h.store.find_connection("c1").await.unwrap().unwrap();
+        assert_eq!(connection.user_id.as_deref(), Some("1"));
+        assert_eq!(connection.tenant.as_deref(), Some("acme"));
+        assert_eq!(connection.role.as_deref(), Some("user"));
+        assert_eq!(connection.identity().role, Some(AuthRole::User(1)));
+
+        h.send(
+            "c1",
+            json!({ "type": "subscribe", "id": "q1", "payload": { "query": "{ me }" } }),
+        )
+        .await;
+        let messages = h.messages("c1");
+        assert_eq!(
+            messages[messages.len() - 2],
+            json!({ "type": "next", "id": "q1", "payload": { "data": { "me": "1" } } })
+        );
+
+        for connection_id in ["c1", "c2"] {
+            h.send(
+                connection_id,
+                json!({ "type": "subscribe", "id": "s1", "payload": { "query": "subscription { myNotes { id } }" } }),
+            )
+            .await;
+        }
+        let delivered = h
+            .handler
+            .publish(
+                "myNotes",
+                &Note {
+                    id: 5,
+                    owner: "2".to_string(),
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(delivered, 1);
+        assert_eq!(
+            h.messages("c2").last().unwrap(),
+            &json!({ "type": "next", "id": "s1", "payload": { "data": { "myNotes": { "id": 5 } } } })
+        );
+        assert_eq!(h.messages("c1").len(), messages.len());
+    }
+
+    #[tokio::test]
+    async fn test_anonymous_connections_fail_guards() {
+        let store = Arc::new(MemorySubscriptionStore::new());
+        let connections = Arc::new(MemoryConnectionManager::new());
+        let handler = handler(store.clone(), connections.clone());
+        handler.handle(event("c1", "CONNECT", None)).await;
+        handler
+            .handle(event(
+                "c1",
+                "MESSAGE",
+                Some(json!({ "type": "connection_init", "payload": { "token": "1" } })),
+            ))
+            .await;
+        let connection = store.find_connection("c1").await.unwrap().unwrap();
+        assert!(connection.acknowledged);
+        assert_eq!(connection.user_id, None);
+
+        handler
+            .handle(event(
+                "c1",
+                "MESSAGE",
+                Some(json!({ "type": "subscribe", "id": "s1", "payload": { "query": "subscription { myNotes { id } }" } })),
+            ))
+            .await;
+        let error: Value =
+            serde_json::from_str(connections.messages("c1").last().unwrap()).unwrap();
+        assert_eq!(error["type"], "error");
+        assert_eq!(error["payload"][0]["extensions"]["code"], "UNAUTHORIZED");
+        assert!(store
+            .subscriptions_for_topic("myNotes")
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_gone_connections_are_removed() {
+        let h = harness();
+        h.connect("c1").await;
+        h.send(
+            "c1",
+            json!({ "type": "subscribe", "id": "s1", "payload": { "query": TASK_UPDATED, "variables": { "p": 1 } } }),
+        )
+        .await;
+
+        h.connections.close("c1").await.unwrap();
+        let delivered = h
+            .handler
+            .publish(
+                "taskUpdated",
+                &Task {
+                    id: 1,
+                    project_id: 1,
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(delivered, 0);
+        assert!(h.store.find_connection("c1").await.unwrap().is_none());
+        assert!(h
+            .store
+            .subscriptions_for_topic("taskUpdated")
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -23,9 +23,10 @@ pub use cors::{cors_headers, cors_preflight};
 pub use multipart::parse_multipart;
 pub use router::{extract_tenant, extract_playground_tenant};
 
+use async_graphql::parser::types::{DocumentOperations, OperationDefinition, OperationType};
 use async_graphql::{
-    http::GraphQLPlaygroundConfig, http::playground_source, Error as GqlError, Pos,
-    Request as GraphQlRequest, Response as GraphQlResponse,
+    http::GraphQLPlaygroundConfig, http::playground_source, BatchRequest, BatchResponse,
+    Error as GqlError, Executor, Pos, Request as GraphQlRequest, Response as GraphQlResponse,
 };
 use http::StatusCode;
 use lambda_http::{Body, Error, Request, RequestExt, Response};
@@ -64,28 +65,106 @@ pub fn playground_response(api_endpoint: &str) -> Result<Response<Body>, Error>
 }
 
 /// Parse a GraphQL request from a POST body.
+///
+/// Once [`Config`](crate::config::Config) is initialized, bodies larger than
+/// `query_limits.max_body_bytes` (`max_upload_bytes` for multipart uploads)
+/// are rejected with `QUERY_TOO_COMPLEX` before parsing. Batched (array)
+/// bodies are rejected; use [`graphql_batch_request_from_post`] to accept
+/// them.
 pub async fn graphql_request_from_post(request: Request) -> anyhow::Result<GraphQlRequest> {
-    let content_type = request
+    graphql_batch_request_from_post(request)
+        .await?
+        .into_single()
+        .map_err(|e| anyhow::anyhow!("{}", e))
+}
+
+/// Parse a single or batched GraphQL request from a POST body.
+///
+/// A JSON array body (e.g. from Apollo's batch HTTP link) becomes a
+/// [`BatchRequest::Batch`]. Once [`Config`](crate::config::Config) is
+/// initialized, bodies larger than `query_limits.max_body_bytes`,
+/// multipart uploads larger than `query_limits.max_upload_bytes` and
+/// batches with more than `query_limits.max_batch_size` operations are
+/// rejected with `QUERY_TOO_COMPLEX`.
+///
+/// Batches carrying an `X-Admin-Override` header are rejected: the
+/// override authorizes a single action, but [`execute_batch`] would attach
+/// it to every operation.
+pub async fn graphql_batch_request_from_post(request: Request) -> anyhow::Result<BatchRequest> {
+    let config = crate::config::Config::try_get();
+    let is_multipart = request
         .headers()
         .get("content-type")
         .and_then(|v| v.to_str().ok())
-        .unwrap_or("");
+        .is_some_and(|v| v.starts_with("multipart/form-data"));
+
+    if is_multipart {
+        if let Some(config) = config {
+            config
+                .query_limits
+                .check_upload_size(request.body().len())?;
+        }
+        return Ok(BatchRequest::Single(parse_multipart(request).await?));
+    }
+    if let Some(config) = config {
+        config.query_limits.check_body_size(request.body().len())?;
+    }
+
+    let body = request.body();
+    let body_str = std::str::from_utf8(body).map_err(|e| anyhow::anyhow!("{}", e))?;
+    let batch: BatchRequest =
+        serde_json::from_str(body_str).map_err(|e| anyhow::anyhow!("{}", e))?;
+    if let (Some(config), BatchRequest::Batch(requests)) = (config, &batch) {
+        config.query_limits.check_batch_size(requests.len())?;
+    }
+    #[cfg(feature = "admin-override")]
+    if matches!(batch, BatchRequest::Batch(_))
+        && crate::auth::admin_override::extract_admin_override_header(&request).is_some()
+    {
+        anyhow::bail!("Batched requests cannot carry an admin override");
+    }
+    Ok(batch)
+}
 
-    if content_type.starts_with("multipart/form-data") {
-        parse_multipart(request).await
-    } else {
-        let body = request.body();
-        let body_str = std::str::from_utf8(body).map_err(|e| anyhow::anyhow!("{}", e))?;
-        serde_json::from_str(body_str).map_err(|e| anyhow::anyhow!("{}", e))
+/// Execute a single or batched request.
+///
+/// `prepare` is called for every operation, typically to attach a fresh
+/// [`ContextData`](crate::graphql::ContextData). Batched operations run
+/// concurrently and their responses keep the request order.
+pub async fn execute_batch<E: Executor>(
+    executor: &E,
+    batch: BatchRequest,
+    mut prepare: impl FnMut(GraphQlRequest) -> GraphQlRequest,
+) -> BatchResponse {
+    match batch {
+        BatchRequest::Single(request) => executor.execute(prepare(request)).await.into(),
+        BatchRequest::Batch(requests) => {
+            let requests = requests.into_iter().map(prepare).collect();
+            executor.execute_batch(BatchRequest::Batch(requests)).await
+        }
     }
 }
 
 /// Parse a GraphQL request from GET query parameters.
+///
+/// The `query` parameter may be omitted when `extensions` carries a
+/// `persistedQuery` hash (see [`crate::graphql::persisted`]).
+///
+/// Mutations are rejected: browsers send cookies on cross-site GET
+/// navigations and GET requests skip the CSRF check, so only queries may
+/// run over GET. Persisted mutations sent by hash are refused by
+/// [`PersistedQueries`](crate::graphql::persisted::PersistedQueries) once resolved.
 pub async fn graphql_request_from_get(request: Request) -> anyhow::Result<GraphQlRequest> {
     let query_params = request.query_string_parameters();
-    let query = query_params
-        .first("query")
-        .ok_or_else(|| anyhow::anyhow!("Missing query parameter"))?;
+    let extensions: Option<async_graphql::Extensions> = query_params
+        .first("extensions")
+        .and_then(|v| serde_json::from_str(v).ok());
+
+    let query = match query_params.first("query") {
+        Some(query) => query,
+        None if extensions.is_some() => "",
+        None => return Err(anyhow::anyhow!("Missing query parameter")),
+    };
 
     let variables = query_params
         .first("variables")
@@ -93,18 +172,205 @@ pub async fn graphql_request_from_get(request: Request) -> anyhow::Result<GraphQ
 
     let operation_name = query_params.first("operationName").map(|s| s.to_string());
 
-    Ok(GraphQlRequest::new(query.to_string())
+    if is_mutation(query, operation_name.as_deref()) {
+        return Err(anyhow::anyhow!("Mutations are not allowed over GET"));
+    }
+
+    let mut gql_request = GraphQlRequest::new(query.to_string())
         .variables(variables.unwrap_or_default())
-        .operation_name(operation_name.unwrap_or_default()))
+        .operation_name(operation_name.unwrap_or_default())
+        .data(GetRequest);
+    if let Some(extensions) = extensions {
+        gql_request.extensions = extensions;
+    }
+    Ok(gql_request)
+}
+
+/// Request data marking a request parsed by [`graphql_request_from_get`].
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct GetRequest;
+
+/// Whether the operation selected by `operation_name` is a mutation.
+///
+/// Without a name every operation in the document is considered. Queries
+/// that do not parse return `false` and are left to the executor to reject.
+pub(crate) fn is_mutation(query: &str, operation_name: Option<&str>) -> bool {
+    let Ok(document) = async_graphql::parser::parse_query(query) else {
+        return false;
+    };
+    let is_mutation = |op: &OperationDefinition| op.ty == OperationType::Mutation;
+    match (
+        &document.operations,
+        operation_name.filter(|name| !name.is_empty()),
+    ) {
+        (DocumentOperations::Single(op), _) => is_mutation(&op.node),
+        (DocumentOperations::Multiple(ops), Some(name)) => {
+            ops.get(name).is_some_and(|op| is_mutation(&op.node))
+        }
+        (DocumentOperations::Multiple(ops), None) => ops.values().any(|op| is_mutation(&op.node)),
+    }
 }
 
 /// Build a successful GraphQL response.
-pub fn graphql_response(response: GraphQlResponse) -> Result<Response<Body>, Error> {
+///
+/// Accepts a single [`GraphQlResponse`] or a [`BatchResponse`]; batches are
+/// returned as a JSON array. Headers added by resolvers (e.g. `Set-Cookie`
+/// from [`set_session_cookies`](crate::auth::cookie::set_session_cookies))
+/// are copied to the HTTP response.
+pub fn graphql_response(response: impl Into<BatchResponse>) -> Result<Response<Body>, Error> {
+    let response = response.into();
     let response_body = serde_json::to_string(&response).map_err(ServerError::from)?;
-    Response::builder()
+    let mut builder = Response::builder()
         .status(StatusCode::OK)
-        .header("Access-Control-Allow-Origin", "*")
+        .header("Access-Control-Allow-Origin", "*");
+    for (name, value) in response.http_headers_iter() {
+        builder = builder.header(name, value);
+    }
+    builder
         .body(Body::Text(response_body))
         .map_err(ServerError::from)
         .map_err(Error::from)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_graphql::{EmptyMutation, EmptySubscription, Object, Schema};
+
+    struct Query;
+
+    #[Object]
+    impl Query {
+        async fn hello(&self, ctx: &async_graphql::Context<'_>) -> String {
+            format!("hello {}", ctx.data_unchecked::<String>())
+        }
+    }
+
+    fn post(body: &str) -> Request {
+        http::Request::builder()
+            .method("POST")
+            .header("content-type", "application/json")
+            .body(Body::Text(body.to_string()))
+            .unwrap()
+    }
+
+    fn body_json(response: &Response<Body>) -> serde_json::Value {
+        match response.body() {
+            Body::Text(text) => serde_json::from_str(text).unwrap(),
+            _ => panic!("expected a text body"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_batch_request_round_trip() {
+        crate::auth::tests::init_test_config();
+        let schema = Schema::new(Query, EmptyMutation, EmptySubscription);
+
+        let batch = graphql_batch_request_from_post(post(
+            r#"[{"query":"{ hello }"},{"query":"{ a: hello }"}]"#,
+        ))
+        .await
+        .unwrap();
+        assert!(matches!(&batch, BatchRequest::Batch(requests) if requests.len() == 2));
+
+        let response = execute_batch(&schema, batch, |r| r.data("batch".to_string())).await;
+        let response = graphql_response(response).unwrap();
+        assert_eq!(
+            body_json(&response),
+            serde_json::json!([
+                { "data": { "hello": "hello batch" } },
+                { "data": { "a": "hello batch" } },
+            ])
+        );
+
+        // Single requests still produce a single response object
+        let single = graphql_batch_request_from_post(post(r#"{"query":"{ hello }"}"#))
+            .await
+            .unwrap();
+        let response = execute_batch(&schema, single, |r| r.data("one".to_string())).await;
+        assert_eq!(
+            body_json(&graphql_response(response).unwrap()),
+            serde_json::json!({ "data": { "hello": "hello one" } })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_multipart_uses_upload_limit() {
+        crate::auth::tests::init_test_config();
+        let limits = &crate::config::Config::get().query_limits;
+        let multipart = |file_bytes: usize| {
+            let body = format!(
+                "--b\r\nContent-Disposition: form-data; name=\"operations\"\r\n\r\n\
+                 {{\"query\":\"{{ hello }}\"}}\r\n\
+                 --b\r\nContent-Disposition: form-data; name=\"0\"; filename=\"a.bin\"\r\n\r\n\
+                 {}\r\n--b--\r\n",
+                "x".repeat(file_bytes)
+            );
+            http::Request::builder()
+                .method("POST")
+                .header("content-type", "multipart/form-data; boundary=b")
+                .body(Body::Text(body))
+                .unwrap()
+        };
+
+        // Larger than max_body_bytes, within max_upload_bytes
+        let request = graphql_request_from_post(multipart(limits.max_body_bytes + 1))
+            .await
+            .unwrap();
+        assert_eq!(request.query, "{ hello }");
+
+        let err = graphql_request_from_post(multipart(limits.max_upload_bytes))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("upload is"));
+    }
+
+    #[tokio::test]
+    async fn test_batch_size_limit() {
+        crate::auth::tests::init_test_config();
+        let max = crate::config::Config::get().query_limits.max_batch_size;
+        let body =
+            serde_json::to_string(&vec![serde_json::json!({ "query": "{ hello }" }); max + 1])
+                .unwrap();
+
+        let err = graphql_batch_request_from_post(post(&body))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("batch has"));
+
+        // The single-request parser rejects arrays
+        assert!(
+            graphql_request_from_post(post(r#"[{"query":"{ hello }"}]"#))
+                .await
+                .is_err()
+        );
+    }
+
+    #[cfg(feature = "admin-override")]
+    #[tokio::test]
+    async fn test_batch_with_admin_override_is_rejected() {
+        crate::auth::tests::init_test_config();
+        let with_override = |body: &str| {
+            let mut request = post(body);
+            request.headers_mut().insert(
+                crate::auth::admin_override::ADMIN_OVERRIDE_HEADER,
+                http::HeaderValue::from_static("token"),
+            );
+            request
+        };
+
+        let err = graphql_batch_request_from_post(with_override(
+            r#"[{"query":"{ hello }"},{"query":"{ a: hello }"}]"#,
+        ))
+        .await
+        .unwrap_err();
+        assert!(err.to_string().contains("admin override"));
+
+        // A single operation may still carry one
+        assert!(
+            graphql_batch_request_from_post(with_override(r#"{"query":"{ hello }"}"#))
+                .await
+                .is_ok()
+        );
+    }
+}
@@ -39,6 +39,7 @@
 //! - [`errors`] - Error handling with GraphQL integration
 //! - [`config`] - Configuration management
 //! - [`auth`] - JWT authentication
+//! - [`audit`] - Persisted audit log
 //! - [`validation`] - Input validation
 //! - [`db`] - Database initialization
 //! - [`graphql`] - GraphQL context and guards
@@ -50,6 +51,7 @@
 #![warn(missing_docs)]
 #![warn(rustdoc::missing_crate_level_docs)]
 
+pub mod audit;
 pub mod auth;
 pub mod config;
 pub mod db;
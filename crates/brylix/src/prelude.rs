@@ -8,27 +8,82 @@
 
 // Error handling
 pub use crate::errors::{
-    gql_bad_request, gql_error, gql_from_anyhow, gql_from_domain, gql_internal, gql_not_found,
-    gql_unauthorized, DomainError, DomainResult,
+    gql_bad_request, gql_error, gql_forbidden, gql_from_anyhow, gql_from_domain, gql_internal, gql_not_found,
+    gql_too_many_attempts, gql_unauthorized, CorrelationId, DomainError, DomainResult,
+    ErrorPolicy,
 };
 
 // Configuration
-pub use crate::config::{Config, ConfigBuilder};
+pub use crate::config::{Config, ConfigBuilder, QueryLimitsConfig};
 
 // Authentication
-pub use crate::auth::{issue_jwt, jwt_middleware, validate_jwt, Claims, JwtResult};
-pub use crate::auth::{hash_password, verify_password};
+pub use crate::auth::{
+    authenticate_token, issue_jwt, issue_jwt_with_role, issue_jwt_with_roles, jwt_middleware,
+    validate_jwt, Claims, JwtResult,
+};
+pub use crate::auth::{
+    hash_password, init_password_hashing, verify_and_rehash, verify_password,
+    PasswordHashConfig,
+};
+
+// API Keys
+pub use crate::auth::api_key::{
+    issue_api_key, revoke_api_key, verify_api_key, ApiKey, ApiKeyStore, SeaOrmApiKeyStore,
+    API_KEY_HEADER,
+};
+pub use crate::auth::jwt_middleware_with_api_keys;
+
+// Cookie sessions
+pub use crate::auth::cookie::{
+    clear_session_cookies, set_session_cookies, CookieConfig, SameSite,
+};
+pub use crate::auth::jwt_middleware_with_cookies;
 
 // Validation
 pub use crate::validation::{
     validate_email, validate_hostname, validate_name, validate_password, validate_tenant_name,
+    PasswordPolicy,
 };
 
 // GraphQL
-pub use crate::graphql::{require_auth, require_auth_user_id, ContextData};
+pub use crate::graphql::{
+    auth, require_auth, require_auth_user_id, require_not_impersonating, require_permission,
+    AnyPerm, AuthRequirement, ContextData, NotImpersonating, Perm, RequireAdmin, RequireAuth,
+    RequireOwner, SchemaBuilderExt,
+};
 
 // Pagination
-pub use crate::graphql::pagination::{page_info, Connection, IntoConnection, PageInfo};
+pub use crate::graphql::pagination::{
+    page_info, paginate, Connection, IntoConnection, PageInfo, MAX_PER_PAGE,
+};
+
+// Cursor Pagination
+pub use crate::graphql::cursor::{
+    keyset, keyset_paginate, Cursor, CursorArgs, CursorConnection, CursorPageInfo, CursorWindow,
+    Edge,
+};
+
+// Filtering & Sorting
+pub use crate::graphql::filter::{
+    ColumnFilter, DateTimeFilter, IntFilter, ListColumns, SortDirection, SortInput, StringFilter,
+};
+
+// DataLoaders
+pub use crate::graphql::loader::{
+    EntityDataLoader, EntityLoader, Loaders, RelatedDataLoader, RelatedLoader,
+};
+
+// Persisted Queries
+pub use crate::graphql::persisted::{
+    query_hash, MemoryPersistedQueryStore, OperationManifest, PersistedQueries,
+    PersistedQueryStore, SeaOrmPersistedQueryStore,
+};
+
+// Subscriptions
+pub use crate::graphql::subscriptions::{
+    subscription_event, ConnectionManager, MemoryConnectionManager, MemorySubscriptionStore,
+    SeaOrmSubscriptionStore, SubscriptionEvent, SubscriptionStore, WebSocketHandler,
+};
 
 // ID Parsing
 pub use crate::graphql::helpers::{parse_gql_id, parse_gql_id_field};
@@ -41,20 +96,87 @@ pub use crate::helpers::time::{utc_now, Timestamped};
 // Multi-role Auth
 pub use crate::auth::roles::{get_auth_role, require_admin, AuthRole, MultiRoleJwtConfig};
 
+// Permissions (RBAC)
+pub use crate::auth::permissions::{
+    DbRolePermissions, Permission, PermissionResolver, PermissionSet, RolePermissions,
+};
+
+// MFA
+pub use crate::auth::mfa::{
+    complete_mfa_login, complete_mfa_login_with_recovery_code, generate_recovery_codes,
+    generate_totp_secret, issue_mfa_pending_token, provisioning_uri, validate_mfa_pending_token,
+    verify_recovery_code, verify_totp, MemoryMfaStore, MfaStore, MfaVerifier, SeaOrmMfaStore, TotpConfig,
+};
+
+// One-time tokens (password reset, email verification)
+pub use crate::auth::tokens::{
+    consume_email_verification, consume_token, issue_email_verification_token, issue_token,
+    OneTimeTokenStore, SeaOrmOneTimeTokenStore, TokenPurpose,
+};
+#[cfg(feature = "email")]
+pub use crate::auth::tokens::{send_password_reset_email, send_verification_email};
+
+// Magic link login
+pub use crate::auth::magic_link::{issue_magic_link_token, verify_magic_link, MagicLinkConfig};
+#[cfg(feature = "email")]
+pub use crate::auth::magic_link::send_magic_link;
+
+// Login throttling
+pub use crate::auth::throttle::{
+    client_ip, AttemptStore, LoginThrottle, MemoryAttemptStore, SeaOrmAttemptStore,
+    ThrottleConfig,
+};
+
+// OAuth social login
+pub use crate::auth::oauth::{
+    authorization_request, complete_oauth_login, LinkedAccount, OAuthAccountLinker,
+    OAuthHttpClient, OAuthLogin, OAuthProvider, OAuthUserInfo, PendingAuthorization,
+};
+#[cfg(feature = "oauth")]
+pub use crate::auth::oauth::ReqwestOAuthClient;
+
+// Sessions
+pub use crate::auth::sessions::{
+    init_session_store, list_sessions, require_sessions, revoke_other_sessions, revoke_session,
+    start_session, MemorySessionStore, SeaOrmSessionStore, Session, SessionInfo, SessionStore,
+};
+
+// Impersonation
+pub use crate::auth::impersonation::{
+    end_impersonation, issue_impersonation_token, ImpersonationConfig, UserRoleLookup,
+};
+
+// Audit log
+pub use crate::audit::{
+    emit, init_audit_sink, AuditEvent, AuditSink, MemoryAuditSink, SeaOrmAuditSink,
+    TracingAuditSink,
+};
+
+// Access Policies
+pub use crate::auth::policy::{
+    init_policies, Owned, OwnerOnly, OwnerOrAdmin, Policy, PolicyRegistry,
+};
+pub use crate::authorize;
+
 // Admin Override (feature-gated)
 #[cfg(feature = "admin-override")]
 pub use crate::auth::admin_override::{
-    get_admin_override, issue_admin_override_token, require_auth_with_admin_override,
-    AdminOverride, AdminOverrideAudit, AdminOverrideConfig, ADMIN_OVERRIDE_HEADER,
+    get_admin_override, issue_admin_override_token, redeem_admin_override_token,
+    require_admin_override_for, require_auth_with_admin_override, AdminOverride,
+    AdminOverrideAudit, AdminOverrideConfig, MemoryOverrideNonceStore, OverrideNonceStore,
+    SeaOrmOverrideNonceStore, ADMIN_OVERRIDE_HEADER,
 };
+#[cfg(feature = "admin-override")]
+pub use crate::auth::admin_override_middleware;
 
 // Database
 pub use crate::db::{connect, connect_with_pool, init_db, PoolConfig};
 
 // Handler
 pub use crate::handler::{
-    cors_preflight, error_response, graphql_error, graphql_request_from_get,
-    graphql_request_from_post, graphql_response, playground_response,
+    cors_preflight, error_response, execute_batch, graphql_batch_request_from_post,
+    graphql_error, graphql_request_from_get, graphql_request_from_post, graphql_response,
+    playground_response,
 };
 
 // Provider
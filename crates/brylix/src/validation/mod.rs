@@ -6,7 +6,7 @@
 //! # Usage
 //!
 //! ```rust
-//! use brylix::validation::{validate_email, validate_password, validate_tenant_name};
+//! use brylix::validation::{validate_email, validate_password, validate_tenant_name, PasswordPolicy};
 //!
 //! // Validate email
 //! validate_email("user@example.com")?;
@@ -16,6 +16,13 @@
 //!
 //! // Validate tenant name (alphanumeric, no SQL injection)
 //! validate_tenant_name("my_tenant")?;
+//!
+//! // Configurable password rules
+//! PasswordPolicy::new()
+//!     .with_min_length(12)
+//!     .with_common_passwords()
+//!     .with_username_check(true)
+//!     .validate_for_user("SecurePass123!", "jane@example.com")?;
 //! ```
 //!
 //! # Regex Patterns
@@ -26,6 +33,8 @@
 //! - `lazy_static!` ensures each regex is compiled exactly once at first use
 //! - Invalid regex patterns represent programmer errors, not runtime conditions
 
+use std::collections::HashSet;
+
 use lazy_static::lazy_static;
 use regex::Regex;
 
@@ -86,43 +95,215 @@ pub fn validate_email(email: &str) -> Result<(), String> {
 /// - Must contain at least one number
 /// - Must contain at least one special character
 pub fn validate_password(password: &str) -> Result<(), String> {
-    if password.is_empty() {
-        return Err("Password cannot be empty".to_string());
+    PasswordPolicy::default().validate(password)
+}
+
+/// Special characters accepted by [`PasswordPolicy::require_special`].
+const SPECIAL_CHARS: &str = "!@#$%^&*()_+-=[]{}|;:,.<>?";
+
+/// Frequently used passwords rejected by [`PasswordPolicy::with_common_passwords`].
+pub const COMMON_PASSWORDS: &[&str] = &[
+    "123456", "123456789", "12345678", "password", "qwerty", "qwerty123", "1q2w3e4r",
+    "111111", "123123", "abc123", "password1", "password123", "passw0rd", "p@ssw0rd",
+    "p@ssword1", "iloveyou", "admin", "admin123", "welcome", "welcome1", "welcome123",
+    "letmein", "monkey", "dragon", "sunshine", "princess", "football", "baseball",
+    "superman", "trustno1", "changeme", "secret", "qwertyuiop", "zaq12wsx",
+];
+
+/// Configurable password rules.
+///
+/// The default policy matches [`validate_password`]: 8 to 128 characters
+/// with at least one uppercase letter, lowercase letter, number and special
+/// character.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use brylix::validation::PasswordPolicy;
+///
+/// let policy = PasswordPolicy::new()
+///     .with_min_length(12)
+///     .with_special(false)
+///     .with_common_passwords()
+///     .with_username_check(true);
+///
+/// policy.validate_for_user(&input.password, &input.email)?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct PasswordPolicy {
+    /// Minimum length in characters
+    pub min_length: usize,
+    /// Maximum length in characters
+    pub max_length: usize,
+    /// Require at least one uppercase letter
+    pub require_uppercase: bool,
+    /// Require at least one lowercase letter
+    pub require_lowercase: bool,
+    /// Require at least one number
+    pub require_digit: bool,
+    /// Require at least one special character
+    pub require_special: bool,
+    /// Rejected passwords (stored lowercase, compared case-insensitively)
+    pub denylist: HashSet<String>,
+    /// Reject passwords containing the username
+    pub reject_username: bool,
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        Self {
+            min_length: 8,
+            max_length: 128,
+            require_uppercase: true,
+            require_lowercase: true,
+            require_digit: true,
+            require_special: true,
+            denylist: HashSet::new(),
+            reject_username: false,
+        }
     }
+}
 
-    if password.len() < 8 {
-        return Err("Password must be at least 8 characters long".to_string());
+impl PasswordPolicy {
+    /// Create a policy with the default rules.
+    pub fn new() -> Self {
+        Self::default()
     }
 
-    if password.len() > 128 {
-        return Err("Password must be less than 128 characters".to_string());
+    /// Set the minimum length.
+    pub fn with_min_length(mut self, min_length: usize) -> Self {
+        self.min_length = min_length;
+        self
     }
 
-    // Check for at least one uppercase letter
-    if !password.chars().any(|c| c.is_uppercase()) {
-        return Err("Password must contain at least one uppercase letter".to_string());
+    /// Set the maximum length.
+    pub fn with_max_length(mut self, max_length: usize) -> Self {
+        self.max_length = max_length;
+        self
     }
 
-    // Check for at least one lowercase letter
-    if !password.chars().any(|c| c.is_lowercase()) {
-        return Err("Password must contain at least one lowercase letter".to_string());
+    /// Require (or not) an uppercase letter.
+    pub fn with_uppercase(mut self, required: bool) -> Self {
+        self.require_uppercase = required;
+        self
     }
 
-    // Check for at least one digit
-    if !password.chars().any(|c| c.is_numeric()) {
-        return Err("Password must contain at least one number".to_string());
+    /// Require (or not) a lowercase letter.
+    pub fn with_lowercase(mut self, required: bool) -> Self {
+        self.require_lowercase = required;
+        self
     }
 
-    // Check for at least one special character
-    let special_chars = "!@#$%^&*()_+-=[]{}|;:,.<>?";
-    if !password.chars().any(|c| special_chars.contains(c)) {
-        return Err(
-            "Password must contain at least one special character (!@#$%^&*()_+-=[]{}|;:,.<>?)"
-                .to_string(),
-        );
+    /// Require (or not) a number.
+    pub fn with_digit(mut self, required: bool) -> Self {
+        self.require_digit = required;
+        self
     }
 
-    Ok(())
+    /// Require (or not) a special character.
+    pub fn with_special(mut self, required: bool) -> Self {
+        self.require_special = required;
+        self
+    }
+
+    /// Add passwords to the denylist.
+    pub fn with_denylist<I, S>(mut self, passwords: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.denylist
+            .extend(passwords.into_iter().map(|p| p.as_ref().to_lowercase()));
+        self
+    }
+
+    /// Add the built-in [`COMMON_PASSWORDS`] to the denylist.
+    pub fn with_common_passwords(self) -> Self {
+        self.with_denylist(COMMON_PASSWORDS)
+    }
+
+    /// Reject (or not) passwords that contain the username.
+    pub fn with_username_check(mut self, enabled: bool) -> Self {
+        self.reject_username = enabled;
+        self
+    }
+
+    /// Validate a password against the policy.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` if valid, `Err(String)` with error message if invalid
+    pub fn validate(&self, password: &str) -> Result<(), String> {
+        if password.is_empty() {
+            return Err("Password cannot be empty".to_string());
+        }
+
+        let length = password.chars().count();
+        if length < self.min_length {
+            return Err(format!(
+                "Password must be at least {} characters long",
+                self.min_length
+            ));
+        }
+
+        if length > self.max_length {
+            return Err(format!(
+                "Password must be less than {} characters",
+                self.max_length
+            ));
+        }
+
+        if self.require_uppercase && !password.chars().any(|c| c.is_uppercase()) {
+            return Err("Password must contain at least one uppercase letter".to_string());
+        }
+
+        if self.require_lowercase && !password.chars().any(|c| c.is_lowercase()) {
+            return Err("Password must contain at least one lowercase letter".to_string());
+        }
+
+        if self.require_digit && !password.chars().any(|c| c.is_numeric()) {
+            return Err("Password must contain at least one number".to_string());
+        }
+
+        if self.require_special && !password.chars().any(|c| SPECIAL_CHARS.contains(c)) {
+            return Err(format!(
+                "Password must contain at least one special character ({})",
+                SPECIAL_CHARS
+            ));
+        }
+
+        if self.denylist.contains(&password.to_lowercase()) {
+            return Err("Password is too common".to_string());
+        }
+
+        Ok(())
+    }
+
+    /// Validate a password for a specific user.
+    ///
+    /// Applies [`validate`](Self::validate) and, when enabled, rejects
+    /// passwords containing the username (or the local part of an email
+    /// username). Usernames shorter than 3 characters are not checked.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` if valid, `Err(String)` with error message if invalid
+    pub fn validate_for_user(&self, password: &str, username: &str) -> Result<(), String> {
+        self.validate(password)?;
+
+        if self.reject_username {
+            let name = username
+                .split('@')
+                .next()
+                .unwrap_or(username)
+                .to_lowercase();
+            if name.chars().count() >= 3 && password.to_lowercase().contains(&name) {
+                return Err("Password must not contain your username".to_string());
+            }
+        }
+
+        Ok(())
+    }
 }
 
 /// Validate a name (FirstName, LastName, etc.).
@@ -308,6 +489,43 @@ mod tests {
         assert!(validate_password("NoSpecial123").is_err()); // No special chars
     }
 
+    #[test]
+    fn test_password_policy_custom_rules() {
+        let policy = PasswordPolicy::new()
+            .with_min_length(12)
+            .with_uppercase(false)
+            .with_special(false);
+
+        assert!(policy.validate("longlowercase1").is_ok());
+        assert!(policy.validate("short1").is_err());
+        assert!(policy.validate("nodigitsatall").is_err());
+        assert!(PasswordPolicy::new().with_max_length(10).validate("TooLong123!!").is_err());
+    }
+
+    #[test]
+    fn test_password_policy_denylist() {
+        let policy = PasswordPolicy::new()
+            .with_uppercase(false)
+            .with_special(false)
+            .with_common_passwords()
+            .with_denylist(["Acme2026"]);
+
+        assert_eq!(policy.validate("password123").unwrap_err(), "Password is too common");
+        assert!(policy.validate("acme2026").is_err());
+        assert!(policy.validate("quiet7harbor").is_ok());
+    }
+
+    #[test]
+    fn test_password_policy_username_check() {
+        let policy = PasswordPolicy::new().with_username_check(true);
+
+        assert!(policy.validate_for_user("Jane.Doe2026!", "jane.doe@example.com").is_err());
+        assert!(policy.validate_for_user("Harbor#Quiet7", "jane.doe@example.com").is_ok());
+        assert!(PasswordPolicy::new()
+            .validate_for_user("Jane.Doe2026!", "jane.doe@example.com")
+            .is_ok());
+    }
+
     #[test]
     fn test_validate_tenant_name_valid() {
         assert!(validate_tenant_name("tenant").is_ok());
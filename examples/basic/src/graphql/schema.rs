@@ -85,8 +85,12 @@ impl MutationRoot {
 }
 
 /// Build the GraphQL schema
-pub fn build_schema(db: DatabaseConnection) -> Schema<QueryRoot, MutationRoot, EmptySubscription> {
+pub fn build_schema(
+    db: DatabaseConnection,
+    limits: &QueryLimitsConfig,
+) -> Schema<QueryRoot, MutationRoot, EmptySubscription> {
     Schema::build(QueryRoot, MutationRoot, EmptySubscription)
+        .with_query_limits(limits)
         .data(ContextData::single_tenant(db, None, None))
         .finish()
 }
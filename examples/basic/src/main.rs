@@ -31,7 +31,7 @@ async fn main() -> Result<(), Error> {
         .expect("Failed to run migrations");
 
     // Build GraphQL schema with database connection
-    let schema = build_schema(db);
+    let schema = build_schema(db, &config.query_limits);
 
     // Start Lambda (simplified for example)
     println!("Starting server...");
@@ -196,6 +196,8 @@ impl MutationRoot {
 }
 
 /// Build the GraphQL schema (without data - data added per request)
-pub fn build_schema() -> Schema<QueryRoot, MutationRoot, EmptySubscription> {
-    Schema::build(QueryRoot, MutationRoot, EmptySubscription).finish()
+pub fn build_schema(limits: &QueryLimitsConfig) -> Schema<QueryRoot, MutationRoot, EmptySubscription> {
+    Schema::build(QueryRoot, MutationRoot, EmptySubscription)
+        .with_query_limits(limits)
+        .finish()
 }
@@ -26,7 +26,7 @@ async fn main() -> Result<(), Error> {
         .expect("Failed to connect to master database");
 
     // Build GraphQL schema
-    let _schema = build_schema();
+    let _schema = build_schema(&config.query_limits);
 
     // Note: Full multi-tenant handler implementation would:
     // 1. Parse tenant from URL path (/api/{tenant})